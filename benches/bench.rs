@@ -22,10 +22,55 @@ mod tests {
              - # # -
              - - - -"
                 .to_string(),
+            (0, 0),
         );
 
         b.iter(|| {
             world.simulate();
         });
     }
+
+    #[bench]
+    fn bench_simulate_large(b: &mut Bencher) {
+        let mut world = World::new(1000, 1000);
+        world.seed_random();
+
+        b.iter(|| {
+            world.simulate();
+        });
+    }
+
+    #[bench]
+    fn bench_simulate_glider(b: &mut Bencher) {
+        let mut world = World::new(1000, 1000);
+        world.seed_from_string(
+            "- # -
+             - - #
+             # # #"
+                .to_string(),
+            (0, 0),
+        );
+
+        b.iter(|| {
+            world.simulate();
+        });
+    }
+
+    #[bench]
+    fn bench_simulate_bitpacked(b: &mut Bencher) {
+        let mut world = World::new(100, 100);
+        world.seed_from_string(
+            "- - - -
+             - # # -
+             - # # -
+             - - - -"
+                .to_string(),
+            (0, 0),
+        );
+        let mut world = BitWorld::from_world(&world).unwrap();
+
+        b.iter(|| {
+            world.simulate();
+        });
+    }
 }
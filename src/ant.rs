@@ -0,0 +1,141 @@
+//! Langton's Ant, walking over a world's cells.
+
+use crate::world::World;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    fn turn_right(self) -> Self {
+        match self {
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North,
+        }
+    }
+
+    fn turn_left(self) -> Self {
+        self.turn_right().turn_right().turn_right()
+    }
+}
+
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "n" | "north" => Ok(Direction::North),
+            "e" | "east" => Ok(Direction::East),
+            "s" | "south" => Ok(Direction::South),
+            "w" | "west" => Ok(Direction::West),
+            _ => Err(format!("unknown direction: {}", s)),
+        }
+    }
+}
+
+/// An ant that turns right on dead cells and left on live ones, flipping
+/// the cell it leaves. Ants walk off one edge of the world onto the other.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ant {
+    pub x: usize,
+    pub y: usize,
+    pub direction: Direction,
+}
+
+impl Ant {
+    pub fn new(x: usize, y: usize, direction: Direction) -> Self {
+        Self { x, y, direction }
+    }
+
+    pub fn step(&mut self, world: &mut World) {
+        self.direction = if world.cell(self.x, self.y).alive {
+            self.direction.turn_left()
+        } else {
+            self.direction.turn_right()
+        };
+        world.toggle_cell(self.x, self.y);
+
+        let (width, height) = (world.width, world.height);
+        match self.direction {
+            Direction::North => self.y = (self.y + height - 1) % height,
+            Direction::East => self.x = (self.x + 1) % width,
+            Direction::South => self.y = (self.y + 1) % height,
+            Direction::West => self.x = (self.x + width - 1) % width,
+        }
+    }
+}
+
+/// Parses `X,Y` or `X,Y,DIRECTION`, where the direction is `n`, `e`, `s` or
+/// `w` and defaults to north.
+impl FromStr for Ant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(',').map(str::trim).collect();
+        let coordinate = |value: &str| {
+            value
+                .parse()
+                .map_err(|_| format!("invalid coordinate in {}: {:?}", s, value))
+        };
+
+        match parts.as_slice() {
+            [x, y] => Ok(Self::new(coordinate(x)?, coordinate(y)?, Direction::North)),
+            [x, y, direction] => Ok(Self::new(
+                coordinate(x)?,
+                coordinate(y)?,
+                direction.parse()?,
+            )),
+            _ => Err(format!("invalid ant: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step() {
+        let mut world = World::new(5, 5);
+        let mut ant = Ant::new(2, 2, Direction::North);
+
+        // On a dead cell it turns right, leaving the cell alive.
+        ant.step(&mut world);
+        assert_eq!(ant, Ant::new(3, 2, Direction::East));
+        assert!(world.cell(2, 2).alive);
+
+        // Three more right turns bring it back, to turn left off a live cell.
+        for _ in 0..3 {
+            ant.step(&mut world);
+        }
+        assert_eq!(ant, Ant::new(2, 2, Direction::North));
+        ant.step(&mut world);
+        assert_eq!(ant, Ant::new(1, 2, Direction::West));
+        assert!(!world.cell(2, 2).alive);
+        assert_eq!(world.live_cells().count(), 3);
+    }
+
+    #[test]
+    fn test_wraps_around() {
+        let mut world = World::new(3, 3);
+        let mut ant = Ant::new(2, 0, Direction::North);
+
+        ant.step(&mut world);
+        assert_eq!(ant, Ant::new(0, 0, Direction::East));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("3,4".parse(), Ok(Ant::new(3, 4, Direction::North)));
+        assert_eq!("3, 4, w".parse(), Ok(Ant::new(3, 4, Direction::West)));
+        assert!("3".parse::<Ant>().is_err());
+        assert!("3,4,up".parse::<Ant>().is_err());
+    }
+}
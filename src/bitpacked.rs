@@ -0,0 +1,383 @@
+//! A dense world packed one bit per cell, which moves sixty-four cells on a
+//! generation at a time.
+
+use crate::boundary::Boundary;
+use crate::grid::{heap_bytes, Grid};
+use crate::rule::{Neighbourhood, Rule};
+use crate::world::World;
+use std::array;
+use std::borrow::Cow;
+use std::mem;
+
+const WORD_BITS: usize = 64;
+
+/// A world of two-state cells under a Moore neighbourhood rule, stored as
+/// rows of `u64` words where bit `x % 64` of word `x / 64` is the cell at
+/// `x`. Neighbours are counted for a whole word at once by adding the eight
+/// shifted neighbouring rows together bitwise.
+#[derive(Clone, Debug)]
+pub struct BitWorld {
+    pub width: usize,
+    pub height: usize,
+    rows: Vec<Vec<u64>>,
+    /// Whether a dead or live cell with each number of live neighbours is
+    /// alive next generation.
+    births: [bool; 9],
+    survivals: [bool; 9],
+    boundary: Boundary,
+    generation: usize,
+}
+
+impl BitWorld {
+    pub fn new(width: usize, height: usize, rule: Rule) -> Result<Self, String> {
+        rule.check_two_state()?;
+
+        if rule.neighbourhood() != Neighbourhood::Moore || rule.radius() != 1 {
+            return Err(format!(
+                "{} doesn't count the eight cells around each cell",
+                rule
+            ));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            rows: vec![vec![0; words(width)]; height],
+            births: array::from_fn(|count| rule.births(count as u8)),
+            survivals: array::from_fn(|count| rule.survives(count as u8)),
+            boundary: Boundary::default(),
+            generation: 0,
+        })
+    }
+
+    /// A packed world with the same size, rule, boundary and live cells as
+    /// `world`. Regions and chance aren't carried over.
+    pub fn from_world(world: &World) -> Result<Self, String> {
+        let mut packed = Self::new(world.width, world.height, world.rule())?;
+        packed.boundary = world.boundary();
+        packed.generation = world.generation();
+        for (y, row) in packed.rows.iter_mut().enumerate() {
+            for (x, cell) in world.row(y).iter().enumerate() {
+                if cell.alive {
+                    set_bit(row, x, true);
+                }
+            }
+        }
+        Ok(packed)
+    }
+
+    pub fn set_boundary(&mut self, boundary: Boundary) {
+        self.boundary = boundary;
+    }
+
+    /// Calls `f` with each cell in row `y` of `next`, which must be the
+    /// same size, with whether it's alive and how many live neighbours it
+    /// has. Words of cells that are dead with no live neighbours in both
+    /// worlds haven't changed, so they're skipped over.
+    pub(crate) fn compare_row(
+        &self,
+        next: &BitWorld,
+        y: usize,
+        mut f: impl FnMut(usize, bool, u8),
+    ) {
+        let words = self.rows[y].iter().zip(&next.rows[y]);
+        let counts = self.count_row(y).into_iter().zip(next.count_row(y));
+        for (i, ((word, next_word), (count, next_count))) in words.zip(counts).enumerate() {
+            let busy = count
+                .iter()
+                .chain(&next_count)
+                .fold(word | next_word, |busy, bits| busy | bits);
+            if busy == 0 {
+                continue;
+            }
+
+            let [ones, twos, fours, eights] = next_count;
+            for bit in 0..WORD_BITS.min(self.width - i * WORD_BITS) {
+                let count = ones >> bit & 1
+                    | (twos >> bit & 1) << 1
+                    | (fours >> bit & 1) << 2
+                    | (eights >> bit & 1) << 3;
+                f(i * WORD_BITS + bit, next_word >> bit & 1 != 0, count as u8);
+            }
+        }
+    }
+
+    /// The live neighbours of each cell in row `y`, a word at a time, as
+    /// four words holding the bits of the counts: bit `x % 64` of the
+    /// `n`th word is bit `n` of the count for the cell at `x`.
+    fn count_row(&self, y: usize) -> Vec<[u64; 4]> {
+        let rows = [
+            self.neighbouring_row(y, -1),
+            self.neighbouring_row(y, 0),
+            self.neighbouring_row(y, 1),
+        ];
+
+        (0..words(self.width))
+            .map(|i| {
+                // The eight neighbours of each cell in the word, lined up
+                // with it.
+                let neighbours = [
+                    rows[0].shifted_east(i),
+                    rows[0].words[i],
+                    rows[0].shifted_west(i),
+                    rows[1].shifted_east(i),
+                    rows[1].shifted_west(i),
+                    rows[2].shifted_east(i),
+                    rows[2].words[i],
+                    rows[2].shifted_west(i),
+                ];
+
+                // Add them up bitwise, into four bits per cell.
+                let mut count = [0u64; 4];
+                for neighbour in &neighbours {
+                    let mut carry = *neighbour;
+                    for bit in &mut count {
+                        let overflow = *bit & carry;
+                        *bit ^= carry;
+                        carry = overflow;
+                    }
+                }
+                count
+            })
+            .collect()
+    }
+
+    pub fn live_cells_count(&self) -> usize {
+        self.rows
+            .iter()
+            .flatten()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// The row `y_offset` away from row `y`, with the cells just past its
+    /// west and east ends, as the boundary sees them from that row.
+    fn neighbouring_row(&self, y: usize, y_offset: isize) -> NeighbouringRow<'_> {
+        let size = (self.width, self.height);
+        let y = y as isize + y_offset;
+        let alive = |x: isize| {
+            self.boundary
+                .resolve((x, y), size)
+                .is_some_and(|(x, y)| self.is_alive(x, y))
+        };
+
+        let words = match self.boundary.resolve((0, y), size) {
+            None => Cow::Owned(vec![0; words(self.width)]),
+            Some((0, row_y)) => Cow::Borrowed(&self.rows[row_y][..]),
+            // Some boundaries flip a row over as it wraps round.
+            Some(_) => {
+                let mut row = vec![0; words(self.width)];
+                for x in 0..self.width {
+                    set_bit(&mut row, x, alive(x as isize));
+                }
+                Cow::Owned(row)
+            }
+        };
+
+        NeighbouringRow {
+            words,
+            west: u64::from(alive(-1)),
+            east: u64::from(alive(self.width as isize))
+                << ((self.width + WORD_BITS - 1) % WORD_BITS),
+        }
+    }
+}
+
+struct NeighbouringRow<'a> {
+    words: Cow<'a, [u64]>,
+    /// The cell past the west end, as the lowest bit of a word.
+    west: u64,
+    /// The cell past the east end, in the last word's bit for the east-most
+    /// cell.
+    east: u64,
+}
+
+impl NeighbouringRow<'_> {
+    /// Word `i` of the row moved one cell east, so each cell lines up with
+    /// its west neighbour.
+    fn shifted_east(&self, i: usize) -> u64 {
+        let carry = match i {
+            0 => self.west,
+            _ => self.words[i - 1] >> (WORD_BITS - 1),
+        };
+        self.words[i] << 1 | carry
+    }
+
+    /// Word `i` of the row moved one cell west, so each cell lines up with
+    /// its east neighbour.
+    fn shifted_west(&self, i: usize) -> u64 {
+        let carry = match self.words.get(i + 1) {
+            Some(next) => next << (WORD_BITS - 1),
+            None => self.east,
+        };
+        self.words[i] >> 1 | carry
+    }
+}
+
+impl Grid for BitWorld {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn generation(&self) -> usize {
+        self.generation
+    }
+
+    fn is_alive(&self, x: usize, y: usize) -> bool {
+        self.rows[y][x / WORD_BITS] & (1 << (x % WORD_BITS)) != 0
+    }
+
+    fn set_alive(&mut self, x: usize, y: usize, alive: bool) {
+        set_bit(&mut self.rows[y], x, alive);
+    }
+
+    fn live_cells(&self) -> Vec<(usize, usize)> {
+        let mut cells = Vec::new();
+        for (y, row) in self.rows.iter().enumerate() {
+            for (i, word) in row.iter().enumerate() {
+                let mut word = *word;
+                while word != 0 {
+                    cells.push((i * WORD_BITS + word.trailing_zeros() as usize, y));
+                    word &= word - 1;
+                }
+            }
+        }
+        cells
+    }
+
+    fn population(&self) -> usize {
+        self.live_cells_count()
+    }
+
+    fn memory_usage(&self) -> usize {
+        mem::size_of::<Self>()
+            + heap_bytes::<Vec<u64>>(self.rows.capacity())
+            + self
+                .rows
+                .iter()
+                .map(|row| heap_bytes::<u64>(row.capacity()))
+                .sum::<usize>()
+    }
+
+    fn simulate(&mut self) {
+        let last_mask = last_word_mask(self.width);
+        let mut next = Vec::with_capacity(self.height);
+
+        for y in 0..self.height {
+            let row = &self.rows[y];
+            let mut next_row = vec![0; row.len()];
+            for (i, count) in self.count_row(y).into_iter().enumerate() {
+                let alive = row[i];
+                for n in 0..9 {
+                    if !self.survivals[n] && !self.births[n] {
+                        continue;
+                    }
+                    let has_n = count.iter().enumerate().fold(!0, |mask, (bit, word)| {
+                        mask & if n & (1 << bit) != 0 { *word } else { !*word }
+                    });
+                    if self.survivals[n] {
+                        next_row[i] |= has_n & alive;
+                    }
+                    if self.births[n] {
+                        next_row[i] |= has_n & !alive;
+                    }
+                }
+            }
+            if let Some(last) = next_row.last_mut() {
+                *last &= last_mask;
+            }
+            next.push(next_row);
+        }
+
+        self.rows = next;
+        self.generation += 1;
+    }
+}
+
+fn words(width: usize) -> usize {
+    width.div_ceil(WORD_BITS)
+}
+
+/// The bits of the last word of a row that are inside the world.
+fn last_word_mask(width: usize) -> u64 {
+    match width % WORD_BITS {
+        0 => !0,
+        bits => (1 << bits) - 1,
+    }
+}
+
+fn set_bit(row: &mut [u64], x: usize, alive: bool) {
+    let bit = 1 << (x % WORD_BITS);
+    if alive {
+        row[x / WORD_BITS] |= bit;
+    } else {
+        row[x / WORD_BITS] &= !bit;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_world() {
+        let boundaries = [
+            Boundary::Wrap,
+            Boundary::Dead,
+            Boundary::Mirror,
+            Boundary::Klein,
+            Boundary::CrossSurface,
+        ];
+
+        // Wide enough for rows of more than one word.
+        for rule in &["B3/S23", "B36/S23", "B0/S8", "B2/S"] {
+            for boundary in &boundaries {
+                let mut world = World::new(70, 6);
+                world.set_rule(rule.parse().unwrap());
+                world.set_boundary(*boundary);
+                world.seed_from_string(
+                    "- # # - -
+                     # # - - #
+                     - # - - -"
+                        .to_string(),
+                    (62, 0),
+                );
+                world.toggle_cell(0, 5);
+                world.toggle_cell(69, 5);
+                world.toggle_cell(68, 4);
+                let mut packed = BitWorld::from_world(&world).unwrap();
+
+                for _ in 0..10 {
+                    world.simulate();
+                    packed.simulate();
+
+                    let mut expected = Grid::live_cells(&world);
+                    let mut cells = packed.live_cells();
+                    expected.sort();
+                    cells.sort();
+                    assert_eq!(cells, expected, "{} {:?}", rule, boundary);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_grid() {
+        let mut packed = BitWorld::new(130, 3, Rule::conway()).unwrap();
+        packed.set_alive(63, 1, true);
+        packed.set_alive(64, 1, true);
+        packed.set_alive(65, 1, true);
+        assert_eq!(packed.live_cells(), vec![(63, 1), (64, 1), (65, 1)]);
+
+        packed.simulate();
+        assert_eq!(packed.live_cells(), vec![(64, 0), (64, 1), (64, 2)]);
+        assert_eq!(packed.live_cells_count(), 3);
+    }
+
+    #[test]
+    fn test_unsupported_rules() {
+        assert!(BitWorld::new(5, 5, "B3/S23V".parse().unwrap()).is_err());
+        assert!(BitWorld::new(5, 5, "R2,C0,M0,S3..3,B3..3,NM".parse().unwrap()).is_err());
+        assert!(BitWorld::new(5, 5, "briansbrain".parse().unwrap()).is_err());
+    }
+}
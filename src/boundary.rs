@@ -0,0 +1,136 @@
+//! What lies past the edges of a world.
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Boundary {
+    /// Each edge wraps around to the opposite one, making the world a torus.
+    #[default]
+    Wrap,
+    /// Cells past the edges are always dead.
+    Dead,
+    /// Cells past the edges are reflections of the cells inside them.
+    Mirror,
+    /// Both edges wrap, but going off the top or bottom also flips the
+    /// world left to right.
+    Klein,
+    /// Both edges wrap, and going off any edge flips the world along the
+    /// other axis, making it a cross-surface (a real projective plane).
+    CrossSurface,
+}
+
+impl Boundary {
+    /// Where the cell at `position`, which may be past the edges of a world
+    /// of the given size, really is. `None` if it's past a dead edge, or the
+    /// world has no cells to land on.
+    pub fn resolve(
+        self,
+        (x, y): (isize, isize),
+        (width, height): (usize, usize),
+    ) -> Option<(usize, usize)> {
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        // The twisted surfaces wrap like a torus, except that each time a
+        // cell wraps round one axis its position on the other is reversed.
+        let (flip_x, flip_y) = match self {
+            Boundary::Klein => (laps(y, height) % 2 != 0, false),
+            Boundary::CrossSurface => (laps(y, height) % 2 != 0, laps(x, width) % 2 != 0),
+            _ => (false, false),
+        };
+
+        let x = self.resolve_axis(x, width)?;
+        let y = self.resolve_axis(y, height)?;
+        Some((
+            if flip_x { width - 1 - x } else { x },
+            if flip_y { height - 1 - y } else { y },
+        ))
+    }
+
+    fn resolve_axis(self, n: isize, size: usize) -> Option<usize> {
+        let size = size as isize;
+
+        match self {
+            Boundary::Wrap | Boundary::Klein | Boundary::CrossSurface => {
+                Some(n.rem_euclid(size) as usize)
+            }
+            Boundary::Dead => (0..size).contains(&n).then_some(n as usize),
+            Boundary::Mirror => {
+                // Reflecting back and forth between the edges repeats every
+                // two widths.
+                let n = n.rem_euclid(2 * size);
+                Some(if n < size { n } else { 2 * size - 1 - n } as usize)
+            }
+        }
+    }
+}
+
+/// How many times `n` has gone round an axis of the given size.
+fn laps(n: isize, size: usize) -> isize {
+    n.div_euclid(size as isize)
+}
+
+impl FromStr for Boundary {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wrap" => Ok(Boundary::Wrap),
+            "dead" => Ok(Boundary::Dead),
+            "mirror" => Ok(Boundary::Mirror),
+            "klein" => Ok(Boundary::Klein),
+            "cross-surface" => Ok(Boundary::CrossSurface),
+            _ => Err(format!("unknown boundary: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Boundary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Boundary::Wrap => "wrap",
+            Boundary::Dead => "dead",
+            Boundary::Mirror => "mirror",
+            Boundary::Klein => "klein",
+            Boundary::CrossSurface => "cross-surface",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let size = (4, 3);
+
+        assert_eq!(Boundary::Wrap.resolve((-1, 3), size), Some((3, 0)));
+        assert_eq!(Boundary::Dead.resolve((-1, 0), size), None);
+        assert_eq!(Boundary::Dead.resolve((0, 3), size), None);
+        assert_eq!(Boundary::Dead.resolve((3, 2), size), Some((3, 2)));
+        assert_eq!(Boundary::Mirror.resolve((-1, 3), size), Some((0, 2)));
+        assert_eq!(Boundary::Mirror.resolve((-2, 4), size), Some((1, 1)));
+        assert_eq!(Boundary::Klein.resolve((-1, 1), size), Some((3, 1)));
+        assert_eq!(Boundary::Klein.resolve((1, -1), size), Some((2, 2)));
+        assert_eq!(Boundary::Klein.resolve((1, 5), size), Some((2, 2)));
+        assert_eq!(Boundary::CrossSurface.resolve((-1, 0), size), Some((3, 2)));
+        assert_eq!(Boundary::CrossSurface.resolve((1, 3), size), Some((2, 0)));
+
+        for boundary in &[Boundary::Wrap, Boundary::Mirror, Boundary::CrossSurface] {
+            assert_eq!(boundary.resolve((0, 0), (0, 3)), None);
+            assert_eq!(boundary.resolve((-1, 1), (4, 0)), None);
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("klein".parse(), Ok(Boundary::Klein));
+        assert_eq!("cross-surface".parse(), Ok(Boundary::CrossSurface));
+        assert!("sphere".parse::<Boundary>().is_err());
+        assert_eq!(Boundary::CrossSurface.to_string(), "cross-surface");
+    }
+}
@@ -0,0 +1,202 @@
+//! What part of a world the window shows, and how big, so worlds can be
+//! bigger than the window.
+
+use crate::window_buffer::WindowBuffer;
+
+const MAX_ZOOM: usize = 16;
+
+/// A view of a rectangle of the world's pixels, each drawn `zoom` pixels
+/// wide and tall.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Camera {
+    /// The size of the view, in pixels.
+    width: usize,
+    height: usize,
+    /// The world's pixel at the top left of the view.
+    x: usize,
+    y: usize,
+    zoom: usize,
+}
+
+impl Camera {
+    /// A camera showing the top left of the world, unzoomed.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            x: 0,
+            y: 0,
+            zoom: 1,
+        }
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn position(&self) -> (usize, usize) {
+        (self.x, self.y)
+    }
+
+    pub fn zoom(&self) -> usize {
+        self.zoom
+    }
+
+    /// The rectangle of the world's pixels in view, as its top left corner
+    /// and size.
+    pub fn visible(&self, (width, height): (usize, usize)) -> ((usize, usize), (usize, usize)) {
+        let size = (
+            (self.width / self.zoom).min(width),
+            (self.height / self.zoom).min(height),
+        );
+        ((self.x, self.y), size)
+    }
+
+    /// Moves the view by a number of the world's pixels, stopping at its
+    /// edges.
+    pub fn pan(&mut self, (dx, dy): (isize, isize), world_size: (usize, usize)) {
+        self.x = self.x.saturating_add_signed(dx);
+        self.y = self.y.saturating_add_signed(dy);
+        self.clamp(world_size);
+    }
+
+    /// Doubles the zoom, keeping the middle of the view where it is.
+    pub fn zoom_in(&mut self, world_size: (usize, usize)) {
+        self.set_zoom((self.zoom * 2).min(MAX_ZOOM), world_size);
+    }
+
+    /// Halves the zoom, keeping the middle of the view where it is.
+    pub fn zoom_out(&mut self, world_size: (usize, usize)) {
+        self.set_zoom((self.zoom / 2).max(1), world_size);
+    }
+
+    fn set_zoom(&mut self, zoom: usize, world_size: (usize, usize)) {
+        let (_, (width, height)) = self.visible(world_size);
+        let middle = (self.x + width / 2, self.y + height / 2);
+
+        self.zoom = zoom;
+        let (_, (width, height)) = self.visible(world_size);
+        self.x = middle.0.saturating_sub(width / 2);
+        self.y = middle.1.saturating_sub(height / 2);
+        self.clamp(world_size);
+    }
+
+    fn clamp(&mut self, world_size: (usize, usize)) {
+        let (_, (width, height)) = self.visible(world_size);
+        self.x = self.x.min(world_size.0 - width);
+        self.y = self.y.min(world_size.1 - height);
+    }
+
+    /// The world's pixel under a pixel of the view, if there is one.
+    pub fn to_world(
+        &self,
+        (px, py): (usize, usize),
+        world_size: (usize, usize),
+    ) -> Option<(usize, usize)> {
+        let ((x, y), (width, height)) = self.visible(world_size);
+        let (x_offset, y_offset) = (px / self.zoom, py / self.zoom);
+        (x_offset < width && y_offset < height).then_some((x + x_offset, y + y_offset))
+    }
+
+    /// Draws the part of `world` in view into `view`, which is the camera's
+    /// size.
+    pub fn render(&self, world: &WindowBuffer, view: &mut WindowBuffer) {
+        assert!(view.width() == self.width && view.height() == self.height);
+
+        let world_size = (world.width(), world.height());
+        view.paint(((0, 0), (self.width, self.height)), |px, py| {
+            match self.to_world((px, py), world_size) {
+                Some((x, y)) => world.pixel(x, y),
+                None => 0,
+            }
+        });
+    }
+
+    /// Redraws just the pixels of `view` showing a rectangle of `world`'s
+    /// pixels, for when only they have changed since the last `render`.
+    pub fn render_rect(
+        &self,
+        world: &WindowBuffer,
+        view: &mut WindowBuffer,
+        ((x, y), (width, height)): ((usize, usize), (usize, usize)),
+    ) {
+        let ((left, top), (visible_width, visible_height)) =
+            self.visible((world.width(), world.height()));
+        let (right, bottom) = (left + visible_width, top + visible_height);
+
+        let (x, y) = (x.max(left), y.max(top));
+        let (width, height) = (
+            (x + width).min(right).saturating_sub(x),
+            (y + height).min(bottom).saturating_sub(y),
+        );
+        // Each of the world's pixels is drawn `zoom` pixels wide and tall.
+        let corner = ((x - left) * self.zoom, (y - top) * self.zoom);
+        let size = (width * self.zoom, height * self.zoom);
+        view.paint((corner, size), |px, py| {
+            world.pixel(left + px / self.zoom, top + py / self.zoom)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pan_and_zoom() {
+        let world_size = (100, 80);
+        let mut camera = Camera::new(40, 30);
+        assert_eq!(camera.visible(world_size), ((0, 0), (40, 30)));
+
+        camera.pan((70, -5), world_size);
+        assert_eq!(camera.position(), (60, 0));
+
+        camera.zoom_in(world_size);
+        assert_eq!(camera.zoom(), 2);
+        assert_eq!(camera.visible(world_size), ((70, 8), (20, 15)));
+        assert_eq!(camera.to_world((39, 29), world_size), Some((89, 22)));
+
+        camera.zoom_out(world_size);
+        camera.zoom_out(world_size);
+        assert_eq!(camera.zoom(), 1);
+        assert_eq!(camera.visible(world_size), ((60, 0), (40, 30)));
+    }
+
+    #[test]
+    fn test_smaller_world() {
+        let world_size = (10, 5);
+        let mut camera = Camera::new(40, 30);
+        camera.pan((3, 3), world_size);
+        assert_eq!(camera.visible(world_size), ((0, 0), (10, 5)));
+        assert_eq!(camera.to_world((9, 4), world_size), Some((9, 4)));
+        assert_eq!(camera.to_world((10, 4), world_size), None);
+    }
+
+    #[test]
+    fn test_render() {
+        let mut world = WindowBuffer::new(4, 4);
+        world.set_pixel(2, 1, 0xff0000);
+
+        let mut camera = Camera::new(4, 4);
+        camera.zoom_in((4, 4));
+        let mut view = WindowBuffer::new(4, 4);
+        camera.render(&world, &mut view);
+
+        // The middle 2x2 pixels, each drawn 2x2.
+        assert_eq!(camera.position(), (1, 1));
+        let lit: Vec<_> = (0..16)
+            .filter(|i| view.buffer[*i] != 0)
+            .map(|i| (i % 4, i / 4))
+            .collect();
+        assert_eq!(lit, vec![(2, 0), (3, 0), (2, 1), (3, 1)]);
+
+        world.set_pixel(2, 1, 0);
+        world.set_pixel(1, 2, 0x00ff00);
+        world.set_pixel(0, 0, 0x0000ff);
+        let dirty = world.take_dirty().unwrap();
+        camera.render_rect(&world, &mut view, dirty);
+        let mut full = WindowBuffer::new(4, 4);
+        camera.render(&world, &mut full);
+        assert_eq!(view.buffer, full.buffer);
+    }
+}
@@ -0,0 +1,134 @@
+//! Classic patterns bundled with the crate, so a quick demo doesn't need any seed files.
+
+use crate::formats::{Format, Pattern, Transform};
+use std::str::FromStr;
+
+const PATTERNS: &[(&str, &str)] = &[
+    ("glider", include_str!("../seeds/glider.rle")),
+    ("blinker", include_str!("../seeds/blinker.rle")),
+    ("pulsar", include_str!("../seeds/pulsar.rle")),
+    ("gosper-gun", include_str!("../seeds/gosper_glider_gun.txt")),
+    ("lwss", include_str!("../seeds/lightweight_spaceship.rle")),
+    ("mwss", include_str!("../seeds/middle_weight_spaceship.txt")),
+    ("r-pentomino", include_str!("../seeds/r_pentomino.rle")),
+];
+
+/// The names accepted by `catalog_pattern`.
+pub fn catalog_names() -> impl Iterator<Item = &'static str> {
+    PATTERNS.iter().map(|(name, _)| *name)
+}
+
+pub fn catalog_pattern(name: &str) -> Option<Pattern> {
+    PATTERNS
+        .iter()
+        .find(|(pattern_name, _)| *pattern_name == name)
+        .map(|(_, contents)| Format::parse_auto(contents).expect("built-in patterns are valid"))
+}
+
+/// A catalog pattern placed in a scene, parsed from `NAME@X,Y[,TRANSFORM...]`,
+/// e.g. `glider@10,5,rot90,flipx`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Placement {
+    pub name: String,
+    pub x: usize,
+    pub y: usize,
+    pub transforms: Vec<Transform>,
+}
+
+impl Placement {
+    /// The catalog pattern with every transform applied, in order.
+    pub fn pattern(&self) -> Option<Pattern> {
+        let pattern = catalog_pattern(&self.name)?;
+
+        Some(
+            self.transforms
+                .iter()
+                .fold(pattern, |pattern, transform| pattern.transform(*transform)),
+        )
+    }
+}
+
+impl FromStr for Placement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, '@');
+        let name = parts.next().unwrap_or("").to_string();
+        if catalog_pattern(&name).is_none() {
+            return Err(format!("unknown pattern: {}", name));
+        }
+
+        let mut arguments = parts.next().unwrap_or("0,0").split(',');
+        let mut coordinate = || -> Result<usize, String> {
+            let value = arguments.next().unwrap_or("");
+            value
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid coordinate in {}: {:?}", s, value))
+        };
+        let x = coordinate()?;
+        let y = coordinate()?;
+        let transforms = arguments
+            .map(|transform| transform.trim().parse())
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            name,
+            x,
+            y,
+            transforms,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::World;
+
+    #[test]
+    fn test_all_patterns_parse() {
+        for name in catalog_names() {
+            let pattern = catalog_pattern(name).unwrap();
+            assert!(!pattern.cells.is_empty(), "{} is empty", name);
+        }
+
+        assert_eq!(catalog_pattern("unknown"), None);
+    }
+
+    #[test]
+    fn test_placement() {
+        let placement: Placement = "glider@10,5,rot90,flipx".parse().unwrap();
+
+        assert_eq!(placement.name, "glider");
+        assert_eq!((placement.x, placement.y), (10, 5));
+        assert_eq!(
+            placement.transforms,
+            vec![Transform::Rotate90, Transform::FlipHorizontal]
+        );
+        assert_eq!(
+            placement.pattern().unwrap().cells,
+            vec![(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]
+        );
+
+        assert!("glider".parse::<Placement>().is_ok());
+        assert!("unknown@1,2".parse::<Placement>().is_err());
+        assert!("glider@1".parse::<Placement>().is_err());
+        assert!("glider@1,2,spin".parse::<Placement>().is_err());
+    }
+
+    #[test]
+    fn test_pulsar_has_period_three() {
+        let mut world = World::new(17, 17);
+        world
+            .seed_pattern_at(&catalog_pattern("pulsar").unwrap(), 2, 2)
+            .unwrap();
+        let original = world.clone();
+
+        world.simulate();
+        assert_ne!(original, world);
+        world.simulate();
+        world.simulate();
+        assert_eq!(original, world);
+    }
+}
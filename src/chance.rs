@@ -0,0 +1,79 @@
+//! Noisy rules, where births and survivals don't always happen, and cells
+//! flip at random.
+
+use rand::rngs::StdRng;
+use rand::{FromEntropy, Rng, SeedableRng};
+
+/// The probabilities that a birth or survival the rule allows goes ahead.
+/// Seeding the random number generator makes runs reproducible.
+#[derive(Clone, Debug)]
+pub struct Chance {
+    birth: f64,
+    survival: f64,
+    noise: f64,
+    rng: StdRng,
+    /// What `rng` was last seeded with, if it wasn't seeded at random.
+    seed: Option<u64>,
+}
+
+impl Chance {
+    /// Panics unless both probabilities are between 0 and 1.
+    pub fn new(birth: f64, survival: f64, seed: Option<u64>) -> Self {
+        assert!((0.0..=1.0).contains(&birth) && (0.0..=1.0).contains(&survival));
+
+        Self {
+            birth,
+            survival,
+            noise: 0.0,
+            rng: match seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            },
+            seed,
+        }
+    }
+
+    /// Flips this fraction of cells every generation. Panics unless it's
+    /// between 0 and 1.
+    pub fn with_noise(self, noise: f64) -> Self {
+        assert!((0.0..=1.0).contains(&noise));
+        Self { noise, ..self }
+    }
+
+    pub fn has_noise(&self) -> bool {
+        self.noise > 0.0
+    }
+
+    /// The chances of a birth and a survival, and the noise.
+    pub fn probabilities(&self) -> (f64, f64, f64) {
+        (self.birth, self.survival, self.noise)
+    }
+
+    /// What the random number generator was last seeded with, if it wasn't
+    /// seeded at random.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Reseeds the random number generator with a seed drawn from it,
+    /// returning the seed. A chance made with it carries on the same from
+    /// here, so a run can be saved and picked up again.
+    pub fn reseed(&mut self) -> u64 {
+        let seed = self.rng.gen();
+        self.rng = StdRng::seed_from_u64(seed);
+        self.seed = Some(seed);
+        seed
+    }
+
+    pub fn births(&mut self) -> bool {
+        self.rng.gen_bool(self.birth)
+    }
+
+    pub fn survives(&mut self) -> bool {
+        self.rng.gen_bool(self.survival)
+    }
+
+    pub fn flips(&mut self) -> bool {
+        self.rng.gen_bool(self.noise)
+    }
+}
@@ -0,0 +1,101 @@
+//! One-dimensional elementary cellular automata, drawn a row at a time.
+
+use crate::world::World;
+use std::str::FromStr;
+
+/// A Wolfram rule number, e.g. rule 30 or rule 110. Bit `n` of the number
+/// is the next state of a cell whose left, centre and right neighbours
+/// spell out `n` in binary.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Elementary(u8);
+
+impl Elementary {
+    pub fn new(rule: u8) -> Self {
+        Elementary(rule)
+    }
+
+    /// Scrolls the world up a row and fills the bottom row with the next
+    /// generation of the row that was there. Cells past the edges are dead.
+    pub fn step(self, world: &mut World) {
+        let last = world.row(world.height - 1);
+        let alive = |x: isize| x >= 0 && (x as usize) < world.width && last[x as usize].alive;
+        let next: Vec<bool> = (0..world.width as isize)
+            .map(|x| {
+                let index = (alive(x - 1) as u8) << 2 | (alive(x) as u8) << 1 | alive(x + 1) as u8;
+                self.0 >> index & 1 == 1
+            })
+            .collect();
+
+        // The top row comes round to the bottom, to be overwritten.
+        world.cells.rotate_left(world.width);
+        let start = (world.height - 1) * world.width;
+        for (cell, alive) in world.cells[start..].iter_mut().zip(next) {
+            cell.alive = alive;
+            cell.state = 0;
+        }
+        world.recount_neighbours();
+
+        let generation = world.generation();
+        world.set_generation(generation + 1);
+    }
+}
+
+/// Parses a rule number from 0 to 255, optionally written as `ruleN`.
+impl FromStr for Elementary {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.trim_start_matches("rule")
+            .parse()
+            .map(Elementary)
+            .map_err(|_| format!("invalid elementary rule: {}", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bottom_row(world: &World) -> String {
+        world
+            .row(world.height - 1)
+            .iter()
+            .map(|cell| if cell.alive { '#' } else { '-' })
+            .collect()
+    }
+
+    #[test]
+    fn test_step() {
+        let mut world = World::new(7, 3);
+        world.toggle_cell(3, 2);
+
+        let rule = Elementary::new(30);
+        rule.step(&mut world);
+        assert_eq!(bottom_row(&world), "--###--");
+        rule.step(&mut world);
+        assert_eq!(bottom_row(&world), "-##--#-");
+
+        // Earlier generations scroll up.
+        assert_eq!(
+            world.live_cells().collect::<Vec<_>>(),
+            vec![(3, 0), (2, 1), (3, 1), (4, 1), (1, 2), (2, 2), (5, 2)]
+        );
+        assert_eq!(world.generation(), 2);
+    }
+
+    #[test]
+    fn test_edges_are_dead() {
+        let mut world = World::new(3, 1);
+        world.toggle_cell(0, 0);
+
+        Elementary::new(90).step(&mut world);
+        assert_eq!(bottom_row(&world), "-#-");
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("110".parse(), Ok(Elementary::new(110)));
+        assert_eq!("rule30".parse(), Ok(Elementary::new(30)));
+        assert!("256".parse::<Elementary>().is_err());
+    }
+}
@@ -0,0 +1,95 @@
+use crate::formats::{Pattern, SeedError};
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+const LIFEWIKI_PATTERNS_URL: &str = "https://conwaylife.com/patterns/";
+
+/// Downloads a pattern by LifeWiki name (e.g. `gosperglidergun`) or from an
+/// arbitrary URL. Downloads are cached so repeated runs work offline.
+pub fn fetch_pattern(name_or_url: &str) -> Result<Pattern, SeedError> {
+    let url = pattern_url(name_or_url);
+    let cache_path = cache_dir().map(|dir| dir.join(cache_file_name(&url)));
+
+    if let Some(contents) = cache_path
+        .as_ref()
+        .and_then(|path| fs::read_to_string(path).ok())
+    {
+        return Pattern::from_reader(contents.as_bytes());
+    }
+
+    let mut contents = String::new();
+    ureq::get(&url)
+        .call()
+        .map_err(|e| io::Error::other(e.to_string()))?
+        .into_reader()
+        .read_to_string(&mut contents)?;
+    let pattern = Pattern::from_reader(contents.as_bytes())?;
+
+    // Caching is best effort; a read-only cache shouldn't stop the download.
+    if let Some(path) = cache_path {
+        let _ = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(path, &contents));
+    }
+
+    Ok(pattern)
+}
+
+fn pattern_url(name_or_url: &str) -> String {
+    if name_or_url.contains("://") {
+        name_or_url.to_string()
+    } else {
+        format!(
+            "{}{}.rle",
+            LIFEWIKI_PATTERNS_URL,
+            name_or_url.to_lowercase()
+        )
+    }
+}
+
+fn cache_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .map(|dir| dir.join("gol"))
+}
+
+fn cache_file_name(url: &str) -> String {
+    url.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pattern_url() {
+        assert_eq!(
+            pattern_url("GosperGliderGun"),
+            "https://conwaylife.com/patterns/gosperglidergun.rle"
+        );
+        assert_eq!(
+            pattern_url("http://example.com/glider.rle"),
+            "http://example.com/glider.rle"
+        );
+    }
+
+    #[test]
+    fn test_cache_file_name() {
+        assert_eq!(
+            cache_file_name("https://conwaylife.com/patterns/glider.rle"),
+            "https___conwaylife.com_patterns_glider.rle"
+        );
+    }
+}
@@ -0,0 +1,469 @@
+//! Seed file formats.
+//!
+//! Every format parses into and writes from a `Pattern`, which is independent of any `World`.
+
+pub(crate) mod life105;
+mod life106;
+mod macrocell;
+pub(crate) mod native;
+mod plaintext;
+mod rle;
+
+use crate::rule::Rule;
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{self, Read};
+use std::path::Path;
+use std::str::FromStr;
+
+/// The most cells wide or tall a pattern placed by coordinates can be, which
+/// is far bigger than any world.
+const MAX_SIZE: usize = 1 << 30;
+
+/// A set of live cells decoded from (or ready to be encoded to) a seed file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Pattern {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<(usize, usize)>,
+    pub rule: Option<String>,
+    pub name: Option<String>,
+    pub author: Option<String>,
+    /// The world size the pattern was designed for, if the file suggests one.
+    pub world_size: Option<(usize, usize)>,
+}
+
+impl Pattern {
+    /// Builds a pattern from live cell coordinates, sizing it to fit them.
+    pub fn from_cells(cells: Vec<(usize, usize)>) -> Self {
+        let width = cells.iter().map(|(x, _)| x + 1).max().unwrap_or(0);
+        let height = cells.iter().map(|(_, y)| y + 1).max().unwrap_or(0);
+
+        Self {
+            width,
+            height,
+            cells,
+            ..Self::default()
+        }
+    }
+
+    /// Adds another pattern's cells with its top-left corner at `(x, y)`, growing to fit.
+    pub fn stamp(&mut self, other: &Pattern, x: usize, y: usize) {
+        let existing: HashSet<(usize, usize)> = self.cells.iter().cloned().collect();
+
+        self.cells.extend(
+            other
+                .cells
+                .iter()
+                .map(|(cell_x, cell_y)| (x + cell_x, y + cell_y))
+                .filter(|cell| !existing.contains(cell)),
+        );
+        self.width = self.width.max(x + other.width);
+        self.height = self.height.max(y + other.height);
+    }
+
+    /// The rule the pattern's file names, if any, without the bounded grid
+    /// Golly can append to it (e.g. the `:T100,100` of `B3/S23:T100,100`).
+    pub fn parse_rule(&self) -> Option<Result<Rule, String>> {
+        self.rule
+            .as_ref()
+            .map(|rule| rule.split(':').next().unwrap_or_default().parse())
+    }
+
+    pub fn transform(&self, transform: Transform) -> Pattern {
+        let (width, height) = (self.width, self.height);
+        let (new_width, new_height) = match transform {
+            Transform::Rotate90 | Transform::Rotate270 => (height, width),
+            _ => (width, height),
+        };
+
+        let cells = self
+            .cells
+            .iter()
+            .map(|&(x, y)| match transform {
+                Transform::Rotate90 => (height - 1 - y, x),
+                Transform::Rotate180 => (width - 1 - x, height - 1 - y),
+                Transform::Rotate270 => (y, width - 1 - x),
+                Transform::FlipHorizontal => (width - 1 - x, y),
+                Transform::FlipVertical => (x, height - 1 - y),
+            })
+            .collect();
+
+        Pattern {
+            width: new_width,
+            height: new_height,
+            cells,
+            ..self.clone()
+        }
+    }
+
+    /// Reads a pattern in any format `Format::detect` recognises.
+    pub fn from_reader(mut reader: impl Read) -> Result<Self, SeedError> {
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+        Ok(Format::parse_auto(&contents)?)
+    }
+
+    /// Builds a pattern from coordinates that may be negative, translating them so the
+    /// top-left live cell is at the origin. Fails if they're spread further than `MAX_SIZE`.
+    pub(crate) fn from_signed_cells(cells: Vec<(isize, isize)>) -> Result<Self, String> {
+        let min_x = cells.iter().map(|(x, _)| *x).min().unwrap_or(0);
+        let min_y = cells.iter().map(|(_, y)| *y).min().unwrap_or(0);
+        let translate = |n: isize, min: isize| {
+            n.checked_sub(min)
+                .map(|n| n as usize)
+                .filter(|n| *n < MAX_SIZE)
+        };
+
+        let cells = cells
+            .into_iter()
+            .map(|(x, y)| Some((translate(x, min_x)?, translate(y, min_y)?)))
+            .collect::<Option<_>>()
+            .ok_or_else(|| format!("pattern is more than {} cells wide or tall", MAX_SIZE))?;
+        Ok(Self::from_cells(cells))
+    }
+}
+
+/// A rotation (clockwise) or reflection of a pattern.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transform {
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Mirrors left to right.
+    FlipHorizontal,
+    /// Mirrors top to bottom.
+    FlipVertical,
+}
+
+impl FromStr for Transform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rot90" => Ok(Transform::Rotate90),
+            "rot180" => Ok(Transform::Rotate180),
+            "rot270" => Ok(Transform::Rotate270),
+            "flipx" => Ok(Transform::FlipHorizontal),
+            "flipy" => Ok(Transform::FlipVertical),
+            _ => Err(format!("unknown transform: {}", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    /// The crate's own space separated `#`/`-` grid.
+    Native,
+    /// Run Length Encoded, as used by Golly and LifeWiki.
+    Rle,
+    /// Plaintext `.cells`: rows of `.` and `O` with `!` comments.
+    Plaintext,
+    /// Life 1.05: `#P x y` blocks of `*`/`.` rows placed at their offsets.
+    Life105,
+    /// Life 1.06: a `#Life 1.06` header followed by `x y` pairs.
+    Life106,
+    /// Golly's quadtree based macrocell format, for very large or sparse patterns.
+    Macrocell,
+}
+
+impl Format {
+    /// Guesses the format of a seed file from its contents.
+    pub fn detect(input: &str) -> Option<Format> {
+        let lines: Vec<&str> = input
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+
+        let first_line = *lines.first()?;
+        if first_line.starts_with(macrocell::HEADER) {
+            return Some(Format::Macrocell);
+        }
+        if first_line.starts_with(life105::HEADER) {
+            return Some(Format::Life105);
+        }
+        if first_line.starts_with(life106::HEADER) {
+            return Some(Format::Life106);
+        }
+
+        if lines
+            .iter()
+            .filter(|line| !native::is_comment(line))
+            .all(|line| line.chars().all(|c| c == '#' || c == '-' || c == ' '))
+        {
+            return Some(Format::Native);
+        }
+
+        // Every other format has been ruled out if all lines are `#` comments.
+        let first_row = lines.iter().find(|line| !line.starts_with('#'))?;
+        if first_row.starts_with('x') && first_row[1..].trim_start().starts_with('=') {
+            return Some(Format::Rle);
+        }
+
+        if lines
+            .iter()
+            .filter(|line| !line.starts_with('!'))
+            .all(|line| line.chars().all(|c| c == '.' || c == 'O' || c == '*'))
+        {
+            return Some(Format::Plaintext);
+        }
+
+        None
+    }
+
+    /// Guesses the format of a seed file from its extension. Life 1.05 and 1.06 share `.lif`,
+    /// so those files have to be told apart with `detect`.
+    pub fn from_extension(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()? {
+            "rle" => Some(Format::Rle),
+            "cells" => Some(Format::Plaintext),
+            "mc" => Some(Format::Macrocell),
+            _ => None,
+        }
+    }
+
+    pub fn parse(self, input: &str) -> Result<Pattern, ParseError> {
+        match self {
+            Format::Native => Ok(native::parse(input)),
+            Format::Rle => rle::parse(input),
+            Format::Plaintext => plaintext::parse(input),
+            Format::Life105 => life105::parse(input),
+            Format::Life106 => life106::parse(input),
+            Format::Macrocell => macrocell::parse(input),
+        }
+    }
+
+    /// Detects the format and parses the input, falling back to the native parser (which
+    /// reports the first unexpected character) if no format matches.
+    pub fn parse_auto(input: &str) -> Result<Pattern, ParseError> {
+        match Format::detect(input) {
+            Some(format) => format.parse(input),
+            None => native::parse_strict(input),
+        }
+    }
+
+    pub fn write(self, pattern: &Pattern) -> String {
+        match self {
+            Format::Native => native::write(pattern),
+            Format::Rle => rle::write(pattern),
+            Format::Plaintext => plaintext::write(pattern),
+            Format::Life105 => life105::write(pattern),
+            Format::Life106 => life106::write(pattern),
+            Format::Macrocell => macrocell::write(pattern),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "native" | "ascii" => Ok(Format::Native),
+            "rle" => Ok(Format::Rle),
+            "plaintext" => Ok(Format::Plaintext),
+            "life105" => Ok(Format::Life105),
+            "life106" => Ok(Format::Life106),
+            "macrocell" => Ok(Format::Macrocell),
+            _ => Err(format!("unknown format: {}", s)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    line: usize,
+    message: String,
+}
+
+impl ParseError {
+    pub(crate) fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// An error loading a seed file from disk.
+#[derive(Debug)]
+pub enum SeedError {
+    Io(io::Error),
+    Parse(ParseError),
+    Image(png::DecodingError),
+    /// The pattern doesn't fit in the world it's being seeded into.
+    TooLarge {
+        pattern_size: (usize, usize),
+        world_size: (usize, usize),
+    },
+}
+
+impl fmt::Display for SeedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SeedError::Io(e) => write!(f, "unable to read seed: {}", e),
+            SeedError::Parse(e) => write!(f, "unable to parse seed: {}", e),
+            SeedError::Image(e) => write!(f, "unable to decode seed image: {}", e),
+            SeedError::TooLarge {
+                pattern_size,
+                world_size,
+            } => write!(
+                f,
+                "seed needs a {}x{} world but the world is only {}x{}",
+                pattern_size.0, pattern_size.1, world_size.0, world_size.1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SeedError {}
+
+impl From<io::Error> for SeedError {
+    fn from(e: io::Error) -> Self {
+        SeedError::Io(e)
+    }
+}
+
+impl From<png::DecodingError> for SeedError {
+    fn from(e: png::DecodingError) -> Self {
+        SeedError::Image(e)
+    }
+}
+
+impl From<ParseError> for SeedError {
+    fn from(e: ParseError) -> Self {
+        SeedError::Parse(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Format::detect("- # -\n- - #\n# # #"), Some(Format::Native));
+        assert_eq!(
+            Format::detect("!Name: Glider\n- # -\n- - #\n# # #"),
+            Some(Format::Native)
+        );
+        assert_eq!(
+            Format::detect("#N Glider\nx = 3, y = 3\nbo$2bo$3o!"),
+            Some(Format::Rle)
+        );
+        assert_eq!(
+            Format::detect("!Name: Glider\n.O.\n..O\nOOO"),
+            Some(Format::Plaintext)
+        );
+        assert_eq!(
+            Format::detect("#Life 1.06\n0 -1\n1 0"),
+            Some(Format::Life106)
+        );
+        assert_eq!(
+            Format::detect("#Life 1.05\n#P 0 0\n.*.\n..*\n***"),
+            Some(Format::Life105)
+        );
+        assert_eq!(
+            Format::detect("[M2] (golly 3.3)\n#R B3/S23\n.*$..*$***$"),
+            Some(Format::Macrocell)
+        );
+        assert_eq!(Format::detect("hello"), None);
+        assert_eq!(Format::detect(""), None);
+    }
+
+    #[test]
+    fn test_from_signed_cells() {
+        let pattern = Pattern::from_signed_cells(vec![(-3, 5), (-1, 4)]).unwrap();
+        assert_eq!(pattern.cells, vec![(0, 1), (2, 0)]);
+        assert_eq!((pattern.width, pattern.height), (3, 2));
+
+        assert!(Pattern::from_signed_cells(vec![(isize::MIN, 0), (isize::MAX, 0)]).is_err());
+        assert!(Pattern::from_signed_cells(vec![(0, 0), (0, MAX_SIZE as isize)]).is_err());
+        assert!(Pattern::from_signed_cells(vec![(0, 0), (0, MAX_SIZE as isize - 1)]).is_ok());
+    }
+
+    #[test]
+    fn test_stamp() {
+        let mut pattern = Pattern::from_cells(vec![(0, 0), (1, 1)]);
+
+        pattern.stamp(&Pattern::from_cells(vec![(0, 0), (1, 0)]), 1, 1);
+
+        assert_eq!(pattern.cells, vec![(0, 0), (1, 1), (2, 1)]);
+        assert_eq!((pattern.width, pattern.height), (3, 2));
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        let pattern = |rule: Option<&str>| Pattern {
+            rule: rule.map(String::from),
+            ..Pattern::default()
+        };
+
+        assert_eq!(pattern(None).parse_rule(), None);
+        assert_eq!(
+            pattern(Some("B36/S23")).parse_rule(),
+            Some(Ok("highlife".parse().unwrap()))
+        );
+        assert_eq!(
+            pattern(Some("B3/S23:T100,100")).parse_rule(),
+            Some(Ok(Rule::conway()))
+        );
+        assert!(pattern(Some("nope")).parse_rule().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_transform() {
+        // A 3x2 "L": #-- / ###
+        let pattern = Pattern::from_cells(vec![(0, 0), (0, 1), (1, 1), (2, 1)]);
+
+        let rotated = pattern.transform(Transform::Rotate90);
+        assert_eq!((rotated.width, rotated.height), (2, 3));
+        assert_eq!(rotated.cells, vec![(1, 0), (0, 0), (0, 1), (0, 2)]);
+
+        assert_eq!(
+            pattern.transform(Transform::Rotate180).cells,
+            vec![(2, 1), (2, 0), (1, 0), (0, 0)]
+        );
+        assert_eq!(rotated.transform(Transform::Rotate270).cells, pattern.cells);
+        assert_eq!(
+            pattern.transform(Transform::FlipHorizontal).cells,
+            vec![(2, 0), (2, 1), (1, 1), (0, 1)]
+        );
+        assert_eq!(
+            pattern.transform(Transform::FlipVertical).cells,
+            vec![(0, 1), (0, 0), (1, 0), (2, 0)]
+        );
+    }
+
+    #[test]
+    fn test_from_extension() {
+        assert_eq!(
+            Format::from_extension(Path::new("glider.rle")),
+            Some(Format::Rle)
+        );
+        assert_eq!(
+            Format::from_extension(Path::new("seeds/glider.cells")),
+            Some(Format::Plaintext)
+        );
+        assert_eq!(Format::from_extension(Path::new("glider.lif")), None);
+        assert_eq!(Format::from_extension(Path::new("glider.txt")), None);
+        assert_eq!(Format::from_extension(Path::new("glider")), None);
+    }
+
+    #[test]
+    fn test_parse_auto_falls_back_to_native() {
+        assert_eq!(
+            Format::parse_auto("- # -\n- x -"),
+            Err(ParseError::new(2, "unexpected character: \"x\""))
+        );
+    }
+}
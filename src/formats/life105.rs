@@ -0,0 +1,139 @@
+use super::{ParseError, Pattern};
+
+pub(crate) const HEADER: &str = "#Life 1.05";
+
+/// Parses `#P x y` blocks of `*`/`.` rows, placing each block at its offset.
+pub(crate) fn parse(input: &str) -> Result<Pattern, ParseError> {
+    let mut coordinates: Vec<(isize, isize)> = Vec::new();
+    // The row is `None` once it's gone past the largest coordinate.
+    let (mut block_x, mut y) = (0, Some(0));
+    let mut rule = None;
+
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+
+        if let Some(offset) = line.strip_prefix("#P") {
+            let invalid = || ParseError::new(i + 1, format!("invalid block offset: {}", line));
+            let offset: Vec<isize> = offset
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|_| invalid())?;
+
+            match offset.as_slice() {
+                [x, offset_y] => {
+                    block_x = *x;
+                    y = Some(*offset_y);
+                }
+                _ => return Err(invalid()),
+            }
+            continue;
+        }
+
+        if let Some(value) = line.strip_prefix("#R") {
+            rule = Some(value.trim().to_string());
+            continue;
+        }
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let too_far = || ParseError::new(i + 1, "cell is too far from the origin");
+        for (x, c) in line.chars().enumerate() {
+            match c {
+                '*' => {
+                    let x = block_x.checked_add(x as isize).ok_or_else(too_far)?;
+                    coordinates.push((x, y.ok_or_else(too_far)?));
+                }
+                '.' => {}
+                c => {
+                    return Err(ParseError::new(
+                        i + 1,
+                        format!("unexpected character: {:?}", c),
+                    ))
+                }
+            }
+        }
+        y = y.and_then(|y| y.checked_add(1));
+    }
+
+    let mut pattern = Pattern::from_signed_cells(coordinates)
+        .map_err(|e| ParseError::new(input.lines().count(), e))?;
+    pattern.rule = rule;
+    Ok(pattern)
+}
+
+pub(crate) fn write(pattern: &Pattern) -> String {
+    let mut grid = vec![vec!['.'; pattern.width]; pattern.height];
+    for (x, y) in &pattern.cells {
+        grid[*y][*x] = '*';
+    }
+
+    let mut output = format!("{}\n", HEADER);
+    if let Some(rule) = &pattern.rule {
+        output.push_str(&format!("#R {}\n", rule));
+    }
+    output.push_str("#P 0 0\n");
+    for row in grid {
+        output.extend(row);
+        output.push('\n');
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_multiple_blocks() {
+        let pattern = parse(
+            "#Life 1.05
+#D Two blocks
+#N
+#P -1 -1
+**
+**
+#P 3 0
+*
+*
+",
+        )
+        .unwrap();
+
+        assert_eq!(pattern.width, 5);
+        assert_eq!(pattern.height, 3);
+        assert_eq!(
+            pattern.cells,
+            vec![(0, 0), (1, 0), (0, 1), (1, 1), (4, 1), (4, 2)]
+        );
+    }
+
+    #[test]
+    fn test_large_offsets() {
+        let too_far = |line| Err(ParseError::new(line, "cell is too far from the origin"));
+        assert_eq!(
+            parse("#Life 1.05\n#P 9223372036854775807 0\n*\n.*\n"),
+            too_far(4)
+        );
+        assert_eq!(
+            parse("#Life 1.05\n#P 0 9223372036854775807\n*\n*\n"),
+            too_far(4)
+        );
+
+        // Right up to the largest coordinate is fine.
+        let pattern =
+            parse("#Life 1.05\n#P 9223372036854775806 9223372036854775807\n.*\n").unwrap();
+        assert_eq!(pattern.cells, vec![(0, 0)]);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let pattern = Pattern::from_cells(vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+
+        assert_eq!(write(&pattern), "#Life 1.05\n#P 0 0\n.*.\n..*\n***\n");
+        assert_eq!(parse(&write(&pattern)).unwrap(), pattern);
+    }
+}
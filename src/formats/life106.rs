@@ -0,0 +1,90 @@
+use super::{ParseError, Pattern};
+
+pub(crate) const HEADER: &str = "#Life 1.06";
+
+/// Parses `x y` coordinate pairs, translating them so the top-left live cell is at the origin.
+pub(crate) fn parse(input: &str) -> Result<Pattern, ParseError> {
+    let mut coordinates: Vec<(isize, isize)> = Vec::new();
+
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let coordinate = match parts.as_slice() {
+            [x, y] => x.parse().and_then(|x| y.parse().map(|y| (x, y))).ok(),
+            _ => None,
+        };
+
+        match coordinate {
+            Some(coordinate) => coordinates.push(coordinate),
+            None => {
+                return Err(ParseError::new(
+                    i + 1,
+                    format!("invalid coordinate: {}", line),
+                ))
+            }
+        }
+    }
+
+    Pattern::from_signed_cells(coordinates).map_err(|e| ParseError::new(input.lines().count(), e))
+}
+
+pub(crate) fn write(pattern: &Pattern) -> String {
+    let mut output = format!("{}\n", HEADER);
+    for (x, y) in &pattern.cells {
+        output.push_str(&format!("{} {}\n", x, y));
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_negative_coordinates() {
+        let pattern = parse("#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n").unwrap();
+
+        assert_eq!(pattern.width, 3);
+        assert_eq!(pattern.height, 3);
+        assert_eq!(pattern.cells, vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn test_write() {
+        let pattern = Pattern::from_cells(vec![(1, 0), (0, 1)]);
+
+        assert_eq!(write(&pattern), "#Life 1.06\n1 0\n0 1\n");
+        assert_eq!(parse(&write(&pattern)).unwrap(), pattern);
+    }
+
+    #[test]
+    fn test_extreme_coordinates() {
+        assert_eq!(
+            parse("#Life 1.06\n-9223372036854775808 0\n9223372036854775807 0\n"),
+            Err(ParseError::new(
+                3,
+                format!("pattern is more than {} cells wide or tall", 1 << 30)
+            ))
+        );
+        assert_eq!(
+            parse("#Life 1.06\n9223372036854775807 -9223372036854775808\n")
+                .unwrap()
+                .cells,
+            vec![(0, 0)]
+        );
+    }
+
+    #[test]
+    fn test_invalid_coordinate() {
+        assert_eq!(
+            parse("#Life 1.06\n1 a\n"),
+            Err(ParseError::new(2, "invalid coordinate: 1 a"))
+        );
+    }
+}
@@ -0,0 +1,398 @@
+//! Golly's macrocell format: a quadtree where each line defines a node, children referring
+//! to earlier lines by number (with `0` meaning empty). Level 3 nodes are 8x8 leaves written
+//! as rows of `.` and `*` terminated by `$`. Multi-state files build up from level 1 nodes
+//! of four cell states instead, and cells in any state but 0 are read as alive.
+
+use super::{ParseError, Pattern};
+use std::collections::{HashMap, HashSet};
+
+pub(crate) const HEADER: &str = "[M2]";
+
+const LEAF_LEVEL: u32 = 3;
+const LEAF_SIZE: usize = 1 << LEAF_LEVEL;
+const MAX_LEVEL: u32 = 62;
+/// The most live cells a file can expand to. Each node can repeat the one
+/// before it four times, so a few lines can describe far more.
+const MAX_CELLS: u64 = 1 << 24;
+
+enum Node {
+    Leaf {
+        level: u32,
+        cells: Vec<(usize, usize)>,
+    },
+    Branch {
+        level: u32,
+        children: [usize; 4],
+        population: u64,
+    },
+}
+
+impl Node {
+    fn level(&self) -> u32 {
+        match self {
+            Node::Leaf { level, .. } | Node::Branch { level, .. } => *level,
+        }
+    }
+
+    fn population(&self) -> u64 {
+        match self {
+            Node::Leaf { cells, .. } => cells.len() as u64,
+            Node::Branch { population, .. } => *population,
+        }
+    }
+}
+
+pub(crate) fn parse(input: &str) -> Result<Pattern, ParseError> {
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut rule = None;
+    let mut root_line = 0;
+
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+        let line_number = i + 1;
+
+        if let Some(value) = line.strip_prefix("#R") {
+            rule = Some(value.trim().to_string());
+            continue;
+        }
+
+        if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+            continue;
+        }
+
+        let node = if line.starts_with(['.', '*', '$']) {
+            parse_leaf(line, line_number)?
+        } else {
+            parse_branch(line, line_number, &nodes)?
+        };
+        nodes.push(node);
+        root_line = line_number;
+    }
+
+    let mut cells = Vec::new();
+    if let Some(root) = nodes.last() {
+        if root.population() > MAX_CELLS {
+            return Err(ParseError::new(
+                root_line,
+                format!("pattern has more than {} live cells", MAX_CELLS),
+            ));
+        }
+        collect_cells(&nodes, nodes.len(), 0, 0, &mut cells)
+            .ok_or_else(|| ParseError::new(root_line, "pattern is too large"))?;
+    }
+
+    let mut pattern =
+        Pattern::from_signed_cells(cells).map_err(|e| ParseError::new(root_line, e))?;
+    pattern.rule = rule;
+    Ok(pattern)
+}
+
+fn parse_leaf(line: &str, line_number: usize) -> Result<Node, ParseError> {
+    let mut cells = Vec::new();
+    let (mut x, mut y) = (0, 0);
+
+    for c in line.chars() {
+        match c {
+            '.' => x += 1,
+            '*' => {
+                cells.push((x, y));
+                x += 1;
+            }
+            '$' => {
+                x = 0;
+                y += 1;
+            }
+            c => {
+                return Err(ParseError::new(
+                    line_number,
+                    format!("unexpected character: {:?}", c),
+                ))
+            }
+        }
+
+        if x > LEAF_SIZE || y > LEAF_SIZE {
+            return Err(ParseError::new(line_number, "leaf is larger than 8x8"));
+        }
+    }
+
+    Ok(Node::Leaf {
+        level: LEAF_LEVEL,
+        cells,
+    })
+}
+
+fn parse_branch(line: &str, line_number: usize, nodes: &[Node]) -> Result<Node, ParseError> {
+    let invalid = || ParseError::new(line_number, format!("invalid node: {}", line));
+    let numbers: Vec<usize> = line
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .map_err(|_| invalid())?;
+
+    match numbers.as_slice() {
+        // Level 1 nodes hold cell states directly, which only appear in multi-state patterns.
+        [1, states @ ..] if states.len() == 4 => {
+            let cells = [(0, 0), (1, 0), (0, 1), (1, 1)]
+                .iter()
+                .zip(states)
+                .filter(|(_, state)| **state != 0)
+                .map(|(cell, _)| *cell)
+                .collect();
+            Ok(Node::Leaf { level: 1, cells })
+        }
+        [level, nw, ne, sw, se] => {
+            let level = *level as u32;
+            let children = [*nw, *ne, *sw, *se];
+
+            if !(2..=MAX_LEVEL).contains(&level) {
+                return Err(ParseError::new(
+                    line_number,
+                    format!("unsupported level: {}", level),
+                ));
+            }
+            if children.iter().any(|child| *child > nodes.len()) {
+                return Err(ParseError::new(line_number, "node refers to a later node"));
+            }
+            let fits = |child: &usize| *child == 0 || nodes[child - 1].level() == level - 1;
+            if !children.iter().all(fits) {
+                return Err(ParseError::new(
+                    line_number,
+                    format!("level {} node refers to a node of another level", level),
+                ));
+            }
+
+            let population = children
+                .iter()
+                .filter(|child| **child != 0)
+                .map(|child| nodes[child - 1].population())
+                .fold(0, u64::saturating_add);
+            Ok(Node::Branch {
+                level,
+                children,
+                population,
+            })
+        }
+        _ => Err(invalid()),
+    }
+}
+
+/// Adds the live cells of the node at `index`, with its top left corner at
+/// `(x, y)`. `None` if any of them are too far away to have coordinates.
+fn collect_cells(
+    nodes: &[Node],
+    index: usize,
+    x: isize,
+    y: isize,
+    cells: &mut Vec<(isize, isize)>,
+) -> Option<()> {
+    match &nodes[index - 1] {
+        Node::Leaf {
+            cells: leaf_cells, ..
+        } => {
+            for (cell_x, cell_y) in leaf_cells {
+                cells.push((
+                    x.checked_add(*cell_x as isize)?,
+                    y.checked_add(*cell_y as isize)?,
+                ));
+            }
+        }
+        Node::Branch {
+            level, children, ..
+        } => {
+            let half = 1isize.checked_shl(level - 1)?;
+            let offsets = [(0, 0), (half, 0), (0, half), (half, half)];
+
+            for (child, (offset_x, offset_y)) in children.iter().zip(&offsets) {
+                if *child != 0 {
+                    let (x, y) = (x.checked_add(*offset_x)?, y.checked_add(*offset_y)?);
+                    collect_cells(nodes, *child, x, y, cells)?;
+                }
+            }
+        }
+    }
+    Some(())
+}
+
+pub(crate) fn write(pattern: &Pattern) -> String {
+    let mut writer = Writer {
+        cells: pattern.cells.iter().cloned().collect(),
+        lines: Vec::new(),
+        indices: HashMap::new(),
+    };
+
+    let mut level = LEAF_LEVEL;
+    while (1 << level) < pattern.width.max(pattern.height) {
+        level += 1;
+    }
+
+    if writer.node(0, 0, level) == 0 {
+        writer.lines.push("$".to_string());
+    }
+
+    let mut output = format!("{}\n", HEADER);
+    if let Some(rule) = &pattern.rule {
+        output.push_str(&format!("#R {}\n", rule));
+    }
+    for line in writer.lines {
+        output.push_str(&line);
+        output.push('\n');
+    }
+
+    output
+}
+
+struct Writer {
+    cells: HashSet<(usize, usize)>,
+    lines: Vec<String>,
+    indices: HashMap<String, usize>,
+}
+
+impl Writer {
+    /// Writes the node covering the square at `(x, y)`, returning its number (0 if empty).
+    fn node(&mut self, x: usize, y: usize, level: u32) -> usize {
+        let line = if level == LEAF_LEVEL {
+            self.leaf(x, y)
+        } else {
+            let half = 1 << (level - 1);
+            let children = [
+                self.node(x, y, level - 1),
+                self.node(x + half, y, level - 1),
+                self.node(x, y + half, level - 1),
+                self.node(x + half, y + half, level - 1),
+            ];
+
+            if children.iter().all(|child| *child == 0) {
+                return 0;
+            }
+            format!(
+                "{} {} {} {} {}",
+                level, children[0], children[1], children[2], children[3]
+            )
+        };
+
+        if line.is_empty() {
+            return 0;
+        }
+
+        if let Some(index) = self.indices.get(&line) {
+            return *index;
+        }
+        self.lines.push(line.clone());
+        self.indices.insert(line, self.lines.len());
+        self.lines.len()
+    }
+
+    fn leaf(&self, x: usize, y: usize) -> String {
+        let mut rows: Vec<String> = (y..y + LEAF_SIZE)
+            .map(|y| {
+                let row: String = (x..x + LEAF_SIZE)
+                    .map(|x| {
+                        if self.cells.contains(&(x, y)) {
+                            '*'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect();
+                row.trim_end_matches('.').to_string()
+            })
+            .collect();
+
+        while rows.last().map(String::is_empty) == Some(true) {
+            rows.pop();
+        }
+
+        rows.iter().map(|row| format!("{}$", row)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLIDER: &str = "[M2] (golly 3.3)
+#R B3/S23
+.*$..*$***$
+4 0 0 0 1
+5 2 0 0 0
+";
+
+    #[test]
+    fn test_parse() {
+        let pattern = parse(GLIDER).unwrap();
+
+        assert_eq!(pattern.rule, Some("B3/S23".to_string()));
+        assert_eq!(pattern.cells, vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn test_parse_shared_nodes() {
+        let pattern = parse("[M2]\n*$\n4 1 0 0 1\n").unwrap();
+
+        assert_eq!(pattern.cells, vec![(0, 0), (8, 8)]);
+    }
+
+    #[test]
+    fn test_write() {
+        let pattern = Pattern::from_cells(vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2), (9, 9)]);
+        let output = write(&pattern);
+
+        assert_eq!(output, "[M2]\n.*$..*$***$\n$.*$\n4 1 0 0 2\n");
+        assert_eq!(parse(&output).unwrap(), pattern);
+    }
+
+    #[test]
+    fn test_parse_multi_state() {
+        // Brian's Brain cells in states 1 and 2, repeated across an 8x8
+        // square.
+        let pattern =
+            parse("[M2]\n#R B2/S/3\n1 0 1 2 0\n1 1 0 0 0\n2 2 0 0 1\n3 3 0 0 3\n").unwrap();
+
+        assert_eq!(pattern.rule, Some("B2/S/3".to_string()));
+        let mut cells = pattern.cells;
+        cells.sort_by_key(|&(x, y)| (y, x));
+        assert_eq!(cells, vec![(0, 0), (3, 2), (2, 3), (4, 4), (7, 6), (6, 7)]);
+    }
+
+    #[test]
+    fn test_mismatched_levels() {
+        assert!(parse("[M2]\n1 0 1 0 0\n3 1 0 0 0\n").is_err());
+        assert!(parse("[M2]\n*$\n2 1 0 0 0\n").is_err());
+        assert!(parse("[M2]\n*$\n5 1 0 0 0\n").is_err());
+        assert!(parse("[M2]\n*$\n4 1 0 0 0\n4 2 0 0 2\n").is_err());
+    }
+
+    #[test]
+    fn test_too_large() {
+        // A chain of nodes each holding the one below four times.
+        let mut input = "[M2]\n*$\n".to_string();
+        for level in 4..=MAX_LEVEL {
+            // The node a level down is the one on the line before.
+            let below = level - 3;
+            input.push_str(&format!(
+                "{} {} {} {} {}\n",
+                level, below, below, below, below
+            ));
+        }
+        let lines = input.lines().count();
+        assert_eq!(
+            parse(&input),
+            Err(ParseError::new(
+                lines,
+                format!("pattern has more than {} live cells", MAX_CELLS)
+            ))
+        );
+
+        // A single cell at the far corner, which is fine.
+        let mut input = "[M2]\n*$\n".to_string();
+        for level in 4..=MAX_LEVEL {
+            input.push_str(&format!("{} 0 0 0 {}\n", level, level - 3));
+        }
+        assert!(parse(&input).is_ok());
+    }
+
+    #[test]
+    fn test_forward_reference() {
+        assert!(parse("[M2]\n4 1 0 0 0\n").is_err());
+    }
+}
@@ -0,0 +1,155 @@
+//! The crate's own format: rows of space separated `#` (alive) and `-` (dead) cells.
+//!
+//! Lines starting with `!`, or with `#` followed by anything but a space (e.g. `#N`), are
+//! comments. Comments of the form `!Key: value` set the pattern's metadata:
+//!
+//!   !Name: Glider
+//!   !Author: Richard K. Guy
+//!   !Size: 40x30
+//!   !Rule: B3/S23
+
+use super::{ParseError, Pattern};
+
+pub(crate) fn is_comment(line: &str) -> bool {
+    line.starts_with('!')
+        || (line.starts_with('#') && line.len() > 1 && !line[1..].starts_with(' '))
+}
+
+pub(crate) fn parse(input: &str) -> Pattern {
+    let mut pattern = Pattern::default();
+    let mut y = 0;
+
+    for line in input.trim().split('\n') {
+        let line = line.trim();
+
+        if is_comment(line) {
+            parse_metadata(&line[1..], &mut pattern);
+            continue;
+        }
+
+        let row: Vec<&str> = line.split(' ').collect();
+        for (x, cell) in row.iter().enumerate() {
+            if *cell == "#" {
+                pattern.cells.push((x, y));
+            }
+        }
+
+        pattern.width = pattern.width.max(row.len());
+        y += 1;
+        pattern.height = y;
+    }
+
+    pattern
+}
+
+fn parse_metadata(comment: &str, pattern: &mut Pattern) {
+    let mut parts = comment.splitn(2, ':');
+    let key = parts.next().unwrap_or("").trim().to_lowercase();
+    let value = match parts.next() {
+        Some(value) => value.trim().to_string(),
+        None => return,
+    };
+
+    match key.as_str() {
+        "name" => pattern.name = Some(value),
+        "author" => pattern.author = Some(value),
+        "rule" => pattern.rule = Some(value),
+        "size" => {
+            let mut size = value.split('x').map(|n| n.trim().parse());
+            if let (Some(Ok(width)), Some(Ok(height)), None) =
+                (size.next(), size.next(), size.next())
+            {
+                pattern.world_size = Some((width, height));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Like `parse`, but rejects anything other than `#` and `-` cells.
+pub(crate) fn parse_strict(input: &str) -> Result<Pattern, ParseError> {
+    for (i, line) in input.trim().split('\n').enumerate() {
+        let line = line.trim();
+        if is_comment(line) {
+            continue;
+        }
+
+        if let Some(cell) = line.split(' ').find(|cell| *cell != "#" && *cell != "-") {
+            return Err(ParseError::new(
+                i + 1,
+                format!("unexpected character: {:?}", cell),
+            ));
+        }
+    }
+
+    Ok(parse(input))
+}
+
+pub(crate) fn write(pattern: &Pattern) -> String {
+    let mut output = String::new();
+    if let Some(name) = &pattern.name {
+        output.push_str(&format!("!Name: {}\n", name));
+    }
+    if let Some(author) = &pattern.author {
+        output.push_str(&format!("!Author: {}\n", author));
+    }
+    if let Some((width, height)) = pattern.world_size {
+        output.push_str(&format!("!Size: {}x{}\n", width, height));
+    }
+    if let Some(rule) = &pattern.rule {
+        output.push_str(&format!("!Rule: {}\n", rule));
+    }
+
+    let mut grid = vec![vec!["-"; pattern.width]; pattern.height];
+    for (x, y) in &pattern.cells {
+        grid[*y][*x] = "#";
+    }
+
+    for row in grid {
+        output.push_str(&row.join(" "));
+        output.push('\n');
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let input = "- # -\n- - #\n# # #\n";
+        let pattern = parse(input);
+
+        assert_eq!(pattern.width, 3);
+        assert_eq!(pattern.height, 3);
+        assert_eq!(pattern.cells, vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        assert_eq!(write(&pattern), input);
+    }
+
+    #[test]
+    fn test_comments_and_metadata() {
+        let input = "!Name: Glider
+#C a comment that doesn't shift the rows
+- # -
+!Size: 40x30
+- - #
+# # #
+#Rule: B3/S23
+";
+        let pattern = parse(input);
+
+        assert_eq!(pattern.cells, vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        assert_eq!(pattern.height, 3);
+        assert_eq!(pattern.name, Some("Glider".to_string()));
+        assert_eq!(pattern.world_size, Some((40, 30)));
+        assert_eq!(pattern.rule, Some("B3/S23".to_string()));
+        assert!(parse_strict(input).is_ok());
+
+        assert_eq!(
+            write(&pattern),
+            "!Name: Glider\n!Size: 40x30\n!Rule: B3/S23\n- # -\n- - #\n# # #\n"
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use super::{ParseError, Pattern};
+
+pub(crate) fn parse(input: &str) -> Result<Pattern, ParseError> {
+    let mut pattern = Pattern::default();
+    let mut y = 0;
+
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim_end();
+
+        if line.starts_with('!') {
+            continue;
+        }
+
+        for (x, c) in line.chars().enumerate() {
+            match c {
+                'O' | '*' => pattern.cells.push((x, y)),
+                '.' => {}
+                c => {
+                    return Err(ParseError::new(
+                        i + 1,
+                        format!("unexpected character: {:?}", c),
+                    ))
+                }
+            }
+        }
+
+        pattern.width = pattern.width.max(line.len());
+        y += 1;
+        pattern.height = y;
+    }
+
+    Ok(pattern)
+}
+
+pub(crate) fn write(pattern: &Pattern) -> String {
+    let mut grid = vec![vec!['.'; pattern.width]; pattern.height];
+    for (x, y) in &pattern.cells {
+        grid[*y][*x] = 'O';
+    }
+
+    let mut output = String::new();
+    for row in grid {
+        output.extend(row);
+        output.push('\n');
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let pattern = parse("!Name: Glider\n.O.\n..O\nOOO\n").unwrap();
+
+        assert_eq!(pattern.width, 3);
+        assert_eq!(pattern.height, 3);
+        assert_eq!(pattern.cells, vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        assert_eq!(write(&pattern), ".O.\n..O\nOOO\n");
+    }
+}
@@ -0,0 +1,204 @@
+use super::{ParseError, Pattern};
+
+const MAX_LINE_LENGTH: usize = 70;
+
+pub(crate) fn parse(input: &str) -> Result<Pattern, ParseError> {
+    let mut pattern = Pattern::default();
+    let (mut x, mut y) = (0, 0);
+    let mut count = String::new();
+
+    'lines: for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+        let line_number = i + 1;
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with('x') {
+            parse_header(line, line_number, &mut pattern)?;
+            continue;
+        }
+
+        for c in line.chars() {
+            let run = if c.is_ascii_digit() {
+                count.push(c);
+                continue;
+            } else if count.is_empty() {
+                1
+            } else {
+                count.parse().map_err(|_| {
+                    ParseError::new(line_number, format!("invalid run count: {}", count))
+                })?
+            };
+            count.clear();
+
+            match c {
+                'b' | '.' => x += run,
+                '$' => {
+                    x = 0;
+                    y += run;
+                }
+                '!' => break 'lines,
+                c if c.is_ascii_alphabetic() => {
+                    for _ in 0..run {
+                        pattern.cells.push((x, y));
+                        x += 1;
+                    }
+                }
+                c if c.is_whitespace() => {}
+                c => {
+                    return Err(ParseError::new(
+                        line_number,
+                        format!("unexpected character: {:?}", c),
+                    ))
+                }
+            }
+
+            pattern.width = pattern.width.max(x);
+            pattern.height = pattern.height.max(y + 1);
+        }
+    }
+
+    Ok(pattern)
+}
+
+fn parse_header(line: &str, line_number: usize, pattern: &mut Pattern) -> Result<(), ParseError> {
+    for field in line.split(',') {
+        let mut parts = field.splitn(2, '=');
+        let key = parts.next().unwrap_or("").trim();
+        let value = parts
+            .next()
+            .ok_or_else(|| {
+                ParseError::new(line_number, format!("invalid header field: {}", field))
+            })?
+            .trim();
+
+        match key {
+            "x" | "y" => {
+                let n = value.parse().map_err(|_| {
+                    ParseError::new(line_number, format!("invalid {}: {}", key, value))
+                })?;
+                if key == "x" {
+                    pattern.width = n;
+                } else {
+                    pattern.height = n;
+                }
+            }
+            "rule" => pattern.rule = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) fn write(pattern: &Pattern) -> String {
+    let mut output = format!("x = {}, y = {}", pattern.width, pattern.height);
+    if let Some(rule) = &pattern.rule {
+        output.push_str(&format!(", rule = {}", rule));
+    }
+    output.push('\n');
+
+    let mut grid = vec![vec![false; pattern.width]; pattern.height];
+    for (x, y) in &pattern.cells {
+        grid[*y][*x] = true;
+    }
+
+    let mut runs: Vec<(usize, char)> = Vec::new();
+    for (y, row) in grid.iter().enumerate() {
+        if y > 0 {
+            push_run(&mut runs, 1, '$');
+        }
+
+        let mut x = 0;
+        while x < row.len() {
+            let alive = row[x];
+            let length = row[x..].iter().take_while(|cell| **cell == alive).count();
+
+            // Trailing dead cells are implied by the end of line marker.
+            if alive || x + length < row.len() {
+                push_run(&mut runs, length, if alive { 'o' } else { 'b' });
+            }
+            x += length;
+        }
+    }
+
+    while runs.last().map(|(_, tag)| *tag) == Some('$') {
+        runs.pop();
+    }
+
+    let mut line = String::new();
+    for (length, tag) in runs {
+        let token = if length == 1 {
+            tag.to_string()
+        } else {
+            format!("{}{}", length, tag)
+        };
+
+        if line.len() + token.len() > MAX_LINE_LENGTH {
+            output.push_str(&line);
+            output.push('\n');
+            line.clear();
+        }
+        line.push_str(&token);
+    }
+    line.push('!');
+    output.push_str(&line);
+    output.push('\n');
+
+    output
+}
+
+/// Appends a run, merging it with the previous one when the tags match (e.g. blank rows).
+fn push_run(runs: &mut Vec<(usize, char)>, length: usize, tag: char) {
+    match runs.last_mut() {
+        Some((previous_length, previous_tag)) if *previous_tag == tag => *previous_length += length,
+        _ => runs.push((length, tag)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLIDER: &str = "#N Glider
+x = 3, y = 3, rule = B3/S23
+bob$2bo$3o!
+";
+
+    #[test]
+    fn test_parse() {
+        let pattern = parse(GLIDER).unwrap();
+
+        assert_eq!(pattern.width, 3);
+        assert_eq!(pattern.height, 3);
+        assert_eq!(pattern.rule, Some("B3/S23".to_string()));
+        assert_eq!(pattern.cells, vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn test_write() {
+        let pattern = parse(GLIDER).unwrap();
+
+        assert_eq!(write(&pattern), "x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n");
+        assert_eq!(parse(&write(&pattern)).unwrap(), pattern);
+    }
+
+    #[test]
+    fn test_blank_rows() {
+        let pattern = parse("x = 2, y = 4\no2$bo!").unwrap();
+
+        assert_eq!(pattern.cells, vec![(0, 0), (1, 2)]);
+        assert_eq!(pattern.height, 4);
+        assert_eq!(write(&pattern), "x = 2, y = 4\no2$bo!\n");
+    }
+
+    #[test]
+    fn test_invalid_character() {
+        assert_eq!(
+            parse("x = 1, y = 1\no?!"),
+            Err(ParseError::new(2, "unexpected character: '?'"))
+        );
+    }
+}
@@ -0,0 +1,377 @@
+//! Life run by a compute shader on the GPU, for worlds of millions of cells.
+
+use crate::boundary::Boundary;
+use crate::grid::{heap_bytes, Grid};
+use crate::rule::Neighbourhood;
+use crate::world::World;
+use std::borrow::Cow;
+use std::cell::{Ref, RefCell};
+use std::mem;
+use std::sync::mpsc;
+use wgpu::util::DeviceExt;
+
+/// Cells are moved on in 8x8 squares, one invocation per cell.
+const WORKGROUP_SIZE: u32 = 8;
+
+const SHADER: &str = "
+struct Params {
+    width: u32,
+    height: u32,
+    births: u32,
+    survivals: u32,
+    wrap: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> cells: array<u32>;
+@group(0) @binding(2) var<storage, read_write> next: array<u32>;
+
+fn alive(x: i32, y: i32) -> u32 {
+    let width = i32(params.width);
+    let height = i32(params.height);
+    if params.wrap == 1u {
+        return cells[u32((y + height) % height) * params.width + u32((x + width) % width)];
+    }
+    if x < 0 || y < 0 || x >= width || y >= height {
+        return 0u;
+    }
+    return cells[u32(y) * params.width + u32(x)];
+}
+
+@compute @workgroup_size(8, 8)
+fn step(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.width || id.y >= params.height {
+        return;
+    }
+
+    let x = i32(id.x);
+    let y = i32(id.y);
+    var count = 0u;
+    for (var dy = -1; dy <= 1; dy = dy + 1) {
+        for (var dx = -1; dx <= 1; dx = dx + 1) {
+            if dx != 0 || dy != 0 {
+                count = count + alive(x + dx, y + dy);
+            }
+        }
+    }
+
+    let i = id.y * params.width + id.x;
+    let rule = select(params.births, params.survivals, cells[i] == 1u);
+    next[i] = (rule >> count) & 1u;
+}
+";
+
+/// A world of two-state cells under a Moore neighbourhood rule, kept on the
+/// GPU as a `u32` per cell and moved on there. Cells are only copied back
+/// when they're looked at.
+#[derive(Debug)]
+pub struct GpuGrid {
+    width: usize,
+    height: usize,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    /// This generation's and next generation's cells, swapping places each
+    /// generation, with a bind group reading from each.
+    buffers: [wgpu::Buffer; 2],
+    bind_groups: [wgpu::BindGroup; 2],
+    current: usize,
+    /// The cells as they were last copied back, until they change.
+    cells: RefCell<Option<Vec<u32>>>,
+    generation: usize,
+}
+
+impl GpuGrid {
+    /// A grid on the first GPU found with the same size, rule, boundary and
+    /// live cells as `world`. Only the wrapping and dead boundaries are
+    /// supported.
+    pub fn from_world(world: &World) -> Result<Self, String> {
+        let rule = world.rule();
+        rule.check_two_state()?;
+        if rule.neighbourhood() != Neighbourhood::Moore || rule.radius() != 1 {
+            return Err(format!(
+                "{} doesn't count the eight cells around each cell",
+                rule
+            ));
+        }
+        let wrap = match world.boundary() {
+            Boundary::Wrap => 1,
+            Boundary::Dead => 0,
+            boundary => return Err(format!("the GPU can't run a {:?} boundary", boundary)),
+        };
+
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+        let adapter =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+                .ok_or("no GPU found")?;
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                required_features: wgpu::Features::empty(),
+                required_limits: adapter.limits(),
+            },
+            None,
+        ))
+        .map_err(|e| e.to_string())?;
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(SHADER)),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            layout: None,
+            module: &shader,
+            entry_point: "step",
+        });
+
+        // Uniforms are padded out to sixteen bytes.
+        let params = [
+            world.width as u32,
+            world.height as u32,
+            rule_mask(|count| rule.births(count)),
+            rule_mask(|count| rule.survives(count)),
+            wrap,
+            0,
+            0,
+            0,
+        ];
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: &to_bytes(&params),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let cells: Vec<u32> = world
+            .cells_with_coords()
+            .map(|(_, cell)| u32::from(cell.alive))
+            .collect();
+        let buffers = [(); 2].map(|_| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: &to_bytes(&cells),
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_SRC
+                    | wgpu::BufferUsages::COPY_DST,
+            })
+        });
+        let bind_groups = [0, 1].map(|current| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout: &pipeline.get_bind_group_layout(0),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: params.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: buffers[current].as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: buffers[1 - current].as_entire_binding(),
+                    },
+                ],
+            })
+        });
+
+        Ok(Self {
+            width: world.width,
+            height: world.height,
+            device,
+            queue,
+            pipeline,
+            buffers,
+            bind_groups,
+            current: 0,
+            cells: RefCell::new(Some(cells)),
+            generation: world.generation(),
+        })
+    }
+
+    /// Copies the cells back from the GPU, unless they already have been
+    /// since they last changed.
+    fn read_cells(&self) -> Ref<'_, Vec<u32>> {
+        if self.cells.borrow().is_none() {
+            let size = (self.width * self.height * 4) as wgpu::BufferAddress;
+            let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let mut encoder = self.device.create_command_encoder(&Default::default());
+            encoder.copy_buffer_to_buffer(&self.buffers[self.current], 0, &staging, 0, size);
+            self.queue.submit(Some(encoder.finish()));
+
+            let slice = staging.slice(..);
+            let (sender, receiver) = mpsc::channel();
+            slice.map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+            self.device.poll(wgpu::Maintain::Wait);
+            receiver
+                .recv()
+                .map_err(|e| e.to_string())
+                .and_then(|result| result.map_err(|e| e.to_string()))
+                .unwrap_or_else(|e| panic!("reading cells from the GPU: {}", e));
+
+            let cells = from_bytes(&slice.get_mapped_range());
+            staging.unmap();
+            *self.cells.borrow_mut() = Some(cells);
+        }
+
+        Ref::map(self.cells.borrow(), |cells| cells.as_ref().unwrap())
+    }
+}
+
+impl Grid for GpuGrid {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn generation(&self) -> usize {
+        self.generation
+    }
+
+    fn is_alive(&self, x: usize, y: usize) -> bool {
+        self.read_cells()[y * self.width + x] == 1
+    }
+
+    fn set_alive(&mut self, x: usize, y: usize, alive: bool) {
+        let i = y * self.width + x;
+        self.queue.write_buffer(
+            &self.buffers[self.current],
+            (i * 4) as wgpu::BufferAddress,
+            &to_bytes(&[u32::from(alive)]),
+        );
+        if let Some(cells) = self.cells.get_mut() {
+            cells[i] = u32::from(alive);
+        }
+    }
+
+    fn live_cells(&self) -> Vec<(usize, usize)> {
+        self.read_cells()
+            .iter()
+            .enumerate()
+            .filter(|(_, alive)| **alive == 1)
+            .map(|(i, _)| (i % self.width, i / self.width))
+            .collect()
+    }
+
+    fn population(&self) -> usize {
+        self.read_cells()
+            .iter()
+            .filter(|alive| **alive == 1)
+            .count()
+    }
+
+    /// Both generations' cells on the GPU, and the copy of them read back.
+    fn memory_usage(&self) -> usize {
+        let cells = self.width * self.height;
+        let read_back = self.cells.borrow().as_ref().map_or(0, Vec::capacity);
+        mem::size_of::<Self>() + heap_bytes::<u32>(2 * cells + read_back)
+    }
+
+    fn simulate(&mut self) {
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: None,
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &self.bind_groups[self.current], &[]);
+            pass.dispatch_workgroups(
+                (self.width as u32).div_ceil(WORKGROUP_SIZE),
+                (self.height as u32).div_ceil(WORKGROUP_SIZE),
+                1,
+            );
+        }
+        self.queue.submit(Some(encoder.finish()));
+
+        self.current = 1 - self.current;
+        *self.cells.get_mut() = None;
+        self.generation += 1;
+    }
+}
+
+/// The counts of live neighbours a rule says yes to, as bits of a word.
+fn rule_mask(f: impl Fn(u8) -> bool) -> u32 {
+    (0..=8)
+        .filter(|count| f(*count))
+        .map(|count| 1 << count)
+        .sum()
+}
+
+fn to_bytes(words: &[u32]) -> Vec<u8> {
+    words.iter().flat_map(|word| word.to_le_bytes()).collect()
+}
+
+fn from_bytes(bytes: &[u8]) -> Vec<u32> {
+    bytes
+        .chunks_exact(4)
+        .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::Rule;
+
+    #[test]
+    fn test_matches_world() {
+        for boundary in &[Boundary::Wrap, Boundary::Dead] {
+            let mut world = World::new(70, 20);
+            world.set_boundary(*boundary);
+            world.seed_from_string(
+                "- # # - -
+                 # # - - #
+                 - # - - -"
+                    .to_string(),
+                (65, 0),
+            );
+            // Without a GPU there's nothing to test.
+            let mut grid = match GpuGrid::from_world(&world) {
+                Ok(grid) => grid,
+                Err(_) => return,
+            };
+
+            grid.set_alive(10, 10, true);
+            world.toggle_cell(10, 10);
+            for _ in 0..10 {
+                world.simulate();
+                grid.simulate();
+            }
+            let mut cells = grid.live_cells();
+            cells.sort_by_key(|&(x, y)| (y, x));
+            assert_eq!(
+                cells,
+                world.live_cells().collect::<Vec<_>>(),
+                "{:?}",
+                boundary
+            );
+            assert_eq!(grid.generation(), 10);
+        }
+    }
+
+    #[test]
+    fn test_unsupported_worlds() {
+        let mut world = World::new(5, 5);
+        world.set_rule("briansbrain".parse().unwrap());
+        assert!(GpuGrid::from_world(&world).is_err());
+
+        let mut world = World::new(5, 5);
+        world.set_boundary(Boundary::Mirror);
+        assert!(GpuGrid::from_world(&world).is_err());
+    }
+
+    #[test]
+    fn test_rule_mask() {
+        let rule = Rule::conway();
+        assert_eq!(rule_mask(|count| rule.births(count)), 0b1000);
+        assert_eq!(rule_mask(|count| rule.survives(count)), 0b1100);
+    }
+}
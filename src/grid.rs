@@ -0,0 +1,260 @@
+//! The interface shared by the ways of storing a world's cells, so seeding,
+//! editing and drawing don't depend on which one is in use.
+
+use crate::bitpacked::BitWorld;
+use crate::formats::{Pattern, SeedError};
+#[cfg(feature = "gpu")]
+use crate::gpu::GpuGrid;
+use crate::hashlife::QuadtreeGrid;
+use crate::sparse::SparseWorld;
+use crate::world::World;
+use std::fmt;
+use std::mem;
+use std::str::FromStr;
+
+/// A world's cells and how they move on. `World` is the full-featured one;
+/// the others trade features for speed or space on particular workloads.
+/// Implement it to run a world some other way.
+pub trait Grid {
+    /// The width and height of the grid, in cells.
+    fn size(&self) -> (usize, usize);
+
+    fn generation(&self) -> usize;
+
+    fn is_alive(&self, x: usize, y: usize) -> bool;
+
+    fn set_alive(&mut self, x: usize, y: usize, alive: bool);
+
+    /// The coordinates of every live cell, in no particular order.
+    fn live_cells(&self) -> Vec<(usize, usize)>;
+
+    /// The number of live cells.
+    fn population(&self) -> usize {
+        self.live_cells().len()
+    }
+
+    /// Moves on a generation.
+    fn simulate(&mut self);
+
+    /// Roughly how many bytes the grid takes, to compare engines by.
+    fn memory_usage(&self) -> usize;
+
+    fn toggle(&mut self, x: usize, y: usize) {
+        let alive = self.is_alive(x, y);
+        self.set_alive(x, y, !alive);
+    }
+
+    /// Births the pattern's cells with its top-left corner at `(x, y)`,
+    /// leaving the grid untouched if any of them would fall outside it.
+    fn seed_pattern_at(&mut self, pattern: &Pattern, x: usize, y: usize) -> Result<(), SeedError> {
+        let (width, height) = self.size();
+        if pattern
+            .cells
+            .iter()
+            .any(|(cell_x, cell_y)| x + cell_x >= width || y + cell_y >= height)
+        {
+            return Err(SeedError::TooLarge {
+                pattern_size: (x + pattern.width, y + pattern.height),
+                world_size: (width, height),
+            });
+        }
+
+        for (cell_x, cell_y) in &pattern.cells {
+            self.set_alive(x + cell_x, y + cell_y, true);
+        }
+        Ok(())
+    }
+
+    fn to_pattern(&self) -> Pattern {
+        let (width, height) = self.size();
+        Pattern {
+            width,
+            height,
+            cells: self.live_cells(),
+            ..Pattern::default()
+        }
+    }
+}
+
+impl Grid for World {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn generation(&self) -> usize {
+        World::generation(self)
+    }
+
+    fn is_alive(&self, x: usize, y: usize) -> bool {
+        self.cell(x, y).alive
+    }
+
+    fn set_alive(&mut self, x: usize, y: usize, alive: bool) {
+        World::set_alive(self, x, y, alive).unwrap_or_else(|e| panic!("{}", e));
+    }
+
+    fn live_cells(&self) -> Vec<(usize, usize)> {
+        World::live_cells(self).collect()
+    }
+
+    fn population(&self) -> usize {
+        World::population(self)
+    }
+
+    fn simulate(&mut self) {
+        World::simulate(self)
+    }
+
+    fn memory_usage(&self) -> usize {
+        World::memory_usage(self)
+    }
+
+    fn seed_pattern_at(&mut self, pattern: &Pattern, x: usize, y: usize) -> Result<(), SeedError> {
+        World::seed_pattern_at(self, pattern, x, y)
+    }
+
+    fn to_pattern(&self) -> Pattern {
+        World::to_pattern(self)
+    }
+}
+
+/// The bytes a `Vec` or boxed slice with room for `capacity` items takes
+/// on the heap.
+pub(crate) fn heap_bytes<T>(capacity: usize) -> usize {
+    capacity * mem::size_of::<T>()
+}
+
+/// Roughly the bytes a hash set or map with room for `capacity` entries
+/// takes on the heap: the entries, and a control byte for each.
+pub(crate) fn hash_bytes<T>(capacity: usize) -> usize {
+    capacity * (mem::size_of::<T>() + 1)
+}
+
+/// The built-in grids, by name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Engine {
+    /// `World`, which supports every rule, boundary and feature.
+    Dense,
+    /// `SparseWorld`, for big worlds that are mostly empty.
+    Sparse,
+    /// `BitWorld`, for big, busy worlds.
+    Bitpacked,
+    /// HashLife on a quadtree, for big, repetitive worlds.
+    Quadtree,
+    /// `GpuGrid`, for huge worlds, when built with the `gpu` feature.
+    Gpu,
+}
+
+impl Engine {
+    pub const NAMES: [&'static str; 5] = ["dense", "sparse", "bitpacked", "quadtree", "gpu"];
+
+    /// A grid with the same cells as `world`, or why this engine can't run
+    /// it.
+    pub fn build(self, world: &World) -> Result<Box<dyn Grid>, String> {
+        Ok(match self {
+            Engine::Dense => Box::new(world.clone()),
+            Engine::Sparse => Box::new(SparseWorld::from_world(world)?),
+            Engine::Bitpacked => Box::new(BitWorld::from_world(world)?),
+            Engine::Quadtree => Box::new(QuadtreeGrid::from_world(world)?),
+            #[cfg(feature = "gpu")]
+            Engine::Gpu => Box::new(GpuGrid::from_world(world)?),
+            #[cfg(not(feature = "gpu"))]
+            Engine::Gpu => return Err("built without the gpu feature".to_string()),
+        })
+    }
+}
+
+impl FromStr for Engine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dense" => Ok(Engine::Dense),
+            "sparse" => Ok(Engine::Sparse),
+            "bitpacked" => Ok(Engine::Bitpacked),
+            "quadtree" => Ok(Engine::Quadtree),
+            "gpu" => Ok(Engine::Gpu),
+            _ => Err(format!("unknown engine: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Engine::Dense => "dense",
+            Engine::Sparse => "sparse",
+            Engine::Bitpacked => "bitpacked",
+            Engine::Quadtree => "quadtree",
+            Engine::Gpu => "gpu",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::boundary::Boundary;
+
+    #[test]
+    fn test_engines_agree() {
+        let mut world = World::new(32, 32);
+        world.set_boundary(Boundary::Dead);
+        world.seed_from_string(
+            "- # # - - - - -
+             # # - - - - # -
+             - # - - - - - #
+             - - - - - # # #"
+                .to_string(),
+            (10, 10),
+        );
+
+        let mut expected = world.clone();
+        for _ in 0..12 {
+            expected.simulate();
+        }
+        let mut expected: Vec<_> = expected.live_cells().collect();
+        expected.sort();
+
+        for name in &Engine::NAMES {
+            let engine: Engine = name.parse().unwrap();
+            assert_eq!(engine.to_string(), *name);
+
+            let mut grid = match engine.build(&world) {
+                Ok(grid) => grid,
+                // Not every build or machine has a GPU to run on.
+                Err(_) if engine == Engine::Gpu => continue,
+                Err(e) => panic!("{}: {}", engine, e),
+            };
+            for _ in 0..12 {
+                grid.simulate();
+            }
+            let mut cells = grid.live_cells();
+            cells.sort();
+            assert_eq!(cells, expected, "{}", engine);
+            assert_eq!(grid.generation(), 12);
+        }
+    }
+
+    #[test]
+    fn test_memory_usage() {
+        let mut world = World::new(256, 256);
+        world.set_boundary(Boundary::Dead);
+        world.seed_from_string("- # -\n- - #\n# # #".to_string(), (10, 10));
+
+        let usage = |engine: Engine| engine.build(&world).unwrap().memory_usage();
+        let dense = usage(Engine::Dense);
+        assert!(dense > 256 * 256, "{}", dense);
+        // One bit a cell.
+        assert!(usage(Engine::Bitpacked) < dense / 8);
+        // Only the live cells.
+        assert!(usage(Engine::Sparse) < 1024);
+        assert!(usage(Engine::Quadtree) < dense / 8);
+    }
+
+    #[test]
+    fn test_unknown_engine() {
+        assert!("abacus".parse::<Engine>().is_err());
+    }
+}
@@ -0,0 +1,487 @@
+//! HashLife: Bill Gosper's algorithm for running Life on a quadtree by
+//! remembering what every node it has seen turns into, so repetitive
+//! patterns can be moved on millions of generations at a time.
+
+use crate::boundary::Boundary;
+use crate::grid::{hash_bytes, Grid};
+use crate::quadtree::{Node, NodeId, Quadtree};
+use crate::rule::{Neighbourhood, Rule};
+use crate::world::World;
+use std::array;
+use std::collections::HashMap;
+use std::mem;
+
+/// An unbounded world of two-state cells under a Moore neighbourhood rule,
+/// moved on a power of two generations at a time.
+#[derive(Clone, Debug)]
+pub struct HashLife {
+    tree: Quadtree,
+    /// Whether a dead or live cell with each number of live neighbours is
+    /// alive next generation.
+    births: [bool; 9],
+    survivals: [bool; 9],
+    /// The middle half of each node `2^j` generations on, keyed by the node
+    /// and `j`.
+    results: HashMap<(NodeId, u8), NodeId>,
+    /// How many nodes the tree can build up before the ones it's no longer
+    /// using are dropped, along with every result.
+    node_limit: usize,
+    generation: u64,
+}
+
+/// The fewest nodes the tree is left to build up before collecting
+/// garbage, so small patterns don't spend their time collecting it.
+const MIN_NODE_LIMIT: usize = 1 << 16;
+
+impl HashLife {
+    pub fn new(rule: Rule) -> Result<Self, String> {
+        rule.check_two_state()?;
+
+        if rule.neighbourhood() != Neighbourhood::Moore || rule.radius() != 1 {
+            Err(format!(
+                "{} doesn't count the eight cells around each cell",
+                rule
+            ))
+        } else if rule.births(0) {
+            Err(format!("{} births cells out of nothing, everywhere", rule))
+        } else {
+            Ok(Self {
+                tree: Quadtree::new(),
+                births: array::from_fn(|count| rule.births(count as u8)),
+                survivals: array::from_fn(|count| rule.survives(count as u8)),
+                results: HashMap::new(),
+                node_limit: MIN_NODE_LIMIT,
+                generation: 0,
+            })
+        }
+    }
+
+    /// A universe with the world's rule and live cells, at the same
+    /// coordinates.
+    pub fn from_world(world: &World) -> Result<Self, String> {
+        let mut hashlife = Self::new(world.rule())?;
+        hashlife.tree = Quadtree::from_world(world);
+        hashlife.generation = world.generation() as u64;
+        Ok(hashlife)
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn population(&self) -> u64 {
+        self.tree.population()
+    }
+
+    /// Roughly how many bytes the tree and the results worked out on it
+    /// take.
+    pub fn memory_usage(&self) -> usize {
+        mem::size_of::<Self>() - mem::size_of::<Quadtree>()
+            + self.tree.memory_usage()
+            + hash_bytes::<((NodeId, u8), NodeId)>(self.results.capacity())
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> bool {
+        self.tree.get(x, y)
+    }
+
+    pub fn set(&mut self, x: i64, y: i64, alive: bool) {
+        self.tree.set(x, y, alive);
+    }
+
+    pub fn live_cells_in(&self, corner: (i64, i64), size: (i64, i64)) -> Vec<(i64, i64)> {
+        self.tree.live_cells_in(corner, size)
+    }
+
+    /// Moves on one generation.
+    pub fn step(&mut self) {
+        self.advance(0);
+    }
+
+    /// Moves on `2^log2` generations in one go.
+    pub fn advance(&mut self, log2: u8) {
+        // Nothing can travel faster than a cell a generation, so with the
+        // pattern in the middle quarter of a tree at least 2^(log2 + 3)
+        // wide, everything stays inside the middle half that's worked out.
+        while self.tree.level() < log2 + 3 || !self.is_centred() {
+            self.tree.expand();
+        }
+
+        let level = self.tree.level();
+        let (x, y) = self.tree.origin();
+        let quarter = 1 << (level - 2);
+        let root = self.successor(self.tree.root(), log2);
+        self.tree.set_root(root, (x + quarter, y + quarter));
+        self.generation += 1 << log2;
+
+        if self.tree.node_count() > self.node_limit {
+            self.collect_garbage();
+        }
+    }
+
+    /// Drops the nodes that aren't in the tree any more, and with them
+    /// every result, since they're keyed by the old nodes. The tree can then
+    /// grow to twice what's left before it happens again.
+    fn collect_garbage(&mut self) {
+        self.results = HashMap::new();
+        self.tree.collect_garbage();
+        self.node_limit = (2 * self.tree.node_count()).max(MIN_NODE_LIMIT);
+    }
+
+    /// Whether all the live cells are in the middle quarter of the tree.
+    fn is_centred(&self) -> bool {
+        let [nw, ne, sw, se] = self.tree.children(self.tree.root());
+        let middle: u64 = [(nw, 3), (ne, 2), (sw, 1), (se, 0)]
+            .iter()
+            .map(|(child, corner)| {
+                let grandchild = self.tree.children(*child)[*corner];
+                self.tree
+                    .node(self.tree.children(grandchild)[*corner])
+                    .population()
+            })
+            .sum();
+        middle == self.tree.population()
+    }
+
+    /// The middle half of a node at least four cells wide, `2^log2`
+    /// generations on. `log2` can be at most two less than the node's
+    /// level.
+    fn successor(&mut self, id: NodeId, log2: u8) -> NodeId {
+        if let Some(result) = self.results.get(&(id, log2)) {
+            return *result;
+        }
+
+        let node = self.tree.node(id);
+        let result = if node.population() == 0 {
+            self.tree.empty(node.level() - 1)
+        } else if node.level() == 2 {
+            self.successor_of_four(id)
+        } else {
+            self.successor_of_branch(id, node.level(), log2)
+        };
+
+        self.results.insert((id, log2), result);
+        result
+    }
+
+    /// Splits the node into nine overlapping squares half its size and
+    /// moves each of them on, then puts the results together into four
+    /// squares and moves those on, giving the middle. Going the full
+    /// `2^(level - 2)` generations takes both steps; going fewer takes the
+    /// middle of the nine without moving them on.
+    fn successor_of_branch(&mut self, id: NodeId, level: u8, log2: u8) -> NodeId {
+        let [nw, ne, sw, se] = self.tree.children(id);
+        let [_, nw_ne, nw_sw, nw_se] = self.tree.children(nw);
+        let [ne_nw, _, ne_sw, ne_se] = self.tree.children(ne);
+        let [sw_nw, sw_ne, _, sw_se] = self.tree.children(sw);
+        let [se_nw, se_ne, se_sw, _] = self.tree.children(se);
+
+        let nine = [
+            nw,
+            self.tree.branch([nw_ne, ne_nw, nw_se, ne_sw]),
+            ne,
+            self.tree.branch([nw_sw, nw_se, sw_nw, sw_ne]),
+            self.tree.branch([nw_se, ne_sw, sw_ne, se_nw]),
+            self.tree.branch([ne_sw, ne_se, se_nw, se_ne]),
+            sw,
+            self.tree.branch([sw_ne, se_nw, sw_se, se_sw]),
+            se,
+        ];
+
+        let full_speed = log2 == level - 2;
+        let mut moved = [0; 9];
+        for (moved, square) in moved.iter_mut().zip(&nine) {
+            *moved = if full_speed {
+                self.successor(*square, log2 - 1)
+            } else {
+                self.middle(*square)
+            };
+        }
+
+        let four = [
+            self.tree.branch([moved[0], moved[1], moved[3], moved[4]]),
+            self.tree.branch([moved[1], moved[2], moved[4], moved[5]]),
+            self.tree.branch([moved[3], moved[4], moved[6], moved[7]]),
+            self.tree.branch([moved[4], moved[5], moved[7], moved[8]]),
+        ];
+        let log2 = if full_speed { log2 - 1 } else { log2 };
+        let mut children = [0; 4];
+        for (child, square) in children.iter_mut().zip(&four) {
+            *child = self.successor(*square, log2);
+        }
+        self.tree.branch(children)
+    }
+
+    /// The middle 2x2 cells of a 4x4 node, a generation on.
+    fn successor_of_four(&mut self, id: NodeId) -> NodeId {
+        let mut cells = [[false; 4]; 4];
+        for (i, child) in self.tree.children(id).iter().enumerate() {
+            for (j, leaf) in self.tree.children(*child).iter().enumerate() {
+                let (x, y) = (i % 2 * 2 + j % 2, i / 2 * 2 + j / 2);
+                cells[y][x] = self.tree.node(*leaf) == Node::Leaf(true);
+            }
+        }
+
+        let mut next = [0; 4];
+        for (i, leaf) in next.iter_mut().enumerate() {
+            let (x, y) = (1 + i % 2, 1 + i / 2);
+            let live_neighbours = (y - 1..=y + 1)
+                .flat_map(|ny| (x - 1..=x + 1).map(move |nx| (nx, ny)))
+                .filter(|&(nx, ny)| (nx, ny) != (x, y) && cells[ny][nx])
+                .count();
+            let alive = if cells[y][x] {
+                self.survivals[live_neighbours]
+            } else {
+                self.births[live_neighbours]
+            };
+            *leaf = self.tree.leaf(alive);
+        }
+        self.tree.branch(next)
+    }
+
+    /// The middle half of a node, as it is.
+    fn middle(&mut self, id: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.tree.children(id);
+        let children = [
+            self.tree.children(nw)[3],
+            self.tree.children(ne)[2],
+            self.tree.children(sw)[1],
+            self.tree.children(se)[0],
+        ];
+        self.tree.branch(children)
+    }
+}
+
+/// HashLife as a `Grid` the size of a world with dead edges. Cells born
+/// past the edges are cleared away each generation, so they never affect
+/// the ones inside.
+#[derive(Clone, Debug)]
+pub struct QuadtreeGrid {
+    hashlife: HashLife,
+    width: usize,
+    height: usize,
+}
+
+impl QuadtreeGrid {
+    /// A grid with the same size, rule and live cells as `world`, which
+    /// must have the dead boundary: cells can't wrap or reflect around a
+    /// quadtree.
+    pub fn from_world(world: &World) -> Result<Self, String> {
+        if world.boundary() != Boundary::Dead {
+            return Err(format!(
+                "the quadtree engine can't run a {:?} boundary",
+                world.boundary()
+            ));
+        }
+
+        Ok(Self {
+            hashlife: HashLife::from_world(world)?,
+            width: world.width,
+            height: world.height,
+        })
+    }
+
+    /// Kills the cells just past the edges. One generation on from a world
+    /// with none past them, that's as far as any can have been born.
+    fn clear_edges(&mut self) {
+        let (width, height) = (self.width as i64, self.height as i64);
+        let strips = [
+            ((-1, -1), (width + 2, 1)),
+            ((-1, height), (width + 2, 1)),
+            ((-1, 0), (1, height)),
+            ((width, 0), (1, height)),
+        ];
+        for (corner, size) in &strips {
+            for (x, y) in self.hashlife.live_cells_in(*corner, *size) {
+                self.hashlife.set(x, y, false);
+            }
+        }
+    }
+}
+
+impl Grid for QuadtreeGrid {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn generation(&self) -> usize {
+        self.hashlife.generation() as usize
+    }
+
+    fn is_alive(&self, x: usize, y: usize) -> bool {
+        self.hashlife.get(x as i64, y as i64)
+    }
+
+    fn set_alive(&mut self, x: usize, y: usize, alive: bool) {
+        self.hashlife.set(x as i64, y as i64, alive);
+    }
+
+    fn live_cells(&self) -> Vec<(usize, usize)> {
+        self.hashlife
+            .live_cells_in((0, 0), (self.width as i64, self.height as i64))
+            .into_iter()
+            .map(|(x, y)| (x as usize, y as usize))
+            .collect()
+    }
+
+    fn simulate(&mut self) {
+        self.hashlife.step();
+        self.clear_edges();
+    }
+
+    fn population(&self) -> usize {
+        self.hashlife.population() as usize
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.hashlife.memory_usage()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glider() -> HashLife {
+        let mut hashlife = HashLife::new(Rule::conway()).unwrap();
+        for (x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            hashlife.set(*x, *y, true);
+        }
+        hashlife
+    }
+
+    fn sorted(mut cells: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn test_step_matches_world() {
+        let mut world = World::new(40, 40);
+        world.set_boundary(Boundary::Dead);
+        world.seed_from_string(
+            "- # # - -
+             # # - - -
+             - # - - -"
+                .to_string(),
+            (18, 18),
+        );
+        let mut hashlife = HashLife::from_world(&world).unwrap();
+
+        for _ in 0..30 {
+            world.simulate();
+            hashlife.step();
+        }
+
+        let expected: Vec<_> = world
+            .live_cells()
+            .map(|(x, y)| (x as i64, y as i64))
+            .collect();
+        assert_eq!(
+            sorted(hashlife.live_cells_in((0, 0), (40, 40))),
+            sorted(expected)
+        );
+        assert_eq!(hashlife.population(), world.live_cells().count() as u64);
+        assert_eq!(hashlife.generation(), 30);
+    }
+
+    #[test]
+    fn test_advance() {
+        let mut stepped = glider();
+        for _ in 0..64 {
+            stepped.step();
+        }
+
+        let mut advanced = glider();
+        advanced.advance(6);
+        assert_eq!(advanced.generation(), 64);
+        assert_eq!(
+            sorted(advanced.live_cells_in((0, 0), (30, 30))),
+            sorted(stepped.live_cells_in((0, 0), (30, 30)))
+        );
+
+        // A glider moves a cell diagonally every four generations, however
+        // far it goes.
+        let mut far = glider();
+        far.advance(20);
+        let moved = 1 << 18;
+        assert_eq!(
+            sorted(far.live_cells_in((moved, moved), (3, 3))),
+            vec![
+                (moved, moved + 2),
+                (moved + 1, moved),
+                (moved + 1, moved + 2),
+                (moved + 2, moved + 1),
+                (moved + 2, moved + 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quadtree_grid_matches_world() {
+        for rule in &["B3/S23", "B36/S23", "B2/S"] {
+            let mut world = World::new(30, 13);
+            world.set_rule(rule.parse().unwrap());
+            world.set_boundary(Boundary::Dead);
+            world.seed_random();
+            let mut grid = QuadtreeGrid::from_world(&world).unwrap();
+
+            for generation in 1..=30 {
+                world.simulate();
+                grid.simulate();
+                let mut cells = grid.live_cells();
+                cells.sort_by_key(|&(x, y)| (y, x));
+                let expected: Vec<_> = world.live_cells().collect();
+                assert_eq!(cells, expected, "{} at generation {}", rule, generation);
+                assert_eq!(grid.population(), world.population());
+            }
+        }
+    }
+
+    #[test]
+    fn test_memory_stays_bounded() {
+        let mut world = World::new(64, 64);
+        world.set_boundary(Boundary::Dead);
+        world.seed_random();
+        let mut grid = QuadtreeGrid::from_world(&world).unwrap();
+
+        // Without collecting garbage, this keeps every node ever built, well
+        // past 30MB by the end.
+        for generation in 1..=1500 {
+            grid.simulate();
+            assert!(
+                grid.memory_usage() < 16 << 20,
+                "{} bytes at generation {}",
+                grid.memory_usage(),
+                generation
+            );
+        }
+        for _ in 0..1500 {
+            world.simulate();
+        }
+        let mut cells = grid.live_cells();
+        cells.sort_by_key(|&(x, y)| (y, x));
+        assert_eq!(cells, world.live_cells().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_quadtree_grid_boundaries() {
+        for boundary in &[
+            Boundary::Wrap,
+            Boundary::Mirror,
+            Boundary::Klein,
+            Boundary::CrossSurface,
+        ] {
+            let mut world = World::new(10, 10);
+            world.set_boundary(*boundary);
+            assert!(QuadtreeGrid::from_world(&world).is_err(), "{:?}", boundary);
+        }
+    }
+
+    #[test]
+    fn test_unsupported_rules() {
+        assert!(HashLife::new("B0/S8".parse().unwrap()).is_err());
+        assert!(HashLife::new("B3/S23V".parse().unwrap()).is_err());
+        assert!(HashLife::new("briansbrain".parse().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,103 @@
+//! The generations a world has been through, to step back through how
+//! something formed.
+
+use crate::world::World;
+use std::collections::VecDeque;
+
+/// The most recent generations of a world, oldest first, dropping the
+/// oldest whenever they take up more than a set number of bytes between
+/// them.
+#[derive(Clone, Default)]
+pub struct History {
+    worlds: VecDeque<World>,
+    /// What `worlds` takes up, by `World::memory_usage`.
+    bytes: usize,
+    max_bytes: usize,
+}
+
+impl History {
+    /// An empty history that keeps no more than `max_bytes` of generations.
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            ..Self::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.worlds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.worlds.is_empty()
+    }
+
+    /// Roughly how many bytes the generations kept take up.
+    pub fn memory_usage(&self) -> usize {
+        self.bytes
+    }
+
+    /// Keeps `world` as the newest generation, dropping the oldest ones
+    /// until they fit. A generation bigger than the cap on its own isn't
+    /// kept at all.
+    pub fn push(&mut self, world: World) {
+        self.bytes += world.memory_usage();
+        self.worlds.push_back(world);
+        while self.bytes > self.max_bytes {
+            match self.worlds.pop_front() {
+                Some(oldest) => self.bytes -= oldest.memory_usage(),
+                None => break,
+            }
+        }
+    }
+
+    /// Takes back the newest generation.
+    pub fn pop(&mut self) -> Option<World> {
+        let world = self.worlds.pop_back()?;
+        self.bytes -= world.memory_usage();
+        Some(world)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_and_pop() {
+        let mut world = World::new(10, 10);
+        world.seed_from_string("- # -\n- # -\n- # -".to_string(), (3, 3));
+        let mut history = History::new(usize::MAX);
+
+        for _ in 0..3 {
+            history.push(world.clone());
+            world.simulate();
+        }
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.pop().unwrap().generation(), 2);
+        assert_eq!(history.pop().unwrap().generation(), 1);
+        assert_eq!(history.pop().unwrap().generation(), 0);
+        assert!(history.pop().is_none());
+        assert_eq!(history.memory_usage(), 0);
+    }
+
+    #[test]
+    fn test_memory_cap() {
+        let mut world = World::new(10, 10);
+        let bytes = world.memory_usage();
+        let mut history = History::new(bytes * 3);
+
+        for _ in 0..5 {
+            history.push(world.clone());
+            world.simulate();
+        }
+        // Only the newest three fit.
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.memory_usage(), bytes * 3);
+        assert_eq!(history.pop().unwrap().generation(), 4);
+
+        let mut history = History::new(bytes - 1);
+        history.push(world);
+        assert!(history.is_empty());
+    }
+}
@@ -0,0 +1,170 @@
+//! Converting images into patterns, and window buffers into images.
+
+use crate::formats::{Pattern, SeedError};
+use crate::window_buffer::WindowBuffer;
+use std::io::{Read, Write};
+
+/// Decodes a PNG into a pattern where every pixel at least as bright as `threshold` is alive.
+/// Images larger than `max_width` x `max_height` are downscaled to fit by averaging blocks of
+/// pixels.
+pub fn pattern_from_png(
+    reader: impl Read,
+    max_width: usize,
+    max_height: usize,
+    threshold: u8,
+) -> Result<Pattern, SeedError> {
+    let (info, mut reader) = png::Decoder::new(reader).read_info()?;
+    let mut buffer = vec![0; info.buffer_size()];
+    reader.next_frame(&mut buffer)?;
+
+    let width = info.width as usize;
+    let height = info.height as usize;
+    let luminance = luminance(&buffer, &info);
+
+    let scale = 1
+        .max(width.div_ceil(max_width))
+        .max(height.div_ceil(max_height));
+    let mut pattern = Pattern {
+        width: width.div_ceil(scale),
+        height: height.div_ceil(scale),
+        ..Pattern::default()
+    };
+
+    for y in 0..pattern.height {
+        for x in 0..pattern.width {
+            let block: Vec<u32> = (y * scale..height.min((y + 1) * scale))
+                .flat_map(|py| (x * scale..width.min((x + 1) * scale)).map(move |px| (px, py)))
+                .map(|(px, py)| u32::from(luminance[py * width + px]))
+                .collect();
+            let average = block.iter().sum::<u32>() / block.len() as u32;
+
+            if average >= u32::from(threshold) {
+                pattern.cells.push((x, y));
+            }
+        }
+    }
+
+    Ok(pattern)
+}
+
+/// Encodes a window buffer as an RGB PNG, drawing each pixel as a `scale` x `scale` block.
+pub fn write_png(
+    writer: impl Write,
+    buffer: &WindowBuffer,
+    scale: usize,
+) -> Result<(), png::EncodingError> {
+    let width = buffer.width() * scale;
+    let height = buffer.height() * scale;
+
+    let mut encoder = png::Encoder::new(writer, width as u32, height as u32);
+    encoder.set_color(png::ColorType::RGB);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut data = Vec::with_capacity(width * height * 3);
+    for row in buffer.buffer.chunks(buffer.width()) {
+        let mut line = Vec::with_capacity(width * 3);
+        for color in row {
+            for _ in 0..scale {
+                line.extend(&[(color >> 16) as u8, (color >> 8) as u8, *color as u8]);
+            }
+        }
+        for _ in 0..scale {
+            data.extend(&line);
+        }
+    }
+
+    encoder.write_header()?.write_image_data(&data)
+}
+
+/// Converts decoded PNG rows into one 8-bit luminance value per pixel.
+fn luminance(buffer: &[u8], info: &png::OutputInfo) -> Vec<u8> {
+    let bytes_per_sample = if info.bit_depth == png::BitDepth::Sixteen {
+        2
+    } else {
+        1
+    };
+    let samples = info.color_type.samples();
+    let bytes_per_pixel = samples * bytes_per_sample;
+
+    buffer
+        .chunks(info.line_size)
+        .flat_map(|row| row[..info.width as usize * bytes_per_pixel].chunks(bytes_per_pixel))
+        .map(|pixel| {
+            // Only the most significant byte of 16-bit samples matters here.
+            let sample = |i: usize| u32::from(pixel[i * bytes_per_sample]);
+
+            if samples >= 3 {
+                ((299 * sample(0) + 587 * sample(1) + 114 * sample(2)) / 1000) as u8
+            } else {
+                sample(0) as u8
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(width: u32, height: u32, color: png::ColorType, data: &[u8]) -> Vec<u8> {
+        let mut output = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut output, width, height);
+            encoder.set_color(color);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(data).unwrap();
+        }
+        output
+    }
+
+    #[test]
+    fn test_threshold() {
+        #[rustfmt::skip]
+        let png = encode(3, 2, png::ColorType::RGB, &[
+            255, 255, 255,   0, 0, 0,   255, 0, 0,
+              0,   0,   0,   0, 0, 0,   200, 200, 200,
+        ]);
+
+        let pattern = pattern_from_png(png.as_slice(), 10, 10, 128).unwrap();
+
+        assert_eq!(pattern.width, 3);
+        assert_eq!(pattern.height, 2);
+        assert_eq!(pattern.cells, vec![(0, 0), (2, 1)]);
+    }
+
+    #[test]
+    fn test_write_png() {
+        let mut buffer = WindowBuffer::new(2, 1);
+        buffer.set_pixel(1, 0, 0xff0000);
+
+        let mut output = Vec::new();
+        write_png(&mut output, &buffer, 2).unwrap();
+
+        let (info, mut reader) = png::Decoder::new(output.as_slice()).read_info().unwrap();
+        let mut data = vec![0; info.buffer_size()];
+        reader.next_frame(&mut data).unwrap();
+
+        assert_eq!((info.width, info.height), (4, 2));
+        #[rustfmt::skip]
+        assert_eq!(data, vec![
+            0, 0, 0,   0, 0, 0,   255, 0, 0,   255, 0, 0,
+            0, 0, 0,   0, 0, 0,   255, 0, 0,   255, 0, 0,
+        ]);
+    }
+
+    #[test]
+    fn test_downscale() {
+        #[rustfmt::skip]
+        let png = encode(4, 2, png::ColorType::Grayscale, &[
+            255, 255,   0, 255,
+            255,   0,   0,   0,
+        ]);
+
+        let pattern = pattern_from_png(png.as_slice(), 2, 2, 128).unwrap();
+
+        assert_eq!(pattern.width, 2);
+        assert_eq!(pattern.height, 1);
+        assert_eq!(pattern.cells, vec![(0, 0)]);
+    }
+}
@@ -0,0 +1,281 @@
+//! An unbounded world, stored as square chunks of cells which are only
+//! allocated while something is alive in them.
+
+use crate::grid::{hash_bytes, heap_bytes};
+use crate::rule::{NeighbourhoodShape, Rule};
+use crate::world::World;
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+/// The width and height of a chunk, in cells.
+pub const CHUNK_SIZE: i64 = 32;
+
+type Chunk = Box<[bool]>;
+
+/// A world without edges for two-state rules, so gliders and growing
+/// patterns never hit a wall. Cells are addressed with signed coordinates.
+#[derive(Clone, Debug)]
+pub struct InfiniteWorld {
+    chunks: HashMap<(i64, i64), Chunk>,
+    rule: Rule,
+    pub generation: usize,
+}
+
+impl InfiniteWorld {
+    pub fn new(rule: Rule) -> Result<Self, String> {
+        let (x_reach, y_reach) = reach(&rule);
+
+        rule.check_two_state()?;
+
+        if rule.births(0) {
+            Err(format!("{} births cells out of nothing, everywhere", rule))
+        } else if x_reach > CHUNK_SIZE || y_reach > CHUNK_SIZE {
+            Err(format!("{} reaches further than a chunk", rule))
+        } else {
+            Ok(Self {
+                chunks: HashMap::new(),
+                rule,
+                generation: 0,
+            })
+        }
+    }
+
+    /// An infinite world with the same rule and live cells as `world`.
+    pub fn from_world(world: &World) -> Result<Self, String> {
+        let mut infinite = Self::new(world.rule())?;
+        for (x, y) in world.live_cells() {
+            infinite.set(x as i64, y as i64, true);
+        }
+        Ok(infinite)
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> bool {
+        let (key, index) = locate(x, y);
+        self.chunks.get(&key).is_some_and(|chunk| chunk[index])
+    }
+
+    pub fn set(&mut self, x: i64, y: i64, alive: bool) {
+        let (key, index) = locate(x, y);
+        if alive {
+            self.chunks.entry(key).or_insert_with(empty_chunk)[index] = true;
+        } else if let Some(chunk) = self.chunks.get_mut(&key) {
+            chunk[index] = false;
+            if !chunk.contains(&true) {
+                self.chunks.remove(&key);
+            }
+        }
+    }
+
+    pub fn live_cells_count(&self) -> usize {
+        self.chunks
+            .values()
+            .map(|chunk| chunk.iter().filter(|alive| **alive).count())
+            .sum()
+    }
+
+    /// How many chunks are allocated.
+    pub fn chunks_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Roughly how many bytes the world takes, which grows and shrinks with
+    /// the chunks it has.
+    pub fn memory_usage(&self) -> usize {
+        mem::size_of::<Self>()
+            + hash_bytes::<((i64, i64), Chunk)>(self.chunks.capacity())
+            + self
+                .chunks
+                .values()
+                .map(|chunk| heap_bytes::<bool>(chunk.len()))
+                .sum::<usize>()
+    }
+
+    /// The live cells within a rectangle, looking only at the chunks which
+    /// intersect it.
+    pub fn live_cells_in(
+        &self,
+        (left, top): (i64, i64),
+        (width, height): (i64, i64),
+    ) -> impl Iterator<Item = (i64, i64)> + '_ {
+        let (right, bottom) = (left + width, top + height);
+        let chunk_xs = left.div_euclid(CHUNK_SIZE)..=(right - 1).div_euclid(CHUNK_SIZE);
+        let chunk_ys = top.div_euclid(CHUNK_SIZE)..=(bottom - 1).div_euclid(CHUNK_SIZE);
+
+        chunk_ys
+            .flat_map(move |chunk_y| chunk_xs.clone().map(move |chunk_x| (chunk_x, chunk_y)))
+            .filter_map(move |key| self.chunks.get(&key).map(|chunk| (key, chunk)))
+            .flat_map(|((chunk_x, chunk_y), chunk)| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, alive)| **alive)
+                    .map(move |(index, _)| {
+                        let index = index as i64;
+                        (
+                            chunk_x * CHUNK_SIZE + index % CHUNK_SIZE,
+                            chunk_y * CHUNK_SIZE + index / CHUNK_SIZE,
+                        )
+                    })
+            })
+            .filter(move |(x, y)| (left..right).contains(x) && (top..bottom).contains(y))
+    }
+
+    /// Moves on a generation. Only chunks with live cells and the chunks
+    /// around them can change; any left empty are freed.
+    pub fn simulate(&mut self) {
+        let candidates: HashSet<(i64, i64)> = self
+            .chunks
+            .keys()
+            .flat_map(|(chunk_x, chunk_y)| {
+                (-1..=1).flat_map(move |dy| (-1..=1).map(move |dx| (chunk_x + dx, chunk_y + dy)))
+            })
+            .collect();
+
+        let chunks = candidates
+            .into_iter()
+            .filter_map(|key| {
+                let chunk = self.next_chunk(key);
+                chunk.contains(&true).then_some((key, chunk))
+            })
+            .collect();
+
+        self.chunks = chunks;
+        self.generation += 1;
+    }
+
+    /// What a chunk will be next generation.
+    fn next_chunk(&self, (chunk_x, chunk_y): (i64, i64)) -> Chunk {
+        let (x_reach, y_reach) = reach(&self.rule);
+        let (origin_x, origin_y) = (chunk_x * CHUNK_SIZE, chunk_y * CHUNK_SIZE);
+
+        // Copy the chunk and its surroundings out once rather than looking
+        // up each neighbour's chunk.
+        let padded_width = CHUNK_SIZE + 2 * x_reach;
+        let padded_height = CHUNK_SIZE + 2 * y_reach;
+        let mut padded = vec![false; (padded_width * padded_height) as usize];
+        for y in 0..padded_height {
+            for x in 0..padded_width {
+                padded[(y * padded_width + x) as usize] =
+                    self.get(origin_x + x - x_reach, origin_y + y - y_reach);
+            }
+        }
+
+        let mut chunk = empty_chunk();
+        if !padded.contains(&true) {
+            return chunk;
+        }
+
+        // Only the parity of a cell's position changes its shape.
+        let offsets = [
+            [self.rule.offsets((0, 0)), self.rule.offsets((1, 0))],
+            [self.rule.offsets((0, 1)), self.rule.offsets((1, 1))],
+        ];
+
+        for y in 0..CHUNK_SIZE {
+            for x in 0..CHUNK_SIZE {
+                let offsets = &offsets[(origin_y + y).rem_euclid(2) as usize]
+                    [(origin_x + x).rem_euclid(2) as usize];
+                let cell = |x_offset: isize, y_offset: isize| {
+                    let x = x + x_reach + x_offset as i64;
+                    let y = y + y_reach + y_offset as i64;
+                    padded[(y * padded_width + x) as usize]
+                };
+
+                let live_neighbours = offsets
+                    .iter()
+                    .filter(|(x_offset, y_offset)| cell(*x_offset, *y_offset))
+                    .count() as u8;
+
+                chunk[(y * CHUNK_SIZE + x) as usize] = if cell(0, 0) {
+                    self.rule.survives(live_neighbours)
+                } else {
+                    self.rule.births(live_neighbours)
+                };
+            }
+        }
+
+        chunk
+    }
+}
+
+fn reach(rule: &Rule) -> (i64, i64) {
+    let (x_reach, y_reach) = rule.reach();
+    (x_reach as i64, y_reach as i64)
+}
+
+/// The chunk a cell is in, and its index within the chunk.
+fn locate(x: i64, y: i64) -> ((i64, i64), usize) {
+    let key = (x.div_euclid(CHUNK_SIZE), y.div_euclid(CHUNK_SIZE));
+    let index = y.rem_euclid(CHUNK_SIZE) * CHUNK_SIZE + x.rem_euclid(CHUNK_SIZE);
+    (key, index as usize)
+}
+
+fn empty_chunk() -> Chunk {
+    vec![false; (CHUNK_SIZE * CHUNK_SIZE) as usize].into_boxed_slice()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set() {
+        let mut world = InfiniteWorld::new(Rule::conway()).unwrap();
+        world.set(-1, -1, true);
+        world.set(40, 3, true);
+
+        assert!(world.get(-1, -1));
+        assert!(!world.get(0, 0));
+        assert_eq!(world.chunks_count(), 2);
+
+        world.set(40, 3, false);
+        assert_eq!(world.chunks_count(), 1);
+        assert_eq!(world.live_cells_count(), 1);
+    }
+
+    #[test]
+    fn test_glider_never_hits_a_wall() {
+        let mut world = InfiniteWorld::new(Rule::conway()).unwrap();
+        for (x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            world.set(*x, *y, true);
+        }
+
+        // A glider moves one cell diagonally every four generations.
+        for _ in 0..4 * 100 {
+            world.simulate();
+        }
+
+        let mut cells: Vec<_> = world.live_cells_in((90, 90), (20, 20)).collect();
+        cells.sort();
+        assert_eq!(
+            cells,
+            vec![(100, 102), (101, 100), (101, 102), (102, 101), (102, 102)]
+        );
+        assert_eq!(world.live_cells_count(), 5);
+        assert!(world.chunks_count() <= 4);
+    }
+
+    #[test]
+    fn test_blinker_across_chunks() {
+        let mut world = InfiniteWorld::new(Rule::conway()).unwrap();
+        for x in -1..=1 {
+            world.set(x, 0, true);
+        }
+
+        world.simulate();
+        let mut cells: Vec<_> = world.live_cells_in((-5, -5), (10, 10)).collect();
+        cells.sort();
+        assert_eq!(cells, vec![(0, -1), (0, 0), (0, 1)]);
+    }
+
+    #[test]
+    fn test_unsupported_rules() {
+        assert!(InfiniteWorld::new("B0/S8".parse().unwrap()).is_err());
+        assert!(InfiniteWorld::new(Rule::wireworld()).is_err());
+        assert!(InfiniteWorld::new("briansbrain".parse().unwrap()).is_err());
+    }
+}
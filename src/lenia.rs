@@ -0,0 +1,154 @@
+//! Lenia, a continuous relative of Life: cells hold values between 0 and 1
+//! which grow or shrink depending on a smooth, ring-shaped neighbourhood.
+
+use crate::world::World;
+
+/// A field of continuous cell values, wrapping around at the edges.
+#[derive(Clone, Debug)]
+pub struct Lenia {
+    pub width: usize,
+    pub height: usize,
+    /// Cell values in reading order.
+    pub cells: Vec<f32>,
+    /// The centre of the growth function: the neighbourhood potential at
+    /// which cells grow fastest.
+    pub mu: f32,
+    /// The width of the growth function.
+    pub sigma: f32,
+    /// How far cells move towards their growth each step.
+    pub dt: f32,
+    /// The neighbours within the kernel's radius, with their weights.
+    kernel: Vec<(isize, isize, f32)>,
+}
+
+impl Lenia {
+    /// An empty field with the parameters of Orbium, Lenia's glider.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![0.0; width * height],
+            mu: 0.15,
+            sigma: 0.015,
+            dt: 0.1,
+            kernel: kernel(10),
+        }
+    }
+
+    /// A field with a value of 1 for each of the world's live cells.
+    pub fn from_world(world: &World) -> Self {
+        let mut lenia = Self::new(world.width, world.height);
+        for (x, y) in world.live_cells() {
+            lenia.set(x, y, 1.0);
+        }
+        lenia
+    }
+
+    /// Changes the kernel's radius, in cells.
+    pub fn set_radius(&mut self, radius: usize) {
+        self.kernel = kernel(radius);
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> f32 {
+        self.cells[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: f32) {
+        self.cells[y * self.width + x] = value;
+    }
+
+    /// Convolves the field with the kernel, then moves each cell towards
+    /// the growth of its potential.
+    pub fn step(&mut self) {
+        let (width, height) = (self.width as isize, self.height as isize);
+        let mut next = self.cells.clone();
+
+        for y in 0..height {
+            for x in 0..width {
+                let potential: f32 = self
+                    .kernel
+                    .iter()
+                    .map(|(x_offset, y_offset, weight)| {
+                        let x = (x + x_offset).rem_euclid(width);
+                        let y = (y + y_offset).rem_euclid(height);
+                        weight * self.cells[(y * width + x) as usize]
+                    })
+                    .sum();
+
+                let cell = &mut next[(y * width + x) as usize];
+                *cell = (*cell + self.dt * self.growth(potential)).clamp(0.0, 1.0);
+            }
+        }
+
+        self.cells = next;
+    }
+
+    /// A bell curve around `mu`, from -1 far away from it up to 1 at it.
+    fn growth(&self, potential: f32) -> f32 {
+        let distance = (potential - self.mu) / self.sigma;
+        2.0 * (-distance * distance / 2.0).exp() - 1.0
+    }
+}
+
+/// A smooth ring of the given radius, peaking halfway out, with weights
+/// that add up to 1.
+fn kernel(radius: usize) -> Vec<(isize, isize, f32)> {
+    let radius = radius as isize;
+    let mut kernel = Vec::new();
+
+    for y in -radius..=radius {
+        for x in -radius..=radius {
+            let r = ((x * x + y * y) as f32).sqrt() / radius as f32;
+            if r > 0.0 && r < 1.0 {
+                kernel.push((x, y, (4.0 - 1.0 / (r * (1.0 - r))).exp()));
+            }
+        }
+    }
+
+    let total: f32 = kernel.iter().map(|(_, _, weight)| weight).sum();
+    for (_, _, weight) in &mut kernel {
+        *weight /= total;
+    }
+    kernel
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kernel() {
+        let kernel = kernel(5);
+        let total: f32 = kernel.iter().map(|(_, _, weight)| weight).sum();
+
+        assert!((total - 1.0).abs() < 1e-5);
+        assert!(kernel.iter().all(|(x, y, _)| x * x + y * y < 25));
+        assert!(!kernel.iter().any(|(x, y, _)| (*x, *y) == (0, 0)));
+    }
+
+    #[test]
+    fn test_step() {
+        let mut lenia = Lenia::new(30, 30);
+        lenia.set_radius(5);
+
+        // With nothing around them, cells shrink.
+        lenia.set(15, 15, 1.0);
+        lenia.step();
+        assert!((lenia.get(15, 15) - 0.9).abs() < 1e-5);
+
+        // At the growth function's peak, they grow.
+        lenia.cells = vec![lenia.mu; 30 * 30];
+        lenia.step();
+        assert!((lenia.get(0, 0) - (lenia.mu + lenia.dt)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_from_world() {
+        let mut world = World::new(4, 3);
+        world.toggle_cell(2, 1);
+
+        let lenia = Lenia::from_world(&world);
+        assert_eq!(lenia.get(2, 1), 1.0);
+        assert_eq!(lenia.cells.iter().sum::<f32>(), 1.0);
+    }
+}
@@ -1,5 +1,68 @@
+mod ant;
+mod bitpacked;
+mod boundary;
+mod camera;
+mod catalog;
+mod chance;
+mod elementary;
+#[cfg(feature = "fetch")]
+mod fetch;
+mod formats;
+#[cfg(feature = "gpu")]
+mod gpu;
+mod grid;
+mod hashlife;
+mod history;
+mod image;
+mod infinite;
+mod lenia;
+mod life3d;
+mod pacer;
+mod profile;
+mod quadtree;
+mod region;
+mod rule;
+#[cfg(feature = "script")]
+mod script;
+mod session;
+mod simulator;
+mod sparse;
+mod svg;
 mod window_buffer;
 mod world;
 
+pub use ant::{Ant, Direction};
+pub use bitpacked::BitWorld;
+pub use boundary::Boundary;
+pub use camera::Camera;
+pub use catalog::{catalog_names, catalog_pattern, Placement};
+pub use chance::Chance;
+pub use elementary::Elementary;
+#[cfg(feature = "fetch")]
+pub use fetch::fetch_pattern;
+pub use formats::{Format, ParseError, Pattern, SeedError, Transform};
+#[cfg(feature = "gpu")]
+pub use gpu::GpuGrid;
+pub use grid::{Engine, Grid};
+pub use hashlife::{HashLife, QuadtreeGrid};
+pub use history::History;
+pub use image::{pattern_from_png, write_png};
+pub use infinite::{InfiniteWorld, CHUNK_SIZE};
+pub use lenia::Lenia;
+pub use life3d::{Rule3d, World3d};
+pub use pacer::Pacer;
+pub use profile::Profile;
+pub use quadtree::Quadtree;
+pub use region::Region;
+pub use rule::{
+    CellRule, ConwayRule, Family, Neighbourhood, NeighbourhoodShape, Rule, WIREWORLD_CONDUCTOR,
+    WIREWORLD_HEAD, WIREWORLD_TAIL,
+};
+#[cfg(feature = "script")]
+pub use script::ScriptRule;
+pub use session::Session;
+pub use simulator::Simulator;
+pub use sparse::SparseWorld;
+pub use svg::{to_svg, SvgOptions};
 pub use window_buffer::WindowBuffer;
-pub use world::World;
+pub use world::{Anchor, CellChange, OutOfBounds, Stats, World};
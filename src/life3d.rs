@@ -0,0 +1,237 @@
+//! Three-dimensional Life, where each cell has 26 neighbours in the cube
+//! around it.
+
+use crate::world::World;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+/// A 3D rule in Carter Bays' notation, e.g. `5766`: live cells survive with
+/// five to seven live neighbours and dead cells are born with six to six.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule3d {
+    survival: RangeInclusive<u8>,
+    birth: RangeInclusive<u8>,
+}
+
+impl Rule3d {
+    pub fn births(&self, live_neighbours: u8) -> bool {
+        self.birth.contains(&live_neighbours)
+    }
+
+    pub fn survives(&self, live_neighbours: u8) -> bool {
+        self.survival.contains(&live_neighbours)
+    }
+}
+
+/// Parses four digits, e.g. `5766` or `4555`, or four comma separated counts
+/// when some are above nine, e.g. `10,21,10,21`.
+impl FromStr for Rule3d {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let counts: Option<Vec<u8>> = if s.contains(',') {
+            s.split(',')
+                .map(|count| count.trim().parse().ok())
+                .collect()
+        } else {
+            s.chars()
+                .map(|c| c.to_digit(10).map(|count| count as u8))
+                .collect()
+        };
+
+        match counts.as_deref() {
+            Some(&[survival_min, survival_max, birth_min, birth_max])
+                if survival_max <= 26 && birth_max <= 26 =>
+            {
+                Ok(Self {
+                    survival: survival_min..=survival_max,
+                    birth: birth_min..=birth_max,
+                })
+            }
+            _ => Err(format!("invalid 3D rule: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Rule3d {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let counts = [
+            *self.survival.start(),
+            *self.survival.end(),
+            *self.birth.start(),
+            *self.birth.end(),
+        ];
+
+        if counts.iter().all(|count| *count < 10) {
+            counts.iter().try_for_each(|count| write!(f, "{}", count))
+        } else {
+            write!(f, "{},{},{},{}", counts[0], counts[1], counts[2], counts[3])
+        }
+    }
+}
+
+/// A box of cells. Cells past the edges are dead.
+#[derive(Clone, Debug)]
+pub struct World3d {
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+    cells: Vec<bool>,
+    rule: Rule3d,
+}
+
+impl World3d {
+    pub fn new(width: usize, height: usize, depth: usize, rule: Rule3d) -> Self {
+        Self {
+            width,
+            height,
+            depth,
+            cells: vec![false; width * height * depth],
+            rule,
+        }
+    }
+
+    /// A box as wide and high as the world, with the world's live cells on
+    /// the middle layer.
+    pub fn from_world(world: &World, depth: usize, rule: Rule3d) -> Self {
+        let mut world3d = Self::new(world.width, world.height, depth, rule);
+        for (x, y) in world.live_cells() {
+            world3d.set(x, y, depth / 2, true);
+        }
+        world3d
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> bool {
+        self.cells[self.index(x, y, z)]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, alive: bool) {
+        let index = self.index(x, y, z);
+        self.cells[index] = alive;
+    }
+
+    pub fn live_cells_count(&self) -> usize {
+        self.cells.iter().filter(|alive| **alive).count()
+    }
+
+    /// The nearest live cell looking down the z axis from in front of the
+    /// box, for projecting it onto a screen.
+    pub fn nearest(&self, x: usize, y: usize) -> Option<usize> {
+        (0..self.depth).find(|z| self.get(x, y, *z))
+    }
+
+    pub fn simulate(&mut self) {
+        let mut next = self.cells.clone();
+
+        for z in 0..self.depth {
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    let count = self.live_neighbours_count(x, y, z);
+                    let index = self.index(x, y, z);
+                    next[index] = if self.cells[index] {
+                        self.rule.survives(count)
+                    } else {
+                        self.rule.births(count)
+                    };
+                }
+            }
+        }
+
+        self.cells = next;
+    }
+
+    fn live_neighbours_count(&self, x: usize, y: usize, z: usize) -> u8 {
+        let mut count = 0;
+
+        for z_offset in -1..=1 {
+            for y_offset in -1..=1 {
+                for x_offset in -1..=1 {
+                    if (x_offset, y_offset, z_offset) == (0, 0, 0) {
+                        continue;
+                    }
+
+                    let neighbour = (
+                        x as isize + x_offset,
+                        y as isize + y_offset,
+                        z as isize + z_offset,
+                    );
+                    if neighbour.0 >= 0
+                        && neighbour.1 >= 0
+                        && neighbour.2 >= 0
+                        && (neighbour.0 as usize) < self.width
+                        && (neighbour.1 as usize) < self.height
+                        && (neighbour.2 as usize) < self.depth
+                        && self.get(
+                            neighbour.0 as usize,
+                            neighbour.1 as usize,
+                            neighbour.2 as usize,
+                        )
+                    {
+                        count += 1;
+                    }
+                }
+            }
+        }
+
+        count
+    }
+
+    fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        (z * self.height + y) * self.width + x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rule() {
+        let rule: Rule3d = "5766".parse().unwrap();
+        assert!(rule.survives(5) && rule.survives(7) && !rule.survives(8));
+        assert!(rule.births(6) && !rule.births(5));
+        assert_eq!(rule.to_string(), "5766");
+
+        let rule: Rule3d = "10, 21, 10, 21".parse().unwrap();
+        assert_eq!(rule.to_string(), "10,21,10,21");
+
+        assert!("576".parse::<Rule3d>().is_err());
+        assert!("1,2,3,27".parse::<Rule3d>().is_err());
+    }
+
+    #[test]
+    fn test_simulate() {
+        // A 2x2x2 cube is still: each of its cells has seven neighbours, and
+        // no dead cell has six.
+        let mut world = World3d::new(6, 6, 6, "5766".parse().unwrap());
+        for z in 2..4 {
+            for y in 2..4 {
+                for x in 2..4 {
+                    world.set(x, y, z, true);
+                }
+            }
+        }
+
+        world.simulate();
+        assert_eq!(world.live_cells_count(), 8);
+
+        // Without enough neighbours, a cell dies.
+        let mut world = World3d::new(6, 6, 6, "5766".parse().unwrap());
+        world.set(2, 2, 2, true);
+        world.simulate();
+        assert_eq!(world.live_cells_count(), 0);
+    }
+
+    #[test]
+    fn test_from_world() {
+        let mut world = World::new(4, 3);
+        world.toggle_cell(2, 1);
+
+        let world3d = World3d::from_world(&world, 5, "5766".parse().unwrap());
+        assert!(world3d.get(2, 1, 2));
+        assert_eq!(world3d.live_cells_count(), 1);
+        assert_eq!(world3d.nearest(2, 1), Some(2));
+        assert_eq!(world3d.nearest(0, 0), None);
+    }
+}
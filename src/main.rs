@@ -7,81 +7,1576 @@
 //!   Any live cell with more than three live neighbours dies, as if by overpopulation.
 //!   Any dead cell with exactly three live neighbours becomes a live cell, as if by reproduction.
 
-use clap::{App, Arg};
-use gol::{WindowBuffer, World};
-use minifb::{MouseButton, MouseMode, Scale, Window, WindowOptions};
-use rand::{thread_rng, Rng};
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::prelude::*;
+use arboard::Clipboard;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use gif::SetParameter;
+use gol::{
+    catalog_names, catalog_pattern, pattern_from_png, to_svg, write_png, Ant, Boundary, Camera,
+    Chance, Elementary, Engine, Family, Format, Grid, HashLife, History, InfiniteWorld, Lenia,
+    Neighbourhood, Pacer, Pattern, Placement, Profile, Rule, SeedError, Session, Simulator,
+    SvgOptions, WindowBuffer, World, World3d, CHUNK_SIZE, WIREWORLD_CONDUCTOR, WIREWORLD_HEAD,
+    WIREWORLD_TAIL,
+};
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Scale, Window, WindowOptions};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io;
+use std::mem;
+use std::ops::Range;
+use std::path::Path;
 use std::{thread, time};
 
-const DESIRED_SLEEP_TIME: time::Duration = time::Duration::from_millis(50);
+/// How many layers deep 3D Life's box is.
+const LIFE_3D_DEPTH: usize = 16;
+/// With warp on, HashLife moves on 2^10 generations a frame.
+const HASHLIFE_WARP_LOG2: u8 = 10;
+/// Ants only change one cell a step, so take many steps each frame.
+const ANT_STEPS_PER_FRAME: usize = 50;
+/// The most generations ] will move on at a time.
+const MAX_STEPS_PER_FRAME: usize = 1 << 16;
+/// How often the window is redrawn and input is handled, however fast
+/// generations are moving on.
+const FRAMES_PER_SECOND: f64 = 60.0;
+/// Hundredths of a second between a GIF's frames, the same as the
+/// window's default generations a second.
+const GIF_FRAME_DELAY: u16 = 5;
 const HEIGHT: usize = 300;
+/// Live cells are red, or the color of their team for rules with teams.
+const TEAM_COLORS: [u32; 4] = [0xff0000, 0x00c0ff, 0xffc000, 0x40ff40];
+/// The keys that pick which state to paint, in order from state 0.
+const STATE_KEYS: [Key; 10] = [
+    Key::Key0,
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+    Key::Key4,
+    Key::Key5,
+    Key::Key6,
+    Key::Key7,
+    Key::Key8,
+    Key::Key9,
+];
+const IMAGE_THRESHOLD: u8 = 128;
+
+/// How live cells are colored in the window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ColorMode {
+    /// Red, or the color of their team under rules with teams.
+    Team,
+    /// A random color of each cell's own, which stays put.
+    Cell,
+    /// A new random color every frame.
+    Flicker,
+}
+
+/// What `draw_world` last left in the window buffer, so the next frame only
+/// has to repaint the cells that have changed since.
+struct Drawn {
+    world: World,
+    visible: ((usize, usize), (usize, usize)),
+    /// Cells drawn over with edits, region outlines, the selection or ants,
+    /// which have to be repainted underneath.
+    overlaid: Vec<(usize, usize)>,
+}
 const WIDTH: usize = 400;
 
 fn main() {
     let matches = App::new("Game of Life")
         .version("0.1.0")
         .author("Odin Dutton <odindutton@gmail.com>")
-        .arg(
-            Arg::with_name("seed")
-                .short("s")
-                .long("seed")
-                .value_name("FILE")
-                .help("Sets a custom seed file")
-                .takes_value(true),
+        .args(&run_args())
+        .subcommand(
+            SubCommand::with_name("run")
+                .about("Runs the simulation in a window (default)")
+                .args(&run_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("headless")
+                .about("Runs the simulation without a window and prints the final generation")
+                .args(&seed_args())
+                .arg(generations_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("render")
+                .about(
+                    "Renders the simulation to an animated GIF, or its last generation to a PNG or SVG",
+                )
+                .args(&seed_args())
+                .arg(generations_arg())
+                .arg(
+                    Arg::with_name("scale")
+                        .long("scale")
+                        .value_name("PIXELS")
+                        .help("Sets the size of each cell in pixels")
+                        .default_value("2"),
+                )
+                .arg(
+                    Arg::with_name("color")
+                        .long("color")
+                        .value_name("HEX")
+                        .help("Sets the color of live cells (SVG only)")
+                        .default_value("ff0000"),
+                )
+                .arg(
+                    Arg::with_name("background")
+                        .long("background")
+                        .value_name("HEX")
+                        .help("Sets the background color (SVG only)")
+                        .default_value("000000"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .value_name("OUTPUT")
+                        .help("Sets the .gif, .png or .svg file to write")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("analyze")
+                .about("Runs the simulation and reports statistics and cycles")
+                .args(&seed_args())
+                .arg(generations_arg()),
         )
-        .arg(
-            Arg::with_name("random_color")
-                .short("r")
-                .long("random-color")
-                .help("Turns on random colors"),
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("Writes the world after a number of generations as a seed file")
+                .args(&seed_args())
+                .arg(generations_arg().default_value("0"))
+                .arg(
+                    format_arg("format")
+                        .help("Sets the output format")
+                        .default_value("ascii"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .value_name("OUTPUT")
+                        .help("Sets the file to write, defaults to stdout"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("bench")
+                .about("Times each engine on a few standard seeds and world sizes")
+                .arg(generations_arg().default_value("20"))
+                .arg(
+                    Arg::with_name("sizes")
+                        .long("sizes")
+                        .value_name("N,...")
+                        .help("Sets the widths of the square worlds to time")
+                        .default_value("64,256,1024"),
+                )
+                .arg(
+                    Arg::with_name("throughput")
+                        .long("throughput")
+                        .help(
+                            "Runs a random soup for a while at each size instead, printing \
+                             generations and cell updates a second as tab-separated values",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("seconds")
+                        .long("seconds")
+                        .value_name("SECONDS")
+                        .help("Sets how long --throughput runs each size for")
+                        .default_value("5"),
+                )
+                .arg(
+                    Arg::with_name("engine")
+                        .long("engine")
+                        .value_name("ENGINE")
+                        .help("Sets the engine --throughput runs [default: dense]")
+                        .takes_value(true)
+                        .possible_values(&Engine::NAMES)
+                        .requires("throughput"),
+                )
+                .arg(
+                    Arg::with_name("rule")
+                        .long("rule")
+                        .value_name("RULE")
+                        .help("Sets the rule --throughput runs [default: life]")
+                        .takes_value(true)
+                        .requires("throughput"),
+                )
+                .arg(
+                    Arg::with_name("boundary")
+                        .long("boundary")
+                        .value_name("BOUNDARY")
+                        .help("Sets the boundary --throughput runs [default: wrap]")
+                        .takes_value(true)
+                        .possible_values(&["wrap", "dead", "mirror", "klein", "cross-surface"])
+                        .requires("throughput"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("convert")
+                .about("Converts a seed file between formats")
+                .arg(format_arg("from").help("Sets the input format, detected if not given"))
+                .arg(
+                    format_arg("to")
+                        .help("Sets the output format, defaults to the OUTPUT file's extension"),
+                )
+                .arg(
+                    Arg::with_name("input")
+                        .value_name("INPUT")
+                        .help("Sets the seed file to read")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .value_name("OUTPUT")
+                        .help("Sets the file to write, defaults to stdout"),
+                ),
         )
         .get_matches();
 
+    match matches.subcommand() {
+        ("run", Some(matches)) => run(matches),
+        ("headless", Some(matches)) => headless(matches),
+        ("render", Some(matches)) => render(matches),
+        ("analyze", Some(matches)) => analyze(matches),
+        ("export", Some(matches)) => export(matches),
+        ("convert", Some(matches)) => convert(matches),
+        ("bench", Some(matches)) => bench(matches),
+        _ => run(&matches),
+    }
+}
+
+fn seed_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("rule")
+            .long("rule")
+            .value_name("RULE")
+            .help(
+                "Sets the rule in B/S notation, e.g. B36/S23 or B2-a/S12, Generations notation, \
+                 e.g. 345/2/4, Larger than Life notation, e.g. R5,C0,M1,S34..58,B34..45,NM, or by \
+                 name: life, highlife, seeds, daynight, lifewithoutdeath, diamoeba, anneal, \
+                 briansbrain, starwars, wireworld, immigration, quadlife or cyclic, e.g. cyclic/C16/T1 [default: life]",
+            )
+            .takes_value(true),
+        Arg::with_name("region")
+            .long("region")
+            .value_name("X,Y,WIDTH,HEIGHT:RULE")
+            .help(
+                "Runs a different rule inside a rectangle, counting the same neighbours as \
+                 --rule. Can be repeated, with later regions on top",
+            )
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1),
+        #[cfg(feature = "script")]
+        Arg::with_name("rule_script")
+            .long("rule-script")
+            .value_name("FILE")
+            .help(
+                "Sets the rule from a Rhai script defining next_state(alive, neighbours), \
+                 which returns whether a cell lives",
+            )
+            .takes_value(true)
+            .conflicts_with_all(&["rule", "neighbourhood"]),
+        Arg::with_name("neighbourhood")
+            .long("neighbourhood")
+            .value_name("NEIGHBOURHOOD")
+            .help("Sets which cells the rule counts as neighbours [default: moore]")
+            .takes_value(true)
+            .possible_values(&["moore", "vonneumann", "hexagonal", "triangular"]),
+        Arg::with_name("boundary")
+            .long("boundary")
+            .value_name("BOUNDARY")
+            .help("Sets what lies past the edges of the world [default: wrap]")
+            .takes_value(true)
+            .possible_values(&["wrap", "dead", "mirror", "klein", "cross-surface"]),
+        Arg::with_name("birth_prob")
+            .long("birth-prob")
+            .value_name("PROBABILITY")
+            .help("Sets the chance that a birth the rule allows happens [default: 1]")
+            .takes_value(true),
+        Arg::with_name("survival_prob")
+            .long("survival-prob")
+            .value_name("PROBABILITY")
+            .help("Sets the chance that a survival the rule allows happens [default: 1]")
+            .takes_value(true),
+        Arg::with_name("noise")
+            .long("noise")
+            .value_name("FRACTION")
+            .help("Flips this fraction of cells at random every generation [default: 0]")
+            .takes_value(true),
+        Arg::with_name("rng_seed")
+            .long("rng-seed")
+            .value_name("NUMBER")
+            .help("Seeds the random numbers behind --birth-prob, --survival-prob and --noise")
+            .takes_value(true),
+        Arg::with_name("seed")
+            .short("s")
+            .long("seed")
+            .value_name("FILE")
+            .help(
+                "Sets a custom seed file, in any supported format (- reads stdin). \
+                 Can be repeated, with FILE@X,Y placing each file within the scene",
+            )
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1),
+        Arg::with_name("pattern")
+            .short("p")
+            .long("pattern")
+            .value_name("NAME")
+            .help("Seeds the world from a built-in pattern")
+            .possible_values(&catalog_names().collect::<Vec<_>>())
+            .conflicts_with("seed")
+            .takes_value(true),
+        #[cfg(feature = "fetch")]
+        Arg::with_name("fetch")
+            .long("fetch")
+            .value_name("NAME_OR_URL")
+            .help("Downloads a pattern from LifeWiki by name, or from a URL")
+            .takes_value(true)
+            .conflicts_with_all(&["seed", "pattern", "seed_image"]),
+        Arg::with_name("place")
+            .long("place")
+            .value_name("NAME@X,Y[,TRANSFORM...]")
+            .help(
+                "Places a built-in pattern, optionally transformed by rot90, rot180, rot270, \
+                 flipx or flipy. Can be repeated",
+            )
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1),
+        Arg::with_name("seed_image")
+            .long("seed-image")
+            .value_name("PNG")
+            .help("Seeds the world from the bright pixels of an image, downscaled to fit")
+            .conflicts_with_all(&["seed", "pattern", "place"])
+            .takes_value(true),
+        Arg::with_name("padding")
+            .long("padding")
+            .value_name("CELLS")
+            .help("Sets the number of dead cells between the seed and the world's edges")
+            .default_value("0"),
+        Arg::with_name("seed_offset")
+            .long("seed-offset")
+            .value_name("X,Y")
+            .help("Places the seed's top-left corner at the given cell")
+            .takes_value(true),
+        Arg::with_name("center")
+            .long("center")
+            .help("Places the seed in the middle of the world")
+            .conflicts_with("seed_offset"),
+    ]
+}
+
+fn generations_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("generations")
+        .short("g")
+        .long("generations")
+        .value_name("N")
+        .help("Sets the number of generations to simulate")
+        .default_value("100")
+}
+
+fn format_arg<'a, 'b>(name: &'a str) -> Arg<'a, 'b> {
+    Arg::with_name(name)
+        .long(name)
+        .value_name("FORMAT")
+        .possible_values(&[
+            "ascii",
+            "native",
+            "rle",
+            "plaintext",
+            "life105",
+            "life106",
+            "macrocell",
+        ])
+        .takes_value(true)
+}
+
+fn run_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    let mut args = seed_args();
+    args.extend(vec![
+        Arg::with_name("random_color")
+            .short("r")
+            .long("random-color")
+            .help("Gives each live cell a random color of its own, the same as --color-mode cell")
+            .conflicts_with("color_mode"),
+        Arg::with_name("color_mode")
+            .long("color-mode")
+            .value_name("MODE")
+            .help(
+                "Sets how live cells are colored: team in red or their team's color, cell in a \
+                 random color of each cell's own, or flicker in a new random color every frame \
+                 [default: team]",
+            )
+            .takes_value(true)
+            .possible_values(&["team", "cell", "flicker"]),
+        Arg::with_name("palette")
+            .long("palette")
+            .value_name("COLORS")
+            .help(
+                "Sets the hex colors for each state of a cyclic rule, repeating if there are \
+                 fewer colors than states [default: a rainbow]",
+            )
+            .takes_value(true)
+            .use_delimiter(true),
+        Arg::with_name("ant")
+            .long("ant")
+            .value_name("X,Y[,DIRECTION]")
+            .help(
+                "Adds a Langton's Ant facing n, e, s or w, which walks the world instead of it \
+                 being simulated. Can be repeated",
+            )
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1),
+        Arg::with_name("elementary")
+            .long("elementary")
+            .value_name("RULE")
+            .help(
+                "Runs an elementary cellular automaton such as rule 30 along the bottom row, \
+                 scrolling earlier generations up",
+            )
+            .takes_value(true)
+            .conflicts_with("ant"),
+        Arg::with_name("lenia")
+            .long("lenia")
+            .help(
+                "Runs Lenia, a continuous relative of Life, starting with the seed's live cells \
+                 at full strength",
+            )
+            .conflicts_with_all(&["ant", "elementary"]),
+        Arg::with_name("life_3d")
+            .long("life-3d")
+            .value_name("RULE")
+            .help(
+                "Runs 3D Life under a rule in Bays' notation, e.g. 5766, with the seed on the \
+                 middle layer, viewed from the front",
+            )
+            .takes_value(true)
+            .conflicts_with_all(&["ant", "elementary", "lenia"]),
+        Arg::with_name("infinite")
+            .long("infinite")
+            .help(
+                "Runs the seed in a world without edges, showing the part of it under the window; \
+                 the arrow keys move around",
+            )
+            .conflicts_with_all(&["ant", "elementary", "lenia", "life_3d"]),
+        Arg::with_name("hashlife")
+            .long("hashlife")
+            .help(
+                "Runs the seed with HashLife in a world without edges, like --infinite; W warps \
+                 ahead 1024 generations a frame",
+            )
+            .conflicts_with_all(&["ant", "elementary", "lenia", "life_3d", "infinite"]),
+        Arg::with_name("engine")
+            .long("engine")
+            .value_name("ENGINE")
+            .help(
+                "Sets how the world's cells are stored and run: sparse is faster for big worlds \
+                 that are mostly empty, bitpacked for big busy ones and quadtree for big \
+                 repetitive ones, with --boundary dead only; gpu runs huge worlds on the GPU, in builds \
+                 with the gpu feature; all but dense take two-state rules only, and bitpacked, \
+                 quadtree and gpu only count the eight cells around each cell [default: dense]",
+            )
+            .takes_value(true)
+            .possible_values(&Engine::NAMES)
+            .conflicts_with_all(&[
+                "ant",
+                "elementary",
+                "lenia",
+                "life_3d",
+                "infinite",
+                "hashlife",
+            ]),
+        Arg::with_name("rule_file")
+            .long("rule-file")
+            .value_name("FILE")
+            .help(
+                "Reads the rule from a file, switching to it again whenever the file changes \
+                 or R is pressed",
+            )
+            .takes_value(true)
+            .conflicts_with("rule"),
+        Arg::with_name("resume")
+            .long("resume")
+            .value_name("FILE")
+            .help("Resumes a session saved with Ctrl+S")
+            .takes_value(true)
+            .conflicts_with_all(&["seed", "pattern", "place", "seed_image"]),
+        Arg::with_name("tile")
+            .short("t")
+            .long("tile")
+            .help("Renders dimmed copies of the world around it to show how it wraps"),
+        Arg::with_name("gps")
+            .long("gps")
+            .value_name("N")
+            .help(
+                "Sets the number of generations to aim for each second, moving on several at \
+                 once to catch up when falling behind",
+            )
+            .default_value("20"),
+        Arg::with_name("max_frame_skip")
+            .long("max-frame-skip")
+            .value_name("N")
+            .help(
+                "Skips drawing up to N frames in a row while generations take longer than a \
+                 frame, rather than slowing them down",
+            )
+            .default_value("0"),
+        Arg::with_name("profile").long("profile").help(
+            "Times drawing, presenting, input and simulating each frame, printing a \
+                 breakdown and how much memory the world takes when the window's closed",
+        ),
+        Arg::with_name("history")
+            .long("history")
+            .value_name("MIB")
+            .help(
+                "Keeps up to this many mebibytes of past generations to step back through \
+                 with , which pauses the world; 0 keeps none",
+            )
+            .default_value("256"),
+        Arg::with_name("steps_per_frame")
+            .long("steps-per-frame")
+            .value_name("N")
+            .help(
+                "Sets the number of generations to move on each time one is due, multiplying \
+                 --gps; ] doubles it and [ halves it",
+            )
+            .default_value("1"),
+    ]);
+    args
+}
+
+fn load_world(matches: &ArgMatches) -> World {
+    let mut world = seed_world(matches);
+
+    if let Some(rule) = matches.value_of("rule") {
+        world.set_rule(rule.parse().unwrap_or_else(|e| panic!("{}", e)));
+    }
+
+    if let Some(neighbourhood) = matches.value_of("neighbourhood") {
+        let rule = world
+            .rule()
+            .with_neighbourhood(neighbourhood.parse().unwrap())
+            .unwrap_or_else(|e| panic!("{}", e));
+        world.set_rule(rule);
+    }
+    if let Some(boundary) = matches.value_of("boundary") {
+        world.set_boundary(boundary.parse().unwrap());
+    }
+    if let Some(regions) = matches.values_of("region") {
+        for region in regions {
+            world
+                .add_region(region.parse().unwrap_or_else(|e| panic!("{}", e)))
+                .unwrap_or_else(|e| panic!("{}: {}", region, e));
+        }
+    }
+    apply_rule_script(matches, &mut world);
+
+    if ["birth_prob", "survival_prob", "noise"]
+        .iter()
+        .any(|name| matches.is_present(name))
+    {
+        let probability = |name, default| match matches.value_of(name) {
+            Some(value) => match value.parse() {
+                Ok(probability) if (0.0..=1.0).contains(&probability) => probability,
+                _ => panic!("{} must be a number between 0 and 1", name),
+            },
+            None => default,
+        };
+        let seed = matches
+            .value_of("rng_seed")
+            .map(|seed| seed.parse().expect("rng seed must be a number"));
+        world.set_chance(
+            Chance::new(
+                probability("birth_prob", 1.0),
+                probability("survival_prob", 1.0),
+                seed,
+            )
+            .with_noise(probability("noise", 0.0)),
+        );
+    }
+
+    world
+}
+
+fn seed_world(matches: &ArgMatches) -> World {
+    let pattern = if let Some(seeds) = matches.values_of("seed") {
+        Some(load_seeds(seeds))
+    } else if let Some(pattern) = fetched_pattern(matches) {
+        Some(pattern)
+    } else {
+        matches
+            .value_of("pattern")
+            .map(|name| catalog_pattern(name).unwrap())
+    };
+    let pattern = match matches.values_of("place") {
+        Some(placements) => Some(place_patterns(pattern.unwrap_or_default(), placements)),
+        None => pattern,
+    };
+
+    if let Some(pattern) = pattern {
+        // Grow the world to fit the seed rather than cropping it.
+        let padding = padding(matches);
+        let offset = matches.value_of("seed_offset").map(|offset| {
+            let (x, y) = parse_pair(offset).expect("seed offset must be in the form X,Y");
+            (x as usize, y as usize)
+        });
+        let (x, y) = offset.unwrap_or((padding, padding));
+        let (width, height) = pattern.world_size.unwrap_or((WIDTH, HEIGHT));
+        let mut world = World::new(
+            width.max(x + pattern.width + padding),
+            height.max(y + pattern.height + padding),
+        );
+
+        let (x, y) = if matches.is_present("center") {
+            (
+                (world.width - pattern.width) / 2,
+                (world.height - pattern.height) / 2,
+            )
+        } else {
+            (x, y)
+        };
+        world
+            .seed_pattern_at(&pattern, x, y)
+            .unwrap_or_else(|e| panic!("{}", e));
+
+        // Run the rule the seed was made for, unless --rule says otherwise.
+        if let (Some(rule), false) = (pattern.parse_rule(), matches.is_present("rule")) {
+            world.set_rule(
+                rule.unwrap_or_else(|e| panic!("{} (pass --rule to run another rule)", e)),
+            );
+        }
+        return world;
+    }
+
     let mut world = World::new(WIDTH, HEIGHT);
 
-    if let Some(seed) = matches.value_of("seed") {
-        let mut file = File::open(seed).expect("unable to open file");
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)
-            .expect("unable to read file");
-        world.seed_from_string(contents);
+    if let Some(image) = matches.value_of("seed_image") {
+        let file = File::open(image).expect("unable to open file");
+        let pattern = pattern_from_png(file, world.width, world.height, IMAGE_THRESHOLD)
+            .unwrap_or_else(|e| panic!("{}: {}", image, e));
+        world
+            .seed_pattern(&pattern)
+            .unwrap_or_else(|e| panic!("{}: {}", image, e));
     } else {
+        // Cyclic rules seed a random state into every cell, so the rule has
+        // to be known first.
+        if let Some(rule) = matches.value_of("rule") {
+            world.set_rule(rule.parse().unwrap_or_else(|e| panic!("{}", e)));
+        }
         world.seed_random();
     }
 
+    world
+}
+
+#[cfg(feature = "fetch")]
+fn fetched_pattern(matches: &ArgMatches) -> Option<Pattern> {
+    matches.value_of("fetch").map(|name_or_url| {
+        gol::fetch_pattern(name_or_url).unwrap_or_else(|e| panic!("{}: {}", name_or_url, e))
+    })
+}
+
+#[cfg(not(feature = "fetch"))]
+fn fetched_pattern(_matches: &ArgMatches) -> Option<Pattern> {
+    None
+}
+
+#[cfg(feature = "script")]
+fn apply_rule_script(matches: &ArgMatches, world: &mut World) {
+    if let Some(path) = matches.value_of("rule_script") {
+        let script = fs::read_to_string(path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+        let rule =
+            gol::ScriptRule::from_script(&script).unwrap_or_else(|e| panic!("{}: {}", path, e));
+        world.set_cell_rule(rule);
+    }
+}
+
+#[cfg(not(feature = "script"))]
+fn apply_rule_script(_matches: &ArgMatches, _world: &mut World) {}
+
+/// Re-reads the rule file if it's changed since `modified`, or if `force`d.
+fn reload_rule(
+    path: &str,
+    modified: &mut Option<time::SystemTime>,
+    force: bool,
+) -> Option<Result<Rule, String>> {
+    let file_modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+    if !force && file_modified == *modified {
+        return None;
+    }
+
+    *modified = file_modified;
+    Some(
+        fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|rule| rule.trim().parse()),
+    )
+}
+
+/// Stamps every `--place NAME@X,Y[,TRANSFORM...]` onto the pattern.
+fn place_patterns<'a>(mut pattern: Pattern, placements: impl Iterator<Item = &'a str>) -> Pattern {
+    for placement in placements {
+        let placement: Placement = placement.parse().unwrap_or_else(|e| panic!("{}", e));
+        pattern.stamp(&placement.pattern().unwrap(), placement.x, placement.y);
+    }
+
+    pattern
+}
+
+/// Combines every `--seed FILE[@X,Y]` into one pattern.
+fn load_seeds<'a>(seeds: impl Iterator<Item = &'a str>) -> Pattern {
+    let mut pattern = Pattern::default();
+
+    for seed in seeds {
+        let (path, (x, y)) = match seed
+            .rfind('@')
+            .map(|i| (&seed[..i], parse_pair(&seed[i + 1..])))
+        {
+            Some((path, Some((x, y)))) => (path, (x as usize, y as usize)),
+            _ => (seed, (0, 0)),
+        };
+
+        let seed_pattern = if path == "-" {
+            Pattern::from_reader(io::stdin())
+        } else {
+            File::open(path)
+                .map_err(SeedError::from)
+                .and_then(Pattern::from_reader)
+        }
+        .unwrap_or_else(|e| panic!("{}: {}", path, e));
+
+        if pattern.world_size.is_none() {
+            pattern.world_size = seed_pattern.world_size;
+        }
+        if pattern.rule.is_none() {
+            pattern.rule = seed_pattern.rule.clone();
+        }
+        pattern.stamp(&seed_pattern, x, y);
+    }
+
+    pattern
+}
+
+fn parse_pair(value: &str) -> Option<(u32, u32)> {
+    let mut parts = value.splitn(2, ',');
+    let x = parts.next()?.trim().parse().ok()?;
+    let y = parts.next()?.trim().parse().ok()?;
+    Some((x, y))
+}
+
+fn padding(matches: &ArgMatches) -> usize {
+    matches
+        .value_of("padding")
+        .unwrap()
+        .parse()
+        .expect("padding must be a number")
+}
+
+fn generations(matches: &ArgMatches) -> usize {
+    matches
+        .value_of("generations")
+        .unwrap()
+        .parse()
+        .expect("generations must be a number")
+}
+
+fn gps(matches: &ArgMatches) -> f64 {
+    matches
+        .value_of("gps")
+        .unwrap()
+        .parse()
+        .ok()
+        .filter(|gps: &f64| *gps > 0.0)
+        .expect("generations a second must be a positive number")
+}
+
+fn color_mode(matches: &ArgMatches) -> ColorMode {
+    if matches.is_present("random_color") {
+        return ColorMode::Cell;
+    }
+    match matches.value_of("color_mode") {
+        Some("cell") => ColorMode::Cell,
+        Some("flicker") => ColorMode::Flicker,
+        _ => ColorMode::Team,
+    }
+}
+
+fn max_frame_skip(matches: &ArgMatches) -> usize {
+    matches
+        .value_of("max_frame_skip")
+        .unwrap()
+        .parse()
+        .expect("max frame skip must be a number")
+}
+
+fn history_bytes(matches: &ArgMatches) -> usize {
+    let mebibytes: usize = matches
+        .value_of("history")
+        .unwrap()
+        .parse()
+        .expect("history must be a number");
+    mebibytes.saturating_mul(1 << 20)
+}
+
+fn steps_per_frame(matches: &ArgMatches) -> usize {
+    matches
+        .value_of("steps_per_frame")
+        .unwrap()
+        .parse()
+        .ok()
+        .filter(|steps| *steps > 0)
+        .expect("steps per frame must be a positive number")
+}
+
+fn headless(matches: &ArgMatches) {
+    let mut world = load_world(matches);
+
+    for _ in 0..generations(matches) {
+        world.simulate();
+    }
+
+    print!("{}", Format::Native.write(&world.to_pattern()));
+}
+
+fn render(matches: &ArgMatches) {
+    let mut world = load_world(matches);
+    let scale: usize = matches
+        .value_of("scale")
+        .unwrap()
+        .parse()
+        .expect("scale must be a number");
+    let output = matches.value_of("output").unwrap();
+
+    if output.ends_with(".png") {
+        world.run_with_callback(generations(matches), |_| {});
+
+        let (width, height) = buffer_size(&world);
+        let mut window_buffer = WindowBuffer::new(width, height);
+        let palette = default_palette(world.rule().states());
+        let visible = ((0, 0), (width, height));
+        draw_world(
+            &world,
+            &mut window_buffer,
+            visible,
+            &HashSet::new(),
+            ColorMode::Team,
+            &palette,
+            &mut None,
+        );
+        let file = File::create(output).expect("unable to create file");
+        write_png(file, &window_buffer, scale).expect("unable to write png");
+        return;
+    }
+
+    if output.ends_with(".svg") {
+        world.run_with_callback(generations(matches), |_| {});
+
+        let options = SvgOptions {
+            cell_size: scale,
+            alive_color: hex_color(matches, "color"),
+            background_color: hex_color(matches, "background"),
+        };
+        fs::write(output, to_svg(&world.to_pattern(), &options)).expect("unable to write svg");
+        return;
+    }
+
+    let (buffer_width, buffer_height) = buffer_size(&world);
+    let mut window_buffer = WindowBuffer::new(buffer_width, buffer_height);
+    let width = buffer_width * scale;
+    let height = buffer_height * scale;
+
+    let file = File::create(output).expect("unable to create file");
+    let mut encoder = gif::Encoder::new(file, width as u16, height as u16, &[0, 0, 0, 0xff, 0, 0])
+        .expect("unable to write gif");
+    encoder
+        .set(gif::Repeat::Infinite)
+        .expect("unable to write gif");
+
+    let mut drawn = None;
+    for _ in 0..generations(matches) {
+        let palette = default_palette(world.rule().states());
+        let visible = ((0, 0), (buffer_width, buffer_height));
+        draw_world(
+            &world,
+            &mut window_buffer,
+            visible,
+            &HashSet::new(),
+            ColorMode::Team,
+            &palette,
+            &mut drawn,
+        );
+        let mut pixels = vec![0; width * height];
+        for py in 0..height {
+            for px in 0..width {
+                let pixel = window_buffer.buffer[(py / scale) * buffer_width + px / scale];
+                pixels[py * width + px] = u8::from(pixel == 0xff0000);
+            }
+        }
+
+        let mut frame = gif::Frame::from_indexed_pixels(width as u16, height as u16, &pixels, None);
+        frame.delay = GIF_FRAME_DELAY;
+        encoder.write_frame(&frame).expect("unable to write gif");
+
+        world.simulate();
+    }
+}
+
+fn hex_color(matches: &ArgMatches, name: &str) -> u32 {
+    let value = matches.value_of(name).unwrap();
+    u32::from_str_radix(value.trim_start_matches('#'), 16)
+        .unwrap_or_else(|_| panic!("{} must be a hex color", name))
+}
+
+fn analyze(matches: &ArgMatches) {
+    let mut world = load_world(matches);
+    let generations = generations(matches);
+    let mut seen: HashMap<u64, usize> = HashMap::new();
+
+    for generation in 0..=generations {
+        if let Some(first_seen) = seen.insert(world.state_hash(), generation) {
+            println!("generation: {}", generation);
+            println!("population: {}", world.population());
+            println!(
+                "period: {} (from generation {})",
+                generation - first_seen,
+                first_seen
+            );
+            return;
+        }
+
+        if generation < generations {
+            world.simulate();
+        }
+    }
+
+    println!("generation: {}", generations);
+    println!("population: {}", world.population());
+    println!("period: none detected");
+}
+
+/// The seeds `bench` times each engine on: a busy one, a small one that
+/// moves and a small one that grows.
+const BENCH_SEEDS: [&str; 3] = ["soup", "glider", "r-pentomino"];
+
+fn bench(matches: &ArgMatches) {
+    if matches.is_present("throughput") {
+        return throughput(matches);
+    }
+
+    let generations = generations(matches);
+    let sizes = bench_sizes(matches);
+
+    // The time each engine took a generation, in microseconds, and the
+    // bytes it took up by the end, for each seed and size.
+    let mut results = Vec::new();
+    for seed in &BENCH_SEEDS {
+        for size in &sizes {
+            // Without edges, so every engine runs the same world.
+            let mut world = World::new(*size, *size);
+            world.set_boundary(Boundary::Dead);
+            match catalog_pattern(seed) {
+                Some(pattern) => {
+                    let (x, y) = (
+                        size.saturating_sub(pattern.width) / 2,
+                        size.saturating_sub(pattern.height) / 2,
+                    );
+                    world
+                        .seed_pattern_at(&pattern, x, y)
+                        .unwrap_or_else(|e| panic!("{}", e));
+                }
+                None => world.seed_random(),
+            }
+
+            let timings: Vec<Option<(f64, usize)>> = Engine::NAMES
+                .iter()
+                .map(|name| {
+                    let engine: Engine = name.parse().unwrap();
+                    let mut grid = engine.build(&world).ok()?;
+                    let start = time::Instant::now();
+                    for _ in 0..generations {
+                        grid.simulate();
+                    }
+                    let elapsed = start.elapsed().as_secs_f64() * 1e6;
+                    Some((elapsed / generations.max(1) as f64, grid.memory_usage()))
+                })
+                .collect();
+            results.push((seed, size, timings));
+        }
+    }
+
+    let print_table = |title: String, cell: &dyn Fn((f64, usize)) -> String| {
+        println!("{}; - when an engine can't run", title);
+        print!("{:<12} {:>6}", "seed", "size");
+        for name in &Engine::NAMES {
+            print!(" {:>10}", name);
+        }
+        println!();
+        for (seed, size, timings) in &results {
+            print!("{:<12} {:>6}", seed, size);
+            for timing in timings {
+                print!(" {:>10}", timing.map_or("-".to_string(), cell));
+            }
+            println!();
+        }
+    };
+    print_table(
+        format!(
+            "Microseconds per generation over {} generations{}",
+            generations,
+            if cfg!(feature = "parallel") {
+                ", with dense in parallel"
+            } else {
+                ""
+            }
+        ),
+        &|(micros, _)| format!("{:.1}", micros),
+    );
+    println!();
+    print_table(
+        "Kilobytes taken by the last generation".to_string(),
+        &|(_, bytes)| format!("{:.1}", bytes as f64 / 1024.0),
+    );
+}
+
+fn bench_sizes(matches: &ArgMatches) -> Vec<usize> {
+    matches
+        .value_of("sizes")
+        .unwrap()
+        .split(',')
+        .map(|size| {
+            size.trim()
+                .parse()
+                .ok()
+                .filter(|size| *size > 0)
+                .expect("sizes must be positive numbers")
+        })
+        .collect()
+}
+
+/// Runs a random soup under the given engine, rule and boundary for a fixed
+/// time at each size, printing a row of tab-separated values for each so
+/// runs can be compared from one build to the next.
+fn throughput(matches: &ArgMatches) {
+    let seconds: f64 = matches
+        .value_of("seconds")
+        .unwrap()
+        .parse()
+        .ok()
+        .filter(|seconds| *seconds > 0.0)
+        .expect("seconds must be a positive number");
+    let duration = time::Duration::from_secs_f64(seconds);
+    let engine: Engine = matches
+        .value_of("engine")
+        .unwrap_or("dense")
+        .parse()
+        .unwrap();
+    let boundary = matches.value_of("boundary").unwrap_or("wrap");
+
+    println!("engine\trule\tboundary\tsize\tseconds\tgenerations\tgenerations_per_second\tcell_updates_per_second");
+    for size in bench_sizes(matches) {
+        let mut world = World::new(size, size);
+        if let Some(rule) = matches.value_of("rule") {
+            world.set_rule(rule.parse().unwrap_or_else(|e| panic!("{}", e)));
+        }
+        world.set_boundary(boundary.parse().unwrap());
+        world.seed_random();
+        let mut grid = engine.build(&world).unwrap_or_else(|e| panic!("{}", e));
+
+        let start = time::Instant::now();
+        let mut generations = 0;
+        while start.elapsed() < duration {
+            grid.simulate();
+            generations += 1;
+        }
+        let elapsed = start.elapsed().as_secs_f64();
+        let per_second = generations as f64 / elapsed;
+        println!(
+            "{}\t{}\t{}\t{}\t{:.3}\t{}\t{:.1}\t{:.0}",
+            engine,
+            world.rule(),
+            boundary,
+            size,
+            elapsed,
+            generations,
+            per_second,
+            per_second * (size * size) as f64,
+        );
+    }
+}
+
+fn export(matches: &ArgMatches) {
+    let mut world = load_world(matches);
+    let format: Format = matches.value_of("format").unwrap().parse().unwrap();
+
+    world.run_with_callback(generations(matches), |_| {});
+    let output = format.write(&world.to_pattern());
+
+    if let Some(path) = matches.value_of("output") {
+        fs::write(path, output).expect("unable to write file");
+    } else {
+        print!("{}", output);
+    }
+}
+
+fn convert(matches: &ArgMatches) {
+    let output_path = matches.value_of("output");
+    let to = match matches.value_of("to") {
+        Some(to) => to.parse().unwrap(),
+        None => output_path
+            .and_then(|path| Format::from_extension(Path::new(path)))
+            .unwrap_or_else(|| {
+                clap::Error::with_description(
+                    "unable to infer the output format, please specify --to",
+                    clap::ErrorKind::MissingRequiredArgument,
+                )
+                .exit()
+            }),
+    };
+
+    let input =
+        fs::read_to_string(matches.value_of("input").unwrap()).expect("unable to read file");
+    let pattern = match matches.value_of("from") {
+        Some(from) => from.parse::<Format>().unwrap().parse(&input),
+        None => Format::parse_auto(&input),
+    }
+    .unwrap_or_else(|e| panic!("{}", e));
+    let output = to.write(&pattern);
+
+    if let Some(path) = output_path {
+        fs::write(path, output).expect("unable to write file");
+    } else {
+        print!("{}", output);
+    }
+}
+
+fn run(matches: &ArgMatches) {
+    let session = match matches.value_of("resume") {
+        Some(path) => fs::read_to_string(path)
+            .map_err(SeedError::from)
+            .and_then(|session| Session::parse(&session))
+            .unwrap_or_else(|e| panic!("{}: {}", path, e)),
+        None => Session::new(load_world(matches)),
+    };
+    let mut world = session.world;
+    // Paused worlds can still be edited and looked around, but don't move on.
+    let mut paused = session.paused;
+    let mut ants: Vec<Ant> = matches
+        .values_of("ant")
+        .map(|ants| {
+            ants.map(|ant| {
+                let parsed: Ant = ant.parse().unwrap_or_else(|e| panic!("{}", e));
+                if parsed.x >= world.width || parsed.y >= world.height {
+                    panic!("ant {} is outside the world", ant);
+                }
+                parsed
+            })
+            .collect()
+        })
+        .unwrap_or_default();
+    let elementary: Option<Elementary> = matches
+        .value_of("elementary")
+        .map(|rule| rule.parse().unwrap_or_else(|e| panic!("{}", e)));
+    if elementary.is_some() && !world.row(world.height - 1).iter().any(|c| c.alive) {
+        // Start from a single cell so there's something to scroll.
+        world.toggle_cell(world.width / 2, world.height - 1);
+    }
+    let mut lenia = if matches.is_present("lenia") {
+        Some(Lenia::from_world(&world))
+    } else {
+        None
+    };
+    let mut world3d = matches.value_of("life_3d").map(|rule| {
+        let rule = rule.parse().unwrap_or_else(|e| panic!("{}", e));
+        World3d::from_world(&world, LIFE_3D_DEPTH, rule)
+    });
+    let mut infinite = if matches.is_present("infinite") {
+        Some(InfiniteWorld::from_world(&world).unwrap_or_else(|e| panic!("{}", e)))
+    } else {
+        None
+    };
+    let mut hashlife = if matches.is_present("hashlife") {
+        Some(HashLife::from_world(&world).unwrap_or_else(|e| panic!("{}", e)))
+    } else {
+        None
+    };
+    // How many generations, as a power of two, HashLife moves on a frame.
+    let mut hashlife_log2 = 0;
+    // Another way of storing the world's cells, which takes over running it.
+    let mut grid: Option<Box<dyn Grid>> = match matches.value_of("engine") {
+        None | Some("dense") => None,
+        Some(engine) => {
+            let engine: Engine = engine.parse().unwrap();
+            Some(engine.build(&world).unwrap_or_else(|e| panic!("{}", e)))
+        }
+    };
+    // Generations move on at a fixed rate, and frames are drawn at another.
+    let mut pacer = Pacer::new(gps(matches), time::Instant::now());
+    let mut frames = Pacer::new(FRAMES_PER_SECOND, time::Instant::now());
+    // Plain worlds are moved on by a thread of their own, and the window
+    // shows the newest generation it's sent back.
+    let mut simulator = if lenia.is_none()
+        && world3d.is_none()
+        && grid.is_none()
+        && infinite.is_none()
+        && hashlife.is_none()
+        && elementary.is_none()
+        && ants.is_empty()
+    {
+        Some(Simulator::spawn(world.clone(), pacer))
+    } else {
+        None
+    };
+    let mut steps = steps_per_frame(matches);
+    if let Some(simulator) = &mut simulator {
+        simulator.set_steps(steps);
+        simulator.set_paused(paused);
+    }
+    // The cell at the top left of the window in an infinite world.
+    let mut viewport = (0, 0);
+
+    let tile = matches.is_present("tile");
+    let tiles = if tile { 3 } else { 1 };
+
+    let (buffer_width, buffer_height) = buffer_size(&world);
+    // Bigger worlds than this are shown a window-full at a time, unless
+    // they're tiled.
+    let mut camera = if tile {
+        Camera::new(buffer_width, buffer_height)
+    } else {
+        Camera::new(buffer_width.min(WIDTH), buffer_height.min(HEIGHT))
+    };
+    let (view_width, view_height) = camera.size();
+    // minifb 0.11 keeps drawing at the size the window opened at and never
+    // reports a new one, so resizing it would draw past the end of its
+    // buffer. It stays the size it opened at until minifb can be upgraded.
     let mut window = Window::new(
         "Game of Life",
-        world.width as usize,
-        world.height as usize,
+        view_width * tiles,
+        view_height * tiles,
         WindowOptions {
-            scale: Scale::X2,
+            scale: if tile { Scale::X1 } else { Scale::X2 },
             ..WindowOptions::default()
         },
     )
     .unwrap_or_else(|e| {
         panic!("{}", e);
     });
-    let mut window_buffer = WindowBuffer::new(world.width as usize, world.height as usize);
+    let mut window_buffer = WindowBuffer::new(buffer_width, buffer_height);
+    let mut view_buffer = WindowBuffer::new(view_width, view_height);
+    let mut rendered_camera = None;
+    let mut drawn_world = None;
+    let mut tiled_buffer = WindowBuffer::new(view_width * tiles, view_height * tiles);
     let mut mouse_down = false;
-    let mut cells_to_toggle: HashSet<(usize, usize)> = HashSet::new();
+    let mut mouse_pos = (0, 0);
+    let mut selecting = false;
+    let mut selection: Option<((usize, usize), (usize, usize))> = None;
+    let mut cells_to_toggle: HashSet<(usize, usize)> = session.pending_edits.into_iter().collect();
+    // The state left-clicks paint, picked with the number keys, or `None` to
+    // toggle cells between alive and dead.
+    let mut paint_state: Option<u8> = None;
+    let palette: Vec<u32> = match matches.values_of("palette") {
+        Some(colors) => colors
+            .map(|color| {
+                u32::from_str_radix(color.trim_start_matches('#'), 16)
+                    .unwrap_or_else(|_| panic!("{} must be a hex color", color))
+            })
+            .collect(),
+        None => default_palette(world.rule().states()),
+    };
+
+    // When the rule file was modified as of the last read, to tell when it
+    // changes. Starting at the epoch reads it on the first frame.
+    let mut rule_file_modified = Some(time::UNIX_EPOCH);
+    let mut shown_title = None;
+    // The generations the window's shown, newest last, for stepping back
+    // through. Only plain worlds keep them.
+    let mut history = History::new(history_bytes(matches));
+
+    let color_mode = color_mode(matches);
+    let max_frame_skip = max_frame_skip(matches);
+    // How many frames in a row haven't been drawn, and whether the last
+    // frame's generations took longer than a frame.
+    let mut skipped = 0;
+    let mut simulated_late = false;
+    let mut profile = if matches.is_present("profile") {
+        Some(Profile::new())
+    } else {
+        None
+    };
 
     while window.is_open() {
-        draw_world(
-            &world,
-            &mut window_buffer,
-            &cells_to_toggle,
-            matches.is_present("random_color"),
-        );
-        window
-            .update_with_buffer(&window_buffer.buffer)
-            .expect("unable to update window");
+        let frame_start = time::Instant::now();
+        if let Some(latest) = simulator.as_mut().and_then(Simulator::latest) {
+            history.push(mem::replace(&mut world, latest));
+        }
+        // Whether the world's been edited this frame, and so has to be sent
+        // to the simulation thread.
+        let mut edited = false;
+
+        // The generation and population. Lenia and 3D worlds don't count
+        // either.
+        let stats = if lenia.is_some() || world3d.is_some() {
+            None
+        } else if let Some(grid) = &grid {
+            Some((grid.generation() as u64, grid.population() as u64))
+        } else if let Some(infinite) = &infinite {
+            Some((
+                infinite.generation as u64,
+                infinite.live_cells_count() as u64,
+            ))
+        } else if let Some(hashlife) = &hashlife {
+            Some((hashlife.generation(), hashlife.population()))
+        } else {
+            Some((world.generation() as u64, world.population() as u64))
+        };
+        if Some((stats, paused)) != shown_title {
+            window.set_title(&window_title(stats, paused));
+            shown_title = Some((stats, paused));
+        }
+
+        // Drawing's skipped for a few frames at a time while generations are
+        // taking longer than a frame, so they don't fall further behind.
+        // Input still has to be picked up.
+        let drawn;
+        if skipped < max_frame_skip && simulated_late {
+            skipped += 1;
+            drawn = time::Instant::now();
+            window.update();
+        } else {
+            skipped = 0;
+            if let Some(lenia) = &lenia {
+                draw_lenia(&world, lenia, &mut window_buffer, &cells_to_toggle);
+            } else if let Some(world3d) = &world3d {
+                draw_world3d(&world, world3d, &mut window_buffer, &cells_to_toggle);
+            } else if let Some(grid) = &grid {
+                draw_grid(&world, grid.as_ref(), &mut window_buffer, &cells_to_toggle);
+            } else if let Some(infinite) = &infinite {
+                let cells =
+                    infinite.live_cells_in(viewport, (world.width as i64, world.height as i64));
+                draw_infinite(
+                    &world,
+                    cells,
+                    viewport,
+                    &mut window_buffer,
+                    &cells_to_toggle,
+                );
+            } else if let Some(hashlife) = &hashlife {
+                let cells =
+                    hashlife.live_cells_in(viewport, (world.width as i64, world.height as i64));
+                draw_infinite(
+                    &world,
+                    cells,
+                    viewport,
+                    &mut window_buffer,
+                    &cells_to_toggle,
+                );
+            } else {
+                draw_world(
+                    &world,
+                    &mut window_buffer,
+                    camera.visible((buffer_width, buffer_height)),
+                    &cells_to_toggle,
+                    color_mode,
+                    &palette,
+                    &mut drawn_world,
+                );
+            }
+            draw_regions(&world, &mut window_buffer);
+            if let Some(selection) = selection {
+                draw_selection(&world, &mut window_buffer, selection);
+            }
+            for ant in &ants {
+                draw_cell(&world, &mut window_buffer, ant.x, ant.y, 0x00ff00);
+            }
+            if let Some(drawn) = &mut drawn_world {
+                drawn.overlaid.extend(region_edges(&world));
+                if let Some(selection) = selection {
+                    drawn.overlaid.extend(selection_edges(selection));
+                }
+                drawn.overlaid.extend(ants.iter().map(|ant| (ant.x, ant.y)));
+            }
+            // Only the part of the view showing changed pixels needs redrawing,
+            // unless the camera's moved.
+            let dirty = window_buffer.take_dirty();
+            if rendered_camera != Some(camera) {
+                camera.render(&window_buffer, &mut view_buffer);
+                rendered_camera = Some(camera);
+            } else if let Some(dirty) = dirty {
+                camera.render_rect(&window_buffer, &mut view_buffer, dirty);
+            }
+            drawn = time::Instant::now();
+            if tile {
+                view_buffer.draw_tiled(&mut tiled_buffer);
+                window
+                    .update_with_buffer(&tiled_buffer.buffer)
+                    .expect("unable to update window");
+            } else {
+                window
+                    .update_with_buffer(&view_buffer.buffer)
+                    .expect("unable to update window");
+            }
+        }
+        let presented = time::Instant::now();
+
+        if window.is_key_pressed(Key::Space, KeyRepeat::No) {
+            paused = !paused;
+            if let Some(simulator) = &mut simulator {
+                simulator.set_paused(paused);
+            }
+        }
+
+        // Stepping back pauses the world at the generation before.
+        if window.is_key_pressed(Key::Comma, KeyRepeat::Yes) {
+            if let Some(previous) = history.pop() {
+                world = previous;
+                edited = true;
+                if !paused {
+                    paused = true;
+                    if let Some(simulator) = &mut simulator {
+                        simulator.set_paused(true);
+                    }
+                }
+            }
+        }
+
+        // Paused worlds move on a generation at a time with . or N.
+        let stepped = paused
+            && (window.is_key_pressed(Key::Period, KeyRepeat::Yes)
+                || window.is_key_pressed(Key::N, KeyRepeat::Yes));
+
+        if window.is_key_pressed(Key::I, KeyRepeat::No) {
+            world.invert();
+            edited = true;
+        }
+
+        if window.is_key_pressed(Key::W, KeyRepeat::No) {
+            hashlife_log2 = if hashlife_log2 == 0 {
+                HASHLIFE_WARP_LOG2
+            } else {
+                0
+            };
+        }
+
+        let new_steps = if window.is_key_pressed(Key::RightBracket, KeyRepeat::No) {
+            (steps * 2).min(MAX_STEPS_PER_FRAME)
+        } else if window.is_key_pressed(Key::LeftBracket, KeyRepeat::No) {
+            (steps / 2).max(1)
+        } else {
+            steps
+        };
+        if new_steps != steps {
+            steps = new_steps;
+            if let Some(simulator) = &mut simulator {
+                simulator.set_steps(steps);
+            }
+        }
+
+        if infinite.is_some() || hashlife.is_some() {
+            let step = CHUNK_SIZE / 2;
+            for (key, (x, y)) in &[
+                (Key::Left, (-step, 0)),
+                (Key::Right, (step, 0)),
+                (Key::Up, (0, -step)),
+                (Key::Down, (0, step)),
+            ] {
+                if window.is_key_pressed(*key, KeyRepeat::Yes) {
+                    viewport = (viewport.0 + x, viewport.1 + y);
+                }
+            }
+        } else if !tile {
+            let buffer_size = (buffer_width, buffer_height);
+            let (_, (width, height)) = camera.visible(buffer_size);
+            let (x_step, y_step) = ((width / 4).max(1) as isize, (height / 4).max(1) as isize);
+            for (key, step) in &[
+                (Key::Left, (-x_step, 0)),
+                (Key::Right, (x_step, 0)),
+                (Key::Up, (0, -y_step)),
+                (Key::Down, (0, y_step)),
+            ] {
+                if window.is_key_pressed(*key, KeyRepeat::Yes) {
+                    camera.pan(*step, buffer_size);
+                }
+            }
+            if window.is_key_pressed(Key::Equal, KeyRepeat::No) {
+                camera.zoom_in(buffer_size);
+            }
+            if window.is_key_pressed(Key::Minus, KeyRepeat::No) {
+                camera.zoom_out(buffer_size);
+            }
+        }
+
+        if let Some(path) = matches.value_of("rule_file") {
+            let force = window.is_key_pressed(Key::R, KeyRepeat::No);
+            match reload_rule(path, &mut rule_file_modified, force) {
+                Some(Ok(rule)) => {
+                    world.set_rule(rule);
+                    edited = true;
+                }
+                Some(Err(e)) => eprintln!("{}: {}", path, e),
+                None => {}
+            }
+        }
+
+        if window.is_key_pressed(Key::T, KeyRepeat::No) {
+            paint_state = None;
+        }
+
+        for (state, key) in STATE_KEYS.iter().enumerate() {
+            // With teams, 1 and up paint live cells into each team.
+            let states = world.rule().states().max(world.rule().teams() + 1);
+            if window.is_key_pressed(*key, KeyRepeat::No) && state < states.into() {
+                paint_state = Some(state as u8);
+            }
+        }
+
+        let ctrl_down = window.is_key_down(Key::LeftCtrl) || window.is_key_down(Key::RightCtrl);
+        if window.is_key_pressed(Key::S, KeyRepeat::No) {
+            if ctrl_down {
+                save_session(&mut world, &cells_to_toggle, paused);
+                // Saving reseeds any chance, which the simulation thread
+                // has to pick up to carry on the same as a resumed session.
+                edited = true;
+            } else {
+                export_seed(&world);
+            }
+        }
+
+        if window.is_key_pressed(Key::E, KeyRepeat::No) {
+            export_rle(&world);
+        }
+
+        if window.is_key_pressed(Key::P, KeyRepeat::No) {
+            export_png(&window_buffer);
+        }
+
+        if ctrl_down && window.is_key_pressed(Key::V, KeyRepeat::No) {
+            paste_clipboard(&mut world, mouse_pos);
+            edited = true;
+        }
 
-        window.get_mouse_pos(MouseMode::Discard).map(|(x, y)| {
-            let x = x as usize;
-            let y = y as usize;
+        if ctrl_down && window.is_key_pressed(Key::C, KeyRepeat::No) {
+            if let Some(selection) = selection {
+                copy_selection(&world, selection);
+            }
+        }
+
+        let mouse_cell = window.get_mouse_pos(MouseMode::Discard).and_then(|(x, y)| {
+            let view_pixel = (x as usize % view_width, y as usize % view_height);
+            let (px, py) = camera.to_world(view_pixel, (buffer_width, buffer_height))?;
+            cell_at_pixel(&world, px, py)
+        });
+        if let Some((x, y)) = mouse_cell {
+            mouse_pos = (x, y);
+
+            // Dragging with the right button selects a rectangle to copy.
+            if window.get_mouse_down(MouseButton::Right) {
+                if !selecting {
+                    selecting = true;
+                    selection = Some(((x, y), (x, y)));
+                } else if let Some((start, _)) = selection {
+                    selection = Some((start, (x, y)));
+                }
+            } else {
+                selecting = false;
+            }
 
             if window.get_mouse_down(MouseButton::Left) {
                 if !mouse_down {
@@ -93,51 +1588,585 @@ fn main() {
                 mouse_down = false;
 
                 for (x, y) in &cells_to_toggle {
-                    world.toggle_cell(*x, *y);
+                    if let Some(lenia) = &mut lenia {
+                        let value = if lenia.get(*x, *y) > 0.5 { 0.0 } else { 1.0 };
+                        lenia.set(*x, *y, value);
+                        continue;
+                    }
+                    if let Some(world3d) = &mut world3d {
+                        let z = world3d.depth / 2;
+                        world3d.set(*x, *y, z, !world3d.get(*x, *y, z));
+                        continue;
+                    }
+                    if let Some(grid) = &mut grid {
+                        grid.toggle(*x, *y);
+                        continue;
+                    }
+                    if let Some(infinite) = &mut infinite {
+                        let (x, y) = (viewport.0 + *x as i64, viewport.1 + *y as i64);
+                        infinite.set(x, y, !infinite.get(x, y));
+                        continue;
+                    }
+                    if let Some(hashlife) = &mut hashlife {
+                        let (x, y) = (viewport.0 + *x as i64, viewport.1 + *y as i64);
+                        hashlife.set(x, y, !hashlife.get(x, y));
+                        continue;
+                    }
+
+                    match paint_state {
+                        Some(team) if team > 0 && world.rule().teams() > 1 => {
+                            world.set_cell_team(*x, *y, team - 1)
+                        }
+                        Some(state) => world.set_cell_state(*x, *y, state),
+                        None => world.toggle_cell(*x, *y),
+                    }
+                    edited = true;
                 }
-                cells_to_toggle.clear();;
+                cells_to_toggle.clear();
             }
-        });
+        }
 
-        let before = time::Instant::now();
-        world.simulate();
+        if let Some(simulator) = &mut simulator {
+            if edited {
+                simulator.replace(world.clone());
+            }
+            // After any edits, so they're stepped from.
+            if stepped {
+                simulator.step();
+            }
+        }
+        let handled = time::Instant::now();
 
-        let after = time::Instant::now();
-        let simulate_duration = after - before;
-        if let Some(d) = DESIRED_SLEEP_TIME.checked_sub(simulate_duration) {
-            thread::sleep(d);
+        // However many generations have come due since the last frame,
+        // which is none for most frames when they're drawn faster than
+        // generations move on. The simulation thread keeps its own time for
+        // plain worlds. They still come due while paused, so none are owed
+        // after.
+        let due = pacer.take_due(time::Instant::now()) as usize;
+        let generations = if paused {
+            usize::from(stepped)
         } else {
-            eprintln!(
-                "simulation too slow: {:?} (desired: {:?})",
-                simulate_duration, DESIRED_SLEEP_TIME
-            );
+            due * steps
+        };
+        if simulator.is_none() {
+            for _ in 0..generations {
+                if let Some(lenia) = &mut lenia {
+                    lenia.step();
+                } else if let Some(world3d) = &mut world3d {
+                    world3d.simulate();
+                } else if let Some(grid) = &mut grid {
+                    grid.simulate();
+                } else if let Some(infinite) = &mut infinite {
+                    infinite.simulate();
+                } else if let Some(hashlife) = &mut hashlife {
+                    hashlife.advance(hashlife_log2);
+                } else if let Some(elementary) = elementary {
+                    elementary.step(&mut world);
+                } else if ants.is_empty() {
+                    world.simulate();
+                } else {
+                    for _ in 0..ANT_STEPS_PER_FRAME {
+                        for ant in &mut ants {
+                            ant.step(&mut world);
+                        }
+                    }
+                }
+            }
+        }
+
+        simulated_late = handled.elapsed() > frames.interval();
+
+        if let Some(profile) = &mut profile {
+            // Plain worlds are simulated on their own thread, alongside the
+            // rest of the frame.
+            let simulated = handled.elapsed()
+                + simulator
+                    .as_mut()
+                    .map_or(time::Duration::ZERO, Simulator::take_time);
+            profile.record("draw", drawn - frame_start);
+            profile.record("present", presented - drawn);
+            profile.record("input", handled - presented);
+            profile.record("simulate", simulated);
+        }
+
+        // The window's redrawn at its own steady rate, however many
+        // generations that turns out to be.
+        thread::sleep(
+            frames
+                .next_due()
+                .saturating_duration_since(time::Instant::now()),
+        );
+        frames.take_due(time::Instant::now());
+    }
+
+    if let Some(profile) = profile {
+        eprint!("{}", profile.report());
+        // Lenia and 3D worlds aren't measured.
+        let memory = if lenia.is_some() || world3d.is_some() {
+            None
+        } else if let Some(grid) = &grid {
+            Some(grid.memory_usage())
+        } else if let Some(infinite) = &infinite {
+            Some(infinite.memory_usage())
+        } else if let Some(hashlife) = &hashlife {
+            Some(hashlife.memory_usage())
+        } else {
+            Some(world.memory_usage())
+        };
+        if let Some(bytes) = memory {
+            eprintln!("memory: {:.1} KiB", bytes as f64 / 1024.0);
         }
     }
 }
 
-fn draw_world(
+fn window_title(stats: Option<(u64, u64)>, paused: bool) -> String {
+    let title = match stats {
+        Some((generation, population)) => format!(
+            "Game of Life - generation {}, population {}",
+            generation, population
+        ),
+        None => "Game of Life".to_string(),
+    };
+    if paused {
+        title + " (paused)"
+    } else {
+        title
+    }
+}
+
+/// Stamps a pattern from the clipboard, in any supported format, with its
+/// top left corner at the cursor.
+fn paste_clipboard(world: &mut World, (x, y): (usize, usize)) {
+    let text = match Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+        Ok(text) => text,
+        Err(e) => return eprintln!("unable to read clipboard: {}", e),
+    };
+
+    let result = Pattern::from_reader(text.as_bytes())
+        .and_then(|pattern| world.seed_pattern_at(&pattern, x, y));
+    if let Err(e) = result {
+        eprintln!("unable to paste clipboard: {}", e);
+    }
+}
+
+/// Normalises the corners of a selection into `(x, y, width, height)`.
+fn selection_rect(
+    ((x1, y1), (x2, y2)): ((usize, usize), (usize, usize)),
+) -> (usize, usize, usize, usize) {
+    (
+        x1.min(x2),
+        y1.min(y2),
+        x1.max(x2) - x1.min(x2) + 1,
+        y1.max(y2) - y1.min(y2) + 1,
+    )
+}
+
+/// Copies the selected cells to the clipboard as RLE.
+fn copy_selection(world: &World, selection: ((usize, usize), (usize, usize))) {
+    let (x, y, width, height) = selection_rect(selection);
+    let rle = Format::Rle.write(&world.region_pattern(x, y, width, height));
+
+    match Clipboard::new().and_then(|mut clipboard| clipboard.set_text(rle)) {
+        Ok(()) => println!("copied {}x{} selection", width, height),
+        Err(e) => eprintln!("unable to write clipboard: {}", e),
+    }
+}
+
+/// Saves everything `--resume` needs to carry on from this point.
+fn save_session(world: &mut World, cells_to_toggle: &HashSet<(usize, usize)>, paused: bool) {
+    let path = timestamped_path("gol");
+    if let Some(chance) = world.chance_mut() {
+        chance.reseed();
+    }
+    let mut session = Session::new(world.clone());
+    session.pending_edits = cells_to_toggle.iter().cloned().collect();
+    session.pending_edits.sort();
+    session.paused = paused;
+
+    match fs::write(&path, session.write()) {
+        Ok(()) => println!("saved session {}", path),
+        Err(e) => eprintln!("unable to save session {}: {}", path, e),
+    }
+}
+
+/// Quick-saves the world as a native seed file that `--seed` can load.
+fn export_seed(world: &World) {
+    let path = timestamped_path("txt");
+
+    match fs::write(&path, Format::Native.write(&world.to_pattern())) {
+        Ok(()) => println!("saved {}", path),
+        Err(e) => eprintln!("unable to save {}: {}", path, e),
+    }
+}
+
+/// Exports the live cells, trimmed to the smallest rectangle holding them.
+fn export_rle(world: &World) {
+    let path = timestamped_path("rle");
+    let rle = match world.bounding_box() {
+        Some(((x, y), (width, height))) => {
+            Format::Rle.write(&world.region_pattern(x, y, width, height))
+        }
+        None => world.to_rle(),
+    };
+
+    match fs::write(&path, rle) {
+        Ok(()) => println!("exported {}", path),
+        Err(e) => eprintln!("unable to export {}: {}", path, e),
+    }
+}
+
+fn export_png(window_buffer: &WindowBuffer) {
+    let path = timestamped_path("png");
+
+    let result = File::create(&path)
+        .map_err(png::EncodingError::from)
+        .and_then(|file| write_png(file, window_buffer, 2));
+
+    match result {
+        Ok(()) => println!("exported {}", path),
+        Err(e) => eprintln!("unable to export {}: {}", path, e),
+    }
+}
+
+fn timestamped_path(extension: &str) -> String {
+    let timestamp = time::SystemTime::now()
+        .duration_since(time::UNIX_EPOCH)
+        .expect("system clock is before 1970")
+        .as_secs();
+
+    format!("gol-{}.{}", timestamp, extension)
+}
+
+/// The size of buffer `draw_world` needs. Hexagonal worlds are drawn
+/// sheared, two pixels per cell, with each row half a cell to the right of
+/// the one below it so that every cell touches its six neighbours.
+/// Triangular worlds are drawn two pixels tall, with each triangle three
+/// pixels wide at its base and overlapping its neighbours' points.
+fn buffer_size(world: &World) -> (usize, usize) {
+    match world.rule().neighbourhood() {
+        Neighbourhood::Hexagonal => (world.width * 2 + world.height - 1, world.height),
+        Neighbourhood::Triangular => (world.width * 2 + 1, world.height * 2),
+        _ => (world.width, world.height),
+    }
+}
+
+/// The pixels a cell covers.
+fn cell_pixels(world: &World, x: usize, y: usize) -> Vec<(usize, usize)> {
+    match world.rule().neighbourhood() {
+        Neighbourhood::Hexagonal => {
+            let px = x * 2 + world.height - 1 - y;
+            vec![(px, y), (px + 1, y)]
+        }
+        Neighbourhood::Triangular => {
+            let (point, base) = if (x + y).is_multiple_of(2) {
+                (y * 2, y * 2 + 1)
+            } else {
+                (y * 2 + 1, y * 2)
+            };
+            vec![
+                (x * 2 + 1, point),
+                (x * 2, base),
+                (x * 2 + 1, base),
+                (x * 2 + 2, base),
+            ]
+        }
+        _ => vec![(x, y)],
+    }
+}
+
+fn draw_cell(world: &World, window_buffer: &mut WindowBuffer, x: usize, y: usize, color: u32) {
+    for (px, py) in cell_pixels(world, x, y) {
+        window_buffer.set_pixel(px, py, color);
+    }
+}
+
+/// The columns and rows of the cells that cover a rectangle of pixels, and
+/// maybe a few around it.
+fn visible_cells(
+    world: &World,
+    ((x, y), (width, height)): ((usize, usize), (usize, usize)),
+) -> (Range<usize>, Range<usize>) {
+    match world.rule().neighbourhood() {
+        // Rows are sheared, so any column could be in view.
+        Neighbourhood::Hexagonal => (0..world.width, y..y + height),
+        Neighbourhood::Triangular => (
+            x.saturating_sub(2) / 2..((x + width) / 2 + 1).min(world.width),
+            y / 2..(y + height).div_ceil(2).min(world.height),
+        ),
+        _ => (x..x + width, y..y + height),
+    }
+}
+
+/// The cell drawn at a pixel, if there is one.
+fn cell_at_pixel(world: &World, px: usize, py: usize) -> Option<(usize, usize)> {
+    match world.rule().neighbourhood() {
+        Neighbourhood::Hexagonal => {
+            let x = px.checked_sub(world.height - 1 - py)? / 2;
+            (x < world.width).then_some((x, py))
+        }
+        Neighbourhood::Triangular => {
+            let y = py / 2;
+            (px.saturating_sub(2) / 2..=px / 2)
+                .filter(|&x| x < world.width)
+                .find(|&x| cell_pixels(world, x, y).contains(&(px, py)))
+                .map(|x| (x, y))
+        }
+        _ => Some((px, py)),
+    }
+}
+
+fn draw_selection(
     world: &World,
     window_buffer: &mut WindowBuffer,
+    selection: ((usize, usize), (usize, usize)),
+) {
+    for (x, y) in selection_edges(selection) {
+        draw_cell(world, window_buffer, x, y, 0x808080);
+    }
+}
+
+/// The cells around the edge of a selection.
+fn selection_edges(
+    selection: ((usize, usize), (usize, usize)),
+) -> impl Iterator<Item = (usize, usize)> {
+    let (x, y, width, height) = selection_rect(selection);
+    let (right, bottom) = (x + width - 1, y + height - 1);
+
+    (x..=right)
+        .flat_map(move |sx| vec![(sx, y), (sx, bottom)])
+        .chain((y..=bottom).flat_map(move |sy| vec![(x, sy), (right, sy)]))
+}
+
+/// Outlines each region with its own rule, leaving live cells showing.
+fn draw_regions(world: &World, window_buffer: &mut WindowBuffer) {
+    for (x, y) in region_edges(world) {
+        if !world.get(x, y).is_some_and(|cell| cell.alive) {
+            draw_cell(world, window_buffer, x, y, 0x404040);
+        }
+    }
+}
+
+/// The cells around the edges of the world's regions.
+fn region_edges(world: &World) -> Vec<(usize, usize)> {
+    let mut edges = Vec::new();
+    for region in world.regions() {
+        let right = (region.x + region.width).min(world.width) - 1;
+        let bottom = (region.y + region.height).min(world.height) - 1;
+        edges.extend((region.x..=right).flat_map(|x| vec![(x, region.y), (x, bottom)]));
+        edges.extend((region.y..=bottom).flat_map(|y| vec![(region.x, y), (right, y)]));
+    }
+    edges
+}
+
+/// Electron heads are blue, tails red and conductors yellow.
+fn wireworld_color(state: u8) -> u32 {
+    match state {
+        WIREWORLD_HEAD => 0x0080ff,
+        WIREWORLD_TAIL => 0xff4000,
+        WIREWORLD_CONDUCTOR => 0xffc000,
+        _ => 0,
+    }
+}
+
+/// A random-looking color for the cell at `(x, y)`, the same every time so
+/// it doesn't flicker, and never too dark to see.
+fn cell_color(x: usize, y: usize) -> u32 {
+    let mut hash = ((x as u64) << 32 | y as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash ^= hash >> 32;
+    (hash as u32 & 0xffffff) | 0x404040
+}
+
+/// A color for each state of a cyclic rule, spaced evenly around the
+/// color wheel.
+fn default_palette(states: u8) -> Vec<u32> {
+    (0..states)
+        .map(|state| {
+            let hue = 6.0 * f32::from(state) / f32::from(states);
+            let channel = |offset: f32| {
+                let distance = ((hue + offset) % 6.0 - 3.0).abs();
+                ((distance - 1.0).clamp(0.0, 1.0) * 255.0) as u32
+            };
+            channel(0.0) << 16 | channel(4.0) << 8 | channel(2.0)
+        })
+        .collect()
+}
+
+/// Lenia's values run from black through red and yellow up to white.
+fn heatmap_color(value: f32) -> u32 {
+    let channel = |offset: f32| ((value * 3.0 - offset).clamp(0.0, 1.0) * 255.0) as u32;
+    channel(0.0) << 16 | channel(1.0) << 8 | channel(2.0)
+}
+
+fn draw_lenia(
+    world: &World,
+    lenia: &Lenia,
+    window_buffer: &mut WindowBuffer,
     cells_to_toggle: &HashSet<(usize, usize)>,
-    random_color: bool,
 ) {
     window_buffer.clear();
-    let mut rng = thread_rng();
 
-    for (y, row) in world.cells.iter().enumerate() {
-        for (x, cell) in row.iter().enumerate() {
-            if cell.alive {
-                let color = if random_color {
-                    rng.gen::<u32>()
-                } else {
-                    0xff0000
-                };
-                window_buffer.set_pixel(x, y, color);
+    for y in 0..lenia.height {
+        for x in 0..lenia.width {
+            let value = lenia.get(x, y);
+            if value > 0.0 {
+                draw_cell(world, window_buffer, x, y, heatmap_color(value));
+            }
+        }
+    }
+
+    for (x, y) in cells_to_toggle {
+        draw_cell(world, window_buffer, *x, *y, 0xffffff);
+    }
+}
+
+/// Projects the box onto the screen, shading nearer cells brighter. Edits
+/// toggle cells on the middle layer.
+fn draw_world3d(
+    world: &World,
+    world3d: &World3d,
+    window_buffer: &mut WindowBuffer,
+    cells_to_toggle: &HashSet<(usize, usize)>,
+) {
+    window_buffer.clear();
+
+    for y in 0..world3d.height {
+        for x in 0..world3d.width {
+            if let Some(z) = world3d.nearest(x, y) {
+                let brightness = 0xff - (0xc0 * z / world3d.depth) as u32;
+                draw_cell(world, window_buffer, x, y, brightness << 16);
             }
         }
     }
 
     for (x, y) in cells_to_toggle {
-        window_buffer.set_pixel(*x, *y, 0xffffff);
+        draw_cell(world, window_buffer, *x, *y, 0xffffff);
+    }
+}
+
+fn draw_grid(
+    world: &World,
+    grid: &dyn Grid,
+    window_buffer: &mut WindowBuffer,
+    cells_to_toggle: &HashSet<(usize, usize)>,
+) {
+    window_buffer.clear();
+
+    for (x, y) in grid.live_cells() {
+        draw_cell(world, window_buffer, x, y, TEAM_COLORS[0]);
+    }
+
+    for (x, y) in cells_to_toggle {
+        draw_cell(world, window_buffer, *x, *y, 0xffffff);
     }
 }
+
+/// Draws the live cells of the window-sized part of an infinite world whose
+/// top left cell is at `viewport`.
+fn draw_infinite(
+    world: &World,
+    cells: impl IntoIterator<Item = (i64, i64)>,
+    viewport: (i64, i64),
+    window_buffer: &mut WindowBuffer,
+    cells_to_toggle: &HashSet<(usize, usize)>,
+) {
+    window_buffer.clear();
+
+    for (x, y) in cells {
+        let (x, y) = ((x - viewport.0) as usize, (y - viewport.1) as usize);
+        draw_cell(world, window_buffer, x, y, TEAM_COLORS[0]);
+    }
+
+    for (x, y) in cells_to_toggle {
+        draw_cell(world, window_buffer, *x, *y, 0xffffff);
+    }
+}
+
+/// Dying cells are blue, fading towards the background as they decay.
+fn decay_color(state: u8, states: u8) -> u32 {
+    0xff * u32::from(states - state + 1) / u32::from(states)
+}
+
+/// Draws the cells covering the `visible` rectangle of pixels. If `drawn`
+/// holds the same view of a world the same size and rule, only the cells
+/// that have changed since, or were drawn over, are repainted. Otherwise
+/// square cells are painted a pixel each and the others over a cleared
+/// buffer.
+fn draw_world(
+    world: &World,
+    window_buffer: &mut WindowBuffer,
+    visible: ((usize, usize), (usize, usize)),
+    cells_to_toggle: &HashSet<(usize, usize)>,
+    color_mode: ColorMode,
+    palette: &[u32],
+    drawn: &mut Option<Drawn>,
+) {
+    let square = !matches!(
+        world.rule().neighbourhood(),
+        Neighbourhood::Hexagonal | Neighbourhood::Triangular
+    );
+    let color = |x: usize, y: usize| {
+        let cell = &world.row(y)[x];
+        if world.rule().family() == Family::Cyclic {
+            palette[usize::from(cell.state()) % palette.len()]
+        } else if world.rule().family() == Family::Wireworld {
+            match cell.state() {
+                0 => 0,
+                state => wireworld_color(state),
+            }
+        } else if cell.alive {
+            match color_mode {
+                ColorMode::Team => TEAM_COLORS[usize::from(cell.team())],
+                ColorMode::Cell => cell_color(x, y),
+                ColorMode::Flicker => rand::random::<u32>(),
+            }
+        } else if cell.state() > 1 {
+            decay_color(cell.state(), world.rule().states())
+        } else {
+            0
+        }
+    };
+
+    let (xs, ys) = visible_cells(world, visible);
+    // Flickering cells change color every frame whether they've changed or
+    // not.
+    let changed = drawn.take().filter(|last| {
+        color_mode != ColorMode::Flicker
+            && last.visible == visible
+            && (last.world.width, last.world.height) == (world.width, world.height)
+            && last.world.rule() == world.rule()
+    });
+    if let Some(last) = changed {
+        // No two cells share a pixel, so they can be painted in any order.
+        let cells = last.world.changed_cells(world);
+        for (x, y) in cells.into_iter().chain(last.overlaid) {
+            if xs.contains(&x) && ys.contains(&y) {
+                draw_cell(world, window_buffer, x, y, color(x, y));
+            }
+        }
+    } else if square {
+        // A row at a time, on separate threads with the `parallel` feature.
+        window_buffer.paint(((xs.start, ys.start), (xs.len(), ys.len())), color);
+    } else {
+        window_buffer.clear();
+        for y in ys {
+            for x in xs.clone() {
+                let color = color(x, y);
+                if color != 0 {
+                    draw_cell(world, window_buffer, x, y, color);
+                }
+            }
+        }
+    }
+
+    for (x, y) in cells_to_toggle {
+        draw_cell(world, window_buffer, *x, *y, 0xffffff);
+    }
+
+    *drawn = Some(Drawn {
+        world: world.clone(),
+        visible,
+        overlaid: cells_to_toggle.iter().cloned().collect(),
+    });
+}
@@ -0,0 +1,91 @@
+//! Keeping a world moving on at a steady number of generations a second,
+//! however long each one takes to work out and draw.
+
+use std::time::{Duration, Instant};
+
+/// The most missed generations made up for at once. Any more than this and
+/// the world just carries on from where it is, rather than trying to catch
+/// up with generations it'll never have time for.
+const MAX_CATCH_UP: u32 = 8;
+
+/// Decides when generations are due, from a target number a second.
+/// Generations that fall behind are made up for by moving on several at
+/// once, so the rate holds when each frame takes longer than it should.
+#[derive(Clone, Copy, Debug)]
+pub struct Pacer {
+    interval: Duration,
+    /// When the next generation is due.
+    due: Instant,
+}
+
+impl Pacer {
+    /// A pacer aiming for `gps` generations a second, starting from `now`.
+    /// Panics unless `gps` is positive.
+    pub fn new(gps: f64, now: Instant) -> Self {
+        assert!(gps > 0.0, "generations a second must be positive");
+        let interval = Duration::from_secs_f64(1.0 / gps);
+        Self {
+            interval,
+            due: now + interval,
+        }
+    }
+
+    /// The time between generations.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// When the next generation is due.
+    pub fn next_due(&self) -> Instant {
+        self.due
+    }
+
+    /// How many generations are due to move on by `now`, counting them as
+    /// done.
+    pub fn take_due(&mut self, now: Instant) -> u32 {
+        if now < self.due {
+            return 0;
+        }
+
+        let behind = (now - self.due).as_nanos() / self.interval.as_nanos().max(1) + 1;
+        if behind <= u128::from(MAX_CATCH_UP) {
+            let behind = behind as u32;
+            self.due += self.interval * behind;
+            behind
+        } else {
+            self.due = now + self.interval;
+            MAX_CATCH_UP
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_due() {
+        let start = Instant::now();
+        let mut pacer = Pacer::new(10.0, start);
+        assert_eq!(pacer.interval(), Duration::from_millis(100));
+        assert_eq!(pacer.take_due(start), 0);
+        assert_eq!(pacer.take_due(start + Duration::from_millis(99)), 0);
+
+        assert_eq!(pacer.take_due(start + Duration::from_millis(100)), 1);
+        assert_eq!(pacer.next_due(), start + Duration::from_millis(200));
+
+        // Running late is made up for.
+        assert_eq!(pacer.take_due(start + Duration::from_millis(450)), 3);
+        assert_eq!(pacer.next_due(), start + Duration::from_millis(500));
+        assert_eq!(pacer.take_due(start + Duration::from_millis(450)), 0);
+    }
+
+    #[test]
+    fn test_gives_up_catching_up() {
+        let start = Instant::now();
+        let mut pacer = Pacer::new(10.0, start);
+        let late = start + Duration::from_secs(60);
+        assert_eq!(pacer.take_due(late), MAX_CATCH_UP);
+        assert_eq!(pacer.next_due(), late + Duration::from_millis(100));
+    }
+}
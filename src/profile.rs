@@ -0,0 +1,105 @@
+//! Timing the phases of each frame, to see where the time goes when the
+//! window can't keep up.
+
+use std::time::Duration;
+
+/// How long each phase of a frame took, every frame, in the order the
+/// phases were first recorded.
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    phases: Vec<(&'static str, Vec<Duration>)>,
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, phase: &'static str, duration: Duration) {
+        match self.phases.iter_mut().find(|(name, _)| *name == phase) {
+            Some((_, durations)) => durations.push(duration),
+            None => self.phases.push((phase, vec![duration])),
+        }
+    }
+
+    /// The time taken by `phase` that `percent` of its times are no longer
+    /// than, if it's been recorded.
+    pub fn percentile(&self, phase: &str, percent: usize) -> Option<Duration> {
+        let (_, durations) = self.phases.iter().find(|(name, _)| *name == phase)?;
+        let mut durations = durations.clone();
+        durations.sort();
+        // The nearest rank.
+        let rank = (percent * durations.len()).div_ceil(100).max(1);
+        durations.get(rank - 1).copied()
+    }
+
+    /// A table of each phase's times in milliseconds, and its share of the
+    /// total.
+    pub fn report(&self) -> String {
+        let total: Duration = self
+            .phases
+            .iter()
+            .flat_map(|(_, durations)| durations)
+            .sum();
+        let mut report = format!(
+            "{:<10} {:>7} {:>8} {:>8} {:>8} {:>8} {:>8} {:>6}\n",
+            "phase", "count", "mean ms", "p50", "p90", "p99", "max", "share"
+        );
+
+        for (phase, durations) in &self.phases {
+            let sum: Duration = durations.iter().sum();
+            let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+            let percentile = |percent| ms(self.percentile(phase, percent).unwrap());
+            report.push_str(&format!(
+                "{:<10} {:>7} {:>8.2} {:>8.2} {:>8.2} {:>8.2} {:>8.2} {:>5.1}%\n",
+                phase,
+                durations.len(),
+                ms(sum) / durations.len() as f64,
+                percentile(50),
+                percentile(90),
+                percentile(99),
+                percentile(100),
+                100.0 * sum.as_secs_f64() / total.as_secs_f64().max(f64::MIN_POSITIVE),
+            ));
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        let mut profile = Profile::new();
+        for ms in (1..=100).rev() {
+            profile.record("draw", Duration::from_millis(ms));
+        }
+        profile.record("input", Duration::from_millis(7));
+
+        let percentile = |phase, percent| profile.percentile(phase, percent);
+        assert_eq!(percentile("draw", 50), Some(Duration::from_millis(50)));
+        assert_eq!(percentile("draw", 99), Some(Duration::from_millis(99)));
+        assert_eq!(percentile("draw", 100), Some(Duration::from_millis(100)));
+        assert_eq!(percentile("draw", 0), Some(Duration::from_millis(1)));
+        assert_eq!(percentile("input", 90), Some(Duration::from_millis(7)));
+        assert_eq!(percentile("simulate", 50), None);
+    }
+
+    #[test]
+    fn test_report() {
+        let mut profile = Profile::new();
+        profile.record("simulate", Duration::from_millis(3));
+        profile.record("draw", Duration::from_millis(1));
+        profile.record("simulate", Duration::from_millis(5));
+
+        let report = profile.report();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("phase"));
+        assert!(lines[1].starts_with("simulate         2     4.00     3.00     5.00"));
+        assert!(lines[1].ends_with("88.9%"));
+        assert!(lines[2].ends_with("11.1%"));
+    }
+}
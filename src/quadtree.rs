@@ -0,0 +1,450 @@
+//! Worlds stored as quadtrees, where identical squares anywhere in the world
+//! are stored once, so large empty or repetitive regions take next to no
+//! space. This is the representation HashLife runs on.
+
+use crate::grid::{hash_bytes, heap_bytes};
+use crate::world::World;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::mem;
+
+/// An index into a `Quadtree`'s nodes.
+pub(crate) type NodeId = u32;
+
+/// A square of `2^level` by `2^level` cells: a single cell at level 0, or
+/// four squares of the level below, north-west, north-east, south-west and
+/// south-east.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Node {
+    Leaf(bool),
+    Branch {
+        level: u8,
+        children: [NodeId; 4],
+        population: u64,
+    },
+}
+
+impl Node {
+    pub(crate) fn level(self) -> u8 {
+        match self {
+            Node::Leaf(_) => 0,
+            Node::Branch { level, .. } => level,
+        }
+    }
+
+    pub(crate) fn population(self) -> u64 {
+        match self {
+            Node::Leaf(alive) => u64::from(alive),
+            Node::Branch { population, .. } => population,
+        }
+    }
+}
+
+/// A square world that grows to fit whatever is put in it. Nodes are never
+/// duplicated: building a node the same as an existing one returns that one.
+#[derive(Clone, Debug)]
+pub struct Quadtree {
+    nodes: Vec<Node>,
+    ids: HashMap<Node, NodeId>,
+    /// The empty node of each level, once it's been built.
+    empty: Vec<NodeId>,
+    root: NodeId,
+    /// The coordinates of the root's north-west corner.
+    origin: (i64, i64),
+}
+
+impl Default for Quadtree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Quadtree {
+    /// An empty 2x2 tree with its north-west corner at the origin.
+    pub fn new() -> Self {
+        let mut tree = Self {
+            nodes: Vec::new(),
+            ids: HashMap::new(),
+            empty: Vec::new(),
+            root: 0,
+            origin: (0, 0),
+        };
+        tree.root = tree.empty(1);
+        tree
+    }
+
+    /// A tree of the world's live cells, with the same coordinates.
+    pub fn from_world(world: &World) -> Self {
+        let mut tree = Self::new();
+        let mut level = 1;
+        while 1 << level < world.width.max(world.height) {
+            level += 1;
+        }
+
+        tree.root = tree.build(world, level, (0, 0));
+        tree
+    }
+
+    fn build(&mut self, world: &World, level: u8, (x, y): (usize, usize)) -> NodeId {
+        if x >= world.width || y >= world.height {
+            return self.empty(level);
+        }
+        if level == 0 {
+            return self.leaf(world.cell(x, y).alive);
+        }
+
+        let half = 1 << (level - 1);
+        let children = [
+            self.build(world, level - 1, (x, y)),
+            self.build(world, level - 1, (x + half, y)),
+            self.build(world, level - 1, (x, y + half)),
+            self.build(world, level - 1, (x + half, y + half)),
+        ];
+        self.branch(children)
+    }
+
+    /// A world of the given size with the tree's live cells inside it,
+    /// taking the world's top left corner as the origin.
+    pub fn to_world(&self, width: usize, height: usize) -> World {
+        let mut world = World::new(width, height);
+        for (x, y) in self.live_cells_in((0, 0), (width as i64, height as i64)) {
+            world.toggle_cell(x as usize, y as usize);
+        }
+        world
+    }
+
+    /// The number of cells along each side of the tree.
+    pub fn size(&self) -> i64 {
+        1 << self.level()
+    }
+
+    pub fn level(&self) -> u8 {
+        self.node(self.root).level()
+    }
+
+    pub fn origin(&self) -> (i64, i64) {
+        self.origin
+    }
+
+    pub fn population(&self) -> u64 {
+        self.node(self.root).population()
+    }
+
+    /// How many distinct nodes have been built, which is how much space the
+    /// tree takes.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Roughly how many bytes the tree takes.
+    pub fn memory_usage(&self) -> usize {
+        mem::size_of::<Self>()
+            + heap_bytes::<Node>(self.nodes.capacity())
+            + hash_bytes::<(Node, NodeId)>(self.ids.capacity())
+            + heap_bytes::<NodeId>(self.empty.capacity())
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> bool {
+        let (mut x, mut y) = (x - self.origin.0, y - self.origin.1);
+        if !(0..self.size()).contains(&x) || !(0..self.size()).contains(&y) {
+            return false;
+        }
+
+        let mut id = self.root;
+        let mut level = self.level();
+        loop {
+            match self.node(id) {
+                Node::Leaf(alive) => return alive,
+                Node::Branch { children, .. } => {
+                    level -= 1;
+                    id = children[quadrant(x, y, level)];
+                    x %= 1 << level;
+                    y %= 1 << level;
+                }
+            }
+        }
+    }
+
+    /// Sets a cell, growing the tree until it's inside it.
+    pub fn set(&mut self, x: i64, y: i64, alive: bool) {
+        loop {
+            let (x, y) = (x - self.origin.0, y - self.origin.1);
+            if (0..self.size()).contains(&x) && (0..self.size()).contains(&y) {
+                break;
+            }
+            self.expand();
+        }
+
+        let position = (x - self.origin.0, y - self.origin.1);
+        self.root = self.set_in(self.root, position, alive);
+    }
+
+    fn set_in(&mut self, id: NodeId, (x, y): (i64, i64), alive: bool) -> NodeId {
+        match self.node(id) {
+            Node::Leaf(_) => self.leaf(alive),
+            Node::Branch {
+                level,
+                mut children,
+                ..
+            } => {
+                let half = 1 << (level - 1);
+                let i = quadrant(x, y, level - 1);
+                children[i] = self.set_in(children[i], (x % half, y % half), alive);
+                self.branch(children)
+            }
+        }
+    }
+
+    /// The live cells in a rectangle, skipping over empty nodes.
+    pub fn live_cells_in(
+        &self,
+        (left, top): (i64, i64),
+        (width, height): (i64, i64),
+    ) -> Vec<(i64, i64)> {
+        let mut cells = Vec::new();
+        let rect = (left, top, left + width, top + height);
+        self.collect_live_cells(self.root, self.origin, rect, &mut cells);
+        cells
+    }
+
+    pub fn live_cells(&self) -> Vec<(i64, i64)> {
+        let (x, y) = self.origin;
+        self.live_cells_in((x, y), (self.size(), self.size()))
+    }
+
+    fn collect_live_cells(
+        &self,
+        id: NodeId,
+        (x, y): (i64, i64),
+        rect: (i64, i64, i64, i64),
+        cells: &mut Vec<(i64, i64)>,
+    ) {
+        let node = self.node(id);
+        let size = 1 << node.level();
+        let (left, top, right, bottom) = rect;
+        if node.population() == 0
+            || x >= right
+            || y >= bottom
+            || x + size <= left
+            || y + size <= top
+        {
+            return;
+        }
+
+        match node {
+            Node::Leaf(_) => cells.push((x, y)),
+            Node::Branch { children, .. } => {
+                let half = size / 2;
+                let corners = [(x, y), (x + half, y), (x, y + half), (x + half, y + half)];
+                for (child, corner) in children.iter().zip(&corners) {
+                    self.collect_live_cells(*child, *corner, rect, cells);
+                }
+            }
+        }
+    }
+
+    /// Doubles the tree's size, keeping its cells where they are by putting
+    /// the old root in the middle of the new one.
+    pub fn expand(&mut self) {
+        let level = self.level();
+        let root = self.root;
+        self.root = self.expanded(root);
+        let half = 1 << (level - 1);
+        self.origin = (self.origin.0 - half, self.origin.1 - half);
+    }
+
+    /// A node twice the size of `id`, with `id` in the middle.
+    pub(crate) fn expanded(&mut self, id: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.children(id);
+        let empty = self.empty(self.node(id).level() - 1);
+        let children = [
+            self.branch([empty, empty, empty, nw]),
+            self.branch([empty, empty, ne, empty]),
+            self.branch([empty, sw, empty, empty]),
+            self.branch([se, empty, empty, empty]),
+        ];
+        self.branch(children)
+    }
+
+    pub(crate) fn root(&self) -> NodeId {
+        self.root
+    }
+
+    /// Replaces the root with a node whose north-west corner is at `origin`.
+    pub(crate) fn set_root(&mut self, root: NodeId, origin: (i64, i64)) {
+        self.root = root;
+        self.origin = origin;
+    }
+
+    pub(crate) fn node(&self, id: NodeId) -> Node {
+        self.nodes[id as usize]
+    }
+
+    pub(crate) fn children(&self, id: NodeId) -> [NodeId; 4] {
+        match self.node(id) {
+            Node::Branch { children, .. } => children,
+            Node::Leaf(_) => panic!("a leaf has no children"),
+        }
+    }
+
+    pub(crate) fn leaf(&mut self, alive: bool) -> NodeId {
+        self.intern(Node::Leaf(alive))
+    }
+
+    /// The node with the given children, which must all be the same level.
+    pub(crate) fn branch(&mut self, children: [NodeId; 4]) -> NodeId {
+        let level = self.node(children[0]).level() + 1;
+        let population = children.iter().map(|id| self.node(*id).population()).sum();
+        self.intern(Node::Branch {
+            level,
+            children,
+            population,
+        })
+    }
+
+    pub(crate) fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= usize::from(level) {
+            let id = match self.empty.last() {
+                None => self.leaf(false),
+                Some(&below) => self.branch([below; 4]),
+            };
+            self.empty.push(id);
+        }
+        self.empty[usize::from(level)]
+    }
+
+    /// Drops every node the root doesn't lead to, numbering the rest again
+    /// from zero, so any other ids held on to from before mean nothing.
+    pub(crate) fn collect_garbage(&mut self) {
+        let mut tree = Self {
+            nodes: Vec::new(),
+            ids: HashMap::new(),
+            empty: Vec::new(),
+            root: 0,
+            origin: self.origin,
+        };
+        let mut moved = vec![None; self.nodes.len()];
+        tree.root = tree.copy_from(self, self.root, &mut moved);
+        *self = tree;
+    }
+
+    /// Builds the node `id` of `other` in this tree, with `moved` keeping
+    /// the new id of each node copied so far.
+    fn copy_from(&mut self, other: &Self, id: NodeId, moved: &mut [Option<NodeId>]) -> NodeId {
+        if let Some(copied) = moved[id as usize] {
+            return copied;
+        }
+
+        let copied = match other.node(id) {
+            Node::Leaf(alive) => self.leaf(alive),
+            Node::Branch { children, .. } => {
+                let children = children.map(|child| self.copy_from(other, child, moved));
+                self.branch(children)
+            }
+        };
+        moved[id as usize] = Some(copied);
+        copied
+    }
+
+    fn intern(&mut self, node: Node) -> NodeId {
+        if let Some(id) = self.ids.get(&node) {
+            return *id;
+        }
+
+        // Collecting garbage keeps the tree far from this many nodes, which
+        // would take hundreds of gigabytes.
+        let id = NodeId::try_from(self.nodes.len()).expect("the quadtree has run out of node ids");
+        self.nodes.push(node);
+        self.ids.insert(node, id);
+        id
+    }
+}
+
+/// Which child a cell is in, for a node whose children are `2^level` wide.
+fn quadrant(x: i64, y: i64, level: u8) -> usize {
+    let half = 1 << level;
+    usize::from(x >= half) + 2 * usize::from(y >= half)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_world_round_trip() {
+        let mut world = World::new(10, 7);
+        world.seed_from_string(
+            "- # -
+             - - #
+             # # #"
+                .to_string(),
+            (6, 3),
+        );
+        world.toggle_cell(9, 6);
+
+        let tree = Quadtree::from_world(&world);
+        assert_eq!(tree.size(), 16);
+        assert_eq!(tree.population(), 6);
+        assert!(tree.get(7, 3));
+        assert!(!tree.get(7, 4));
+        assert_eq!(tree.to_world(10, 7), world);
+    }
+
+    #[test]
+    fn test_repetition_is_stored_once() {
+        // A 256x256 world of blocks every four cells.
+        let mut world = World::new(256, 256);
+        for y in (0..256).step_by(4) {
+            for x in (0..256).step_by(4) {
+                for (dx, dy) in &[(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    world.toggle_cell(x + dx, y + dy);
+                }
+            }
+        }
+
+        let tree = Quadtree::from_world(&world);
+        assert_eq!(tree.population(), 4 * 64 * 64);
+        // One node per level, plus the leaves and the empty nodes.
+        assert!(tree.node_count() < 20, "{}", tree.node_count());
+    }
+
+    #[test]
+    fn test_collect_garbage() {
+        let mut tree = Quadtree::new();
+        for x in 0..40 {
+            tree.set(x, x % 7, true);
+        }
+        for x in 0..30 {
+            tree.set(x, x % 7, false);
+        }
+        let live_cells = tree.live_cells();
+        let (origin, built) = (tree.origin(), tree.node_count());
+
+        tree.collect_garbage();
+        assert!(tree.node_count() < built / 2, "{}", tree.node_count());
+        assert_eq!(tree.live_cells(), live_cells);
+        assert_eq!(tree.origin(), origin);
+        assert_eq!(tree.population(), 10);
+        // Nodes are still only stored once.
+        let count = tree.node_count();
+        tree.set(35, 1, true);
+        tree.set(35, 1, false);
+        assert_eq!(tree.live_cells(), live_cells);
+        assert!(tree.node_count() < count + 2 * usize::from(tree.level()));
+    }
+
+    #[test]
+    fn test_set_grows() {
+        let mut tree = Quadtree::new();
+        tree.set(1, 1, true);
+        tree.set(-5, 20, true);
+        tree.set(1, 1, false);
+
+        assert!(tree.get(-5, 20));
+        assert!(!tree.get(1, 1));
+        assert_eq!(tree.population(), 1);
+        assert_eq!(tree.live_cells(), vec![(-5, 20)]);
+        let (x, y) = tree.origin();
+        assert!(x <= -5 && y + tree.size() > 20);
+    }
+}
@@ -0,0 +1,78 @@
+//! Rectangles of a world that run their own rule.
+
+use crate::rule::Rule;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Region {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub rule: Rule,
+}
+
+impl Region {
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        (self.x..self.x + self.width).contains(&x) && (self.y..self.y + self.height).contains(&y)
+    }
+}
+
+/// Parses `X,Y,WIDTH,HEIGHT:RULE`, e.g. `150,0,150,300:seeds`.
+impl FromStr for Region {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid region: {}", s);
+        let (rect, rule) = s.split_once(':').ok_or_else(invalid)?;
+        let rect: Vec<usize> = rect
+            .split(',')
+            .map(|n| n.trim().parse().map_err(|_| invalid()))
+            .collect::<Result<_, _>>()?;
+
+        match rect.as_slice() {
+            [x, y, width, height] => Ok(Self {
+                x: *x,
+                y: *y,
+                width: *width,
+                height: *height,
+                rule: rule.parse()?,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// The same `X,Y,WIDTH,HEIGHT:RULE` it's parsed from.
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{},{},{},{}:{}",
+            self.x, self.y, self.width, self.height, self.rule
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let region: Region = "10,0,5,20:seeds".parse().unwrap();
+
+        assert_eq!(
+            (region.x, region.y, region.width, region.height),
+            (10, 0, 5, 20)
+        );
+        assert_eq!(region.rule, "B2/S".parse().unwrap());
+        assert!(region.contains(14, 19));
+        assert_eq!(region.to_string().parse(), Ok(region));
+        assert!(!region.contains(15, 0));
+        assert!("10,0,5:seeds".parse::<Region>().is_err());
+        assert!("10,0,5,20".parse::<Region>().is_err());
+        assert!("10,0,5,20:nope".parse::<Region>().is_err());
+    }
+}
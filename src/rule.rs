@@ -0,0 +1,1069 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Well known rules that can be given by name instead of in B/S notation.
+const PRESETS: &[(&str, &str)] = &[
+    ("life", "B3/S23"),
+    ("highlife", "B36/S23"),
+    ("seeds", "B2/S"),
+    ("daynight", "B3678/S34678"),
+    ("lifewithoutdeath", "B3/S012345678"),
+    ("diamoeba", "B35678/S5678"),
+    ("anneal", "B4678/S35678"),
+    ("briansbrain", "B2/S/C3"),
+    ("starwars", "B2/S345/C4"),
+];
+
+/// A Life-like rule in B/S notation: the neighbour counts that bring a dead
+/// cell to life, and the counts that keep a live cell alive.
+///
+/// Rules from the Generations family (e.g. `B2/S345/C4`) have more than two
+/// states: a cell that fails to survive passes through `states - 2` dying
+/// states before it's dead, and can't be born again until then.
+///
+/// Larger than Life rules (e.g. Bosco's rule, `R5,C0,M1,S34..58,B34..45,NM`)
+/// count neighbours out to a larger radius, optionally including the cell
+/// itself.
+///
+/// Rules ending in `V` (e.g. `B2/S013V`) count the four orthogonal
+/// neighbours of the von Neumann neighbourhood instead of all eight, as do
+/// Larger than Life rules ending in `NN`, out to their radius. Rules ending in
+/// `H` (e.g. `B2/S34H`) run on a hexagonal grid, and those ending in `L`
+/// (e.g. `B45/S34L`) on a triangular one.
+///
+/// Cyclic rules (e.g. `Cyclic/C16/T1`) give every cell one of `states`
+/// states, in a cycle. A cell moves on to the next state when at least the
+/// threshold of its neighbours are already in it.
+///
+/// Isotropic non-totalistic rules (e.g. `B2-a/S12`) use Hensel notation,
+/// where letters after a count pick out which arrangements of that many
+/// neighbours apply. Their birth and survival sets hold neighbourhood
+/// configurations (see `Rule::births_configuration`) rather than counts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub struct Rule {
+    birth: Counts,
+    survival: Counts,
+    states: u8,
+    radius: u8,
+    include_middle: bool,
+    isotropic: bool,
+    neighbourhood: Neighbourhood,
+    family: Family,
+}
+
+/// The kind of automaton a rule describes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Family {
+    /// Birth and survival by neighbour count, as in Conway's Game of Life,
+    /// including the Generations and Larger than Life extensions.
+    LifeLike,
+    /// Brian Silverman's Wireworld, for simulating circuits: electron heads
+    /// (live cells) move along conductors, leaving tails behind them.
+    Wireworld,
+    /// Conway's Life with two teams of live cells, where a cell is born into
+    /// the team most of its parents are in.
+    Immigration,
+    /// Conway's Life with four teams. A cell is born into the team most of
+    /// its parents are in, or the fourth team when they're all different.
+    QuadLife,
+    /// David Griffeath's cyclic cellular automata, where each state is
+    /// consumed by the one after it.
+    Cyclic,
+}
+
+pub const WIREWORLD_HEAD: u8 = 1;
+pub const WIREWORLD_TAIL: u8 = 2;
+pub const WIREWORLD_CONDUCTOR: u8 = 3;
+
+/// Which cells around a cell count as its neighbours.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Neighbourhood {
+    /// The square around the cell.
+    Moore,
+    /// The diamond around the cell: only cells within the radius in
+    /// Manhattan distance.
+    VonNeumann,
+    /// The hexagon around the cell, with the grid sheared so each row sits
+    /// half a cell to the right of the one below it. The six neighbours are
+    /// the square's, less the north-east and south-west corners.
+    Hexagonal,
+    /// The twelve triangles sharing an edge or corner with a triangle, where
+    /// cells with an even `x + y` point up and the rest point down. Only
+    /// works with a radius of one.
+    Triangular,
+}
+
+impl Neighbourhood {
+    /// Whether the cell at an offset from the middle cell, which is at
+    /// `cell`, is a neighbour within `radius`.
+    pub fn contains(
+        self,
+        cell: (usize, usize),
+        x_offset: isize,
+        y_offset: isize,
+        radius: u8,
+    ) -> bool {
+        let radius = isize::from(radius);
+
+        (x_offset, y_offset) != (0, 0)
+            && match self {
+                Neighbourhood::Moore => x_offset.abs() <= radius && y_offset.abs() <= radius,
+                Neighbourhood::VonNeumann => x_offset.abs() + y_offset.abs() <= radius,
+                Neighbourhood::Hexagonal => {
+                    x_offset.abs() <= radius
+                        && y_offset.abs() <= radius
+                        && (x_offset - y_offset).abs() <= radius
+                }
+                Neighbourhood::Triangular => {
+                    // The row a triangle's flat edge is on sees three cells,
+                    // the row at its point sees five.
+                    let points_up = (cell.0 + cell.1).is_multiple_of(2);
+                    let edge_row = if points_up { 1 } else { -1 };
+                    match y_offset {
+                        0 => x_offset.abs() <= 2,
+                        _ if y_offset == edge_row => x_offset.abs() <= 2,
+                        _ if y_offset == -edge_row => x_offset.abs() <= 1,
+                        _ => false,
+                    }
+                }
+            }
+    }
+
+    /// How far away neighbours can be horizontally and vertically.
+    pub fn reach(self, radius: u8) -> (isize, isize) {
+        match self {
+            Neighbourhood::Triangular => (2, 1),
+            _ => (isize::from(radius), isize::from(radius)),
+        }
+    }
+
+    /// The most neighbours a cell can have with a radius of one.
+    fn max_neighbours(self) -> u8 {
+        match self {
+            Neighbourhood::Moore => 8,
+            Neighbourhood::VonNeumann => 4,
+            Neighbourhood::Hexagonal => 6,
+            Neighbourhood::Triangular => 12,
+        }
+    }
+}
+
+/// A shape of neighbours around a cell, as offsets from it. `Rule`s have
+/// one, and consumers can count neighbours in shapes of their own with
+/// `World::count_neighbours`.
+pub trait NeighbourhoodShape {
+    /// How far away neighbours can be horizontally and vertically.
+    fn reach(&self) -> (isize, isize);
+
+    /// Whether the cell at an offset from the cell at `cell` is one of its
+    /// neighbours.
+    fn contains(&self, cell: (usize, usize), x_offset: isize, y_offset: isize) -> bool;
+
+    /// The offset of each of the cell at `cell`'s neighbours.
+    fn offsets(&self, cell: (usize, usize)) -> Vec<(isize, isize)> {
+        let (x_reach, y_reach) = self.reach();
+        (-y_reach..=y_reach)
+            .flat_map(|y_offset| (-x_reach..=x_reach).map(move |x_offset| (x_offset, y_offset)))
+            .filter(|(x_offset, y_offset)| self.contains(cell, *x_offset, *y_offset))
+            .collect()
+    }
+}
+
+impl FromStr for Neighbourhood {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "moore" => Ok(Neighbourhood::Moore),
+            "vonneumann" => Ok(Neighbourhood::VonNeumann),
+            "hexagonal" => Ok(Neighbourhood::Hexagonal),
+            "triangular" => Ok(Neighbourhood::Triangular),
+            _ => Err(format!("unknown neighbourhood: {}", s)),
+        }
+    }
+}
+
+/// The largest radius whose neighbour counts still fit in a `u8`.
+const MAX_RADIUS: u8 = 7;
+
+/// One configuration for each Hensel letter, by neighbour count. The
+/// configurations are bitmasks of the neighbours in reading order, so bit 0 is
+/// the north-west neighbour and bit 7 the south-east. Counts above four use
+/// the complements of the configurations for `8 - count`.
+const HENSEL_LETTERS: [(&str, &[u8]); 5] = [
+    ("", &[0]),
+    ("ce", &[1, 2]),
+    ("ceaikn", &[5, 10, 3, 24, 17, 36]),
+    ("ceaiknjqry", &[37, 26, 11, 7, 50, 13, 14, 38, 25, 49]),
+    (
+        "ceaiknjqrytwz",
+        &[165, 90, 15, 29, 51, 39, 58, 54, 27, 53, 57, 46, 60],
+    ),
+];
+
+/// The configuration a Hensel letter stands for, with every rotation and
+/// reflection of it.
+fn hensel_configurations(count: u8, letter: char) -> Option<Vec<u8>> {
+    let (letters, configurations) = HENSEL_LETTERS[usize::from(count.min(8 - count))];
+    let configuration = configurations[letters.find(letter)?];
+    let configuration = if count > 4 {
+        !configuration
+    } else {
+        configuration
+    };
+
+    let mut symmetries: Vec<u8> = (0..8)
+        .map(|symmetry| transform_configuration(configuration, symmetry))
+        .collect();
+    symmetries.sort();
+    symmetries.dedup();
+    Some(symmetries)
+}
+
+/// Applies one of the eight rotations and reflections of the square to a
+/// configuration.
+fn transform_configuration(configuration: u8, symmetry: u8) -> u8 {
+    #[rustfmt::skip]
+    const NEIGHBOURS: [(i8, i8); 8] = [
+        (-1, -1), (0, -1), (1, -1),
+        (-1, 0), (1, 0),
+        (-1, 1), (0, 1), (1, 1),
+    ];
+
+    NEIGHBOURS
+        .iter()
+        .enumerate()
+        .filter(|(bit, _)| configuration & 1 << bit != 0)
+        .map(|(_, &(x, y))| {
+            let (x, y) = if symmetry & 4 != 0 { (-x, y) } else { (x, y) };
+            let (x, y) = (0..symmetry % 4).fold((x, y), |(x, y), _| (-y, x));
+            let bit = NEIGHBOURS.iter().position(|&n| n == (x, y)).unwrap();
+            1 << bit
+        })
+        .fold(0, |configuration, bit| configuration | bit)
+}
+
+impl Rule {
+    /// Conway's Game of Life, B3/S23.
+    pub fn conway() -> Self {
+        "B3/S23".parse().unwrap()
+    }
+
+    /// Wireworld, which has four states: empty, electron head, electron tail
+    /// and conductor.
+    pub fn wireworld() -> Self {
+        Self {
+            family: Family::Wireworld,
+            ..Self::life_like(Counts::default(), Counts::default(), 4)
+        }
+    }
+
+    /// Immigration, Conway's Life played by two teams.
+    pub fn immigration() -> Self {
+        Self {
+            family: Family::Immigration,
+            ..Self::conway()
+        }
+    }
+
+    /// QuadLife, Conway's Life played by four teams.
+    pub fn quadlife() -> Self {
+        Self {
+            family: Family::QuadLife,
+            ..Self::conway()
+        }
+    }
+
+    /// A cyclic rule with `states` states, where a cell moves on to the next
+    /// state once `threshold` of its neighbours are in it.
+    pub fn cyclic(states: u8, threshold: u8) -> Self {
+        // The birth counts hold every count from the threshold up.
+        let mut birth = Counts::default();
+        for count in threshold..=255 {
+            birth.insert(count);
+        }
+
+        Self {
+            family: Family::Cyclic,
+            ..Self::life_like(birth, Counts::default(), states)
+        }
+    }
+
+    pub fn family(&self) -> Family {
+        self.family
+    }
+
+    /// How many teams live cells can be in. One, except for Immigration and
+    /// QuadLife.
+    pub fn teams(&self) -> u8 {
+        match self.family {
+            Family::Immigration => 2,
+            Family::QuadLife => 4,
+            _ => 1,
+        }
+    }
+
+    /// The number of cell states, including alive and dead. Two for
+    /// Life-like rules.
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    /// How far away a cell's neighbours can be. One for Life-like rules.
+    pub fn radius(&self) -> u8 {
+        self.radius
+    }
+
+    pub fn neighbourhood(&self) -> Neighbourhood {
+        self.neighbourhood
+    }
+
+    /// The same rule counting a different neighbourhood. Isotropic rules are
+    /// defined in terms of the Moore neighbourhood, so can't be changed.
+    pub fn with_neighbourhood(self, neighbourhood: Neighbourhood) -> Result<Self, String> {
+        if self.isotropic && neighbourhood != Neighbourhood::Moore {
+            return Err(format!("{} only works with the Moore neighbourhood", self));
+        }
+        if self.radius > 1 && neighbourhood == Neighbourhood::Triangular {
+            return Err(format!(
+                "{} has too large a radius for a triangular grid",
+                self
+            ));
+        }
+
+        Ok(Self {
+            neighbourhood,
+            ..self
+        })
+    }
+
+    /// Errors unless a cell's next state only depends on whether it's alive
+    /// and how many live neighbours it has, which is all the backends other
+    /// than `World` keep track of.
+    pub(crate) fn check_two_state(&self) -> Result<(), String> {
+        if self.family != Family::LifeLike || self.states > 2 || self.isotropic {
+            Err(format!("{} isn't a two-state totalistic rule", self))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Whether the rule depends on the arrangement of neighbours rather than
+    /// just how many there are.
+    pub fn is_isotropic(&self) -> bool {
+        self.isotropic
+    }
+
+    /// Like `births`, for isotropic rules. `configuration` has a bit set for
+    /// each live neighbour in reading order, north-west first.
+    pub fn births_configuration(&self, configuration: u8) -> bool {
+        self.birth.contains(configuration)
+    }
+
+    /// Like `survives`, for isotropic rules.
+    pub fn survives_configuration(&self, configuration: u8) -> bool {
+        self.survival.contains(configuration)
+    }
+
+    pub fn births(&self, live_neighbours: u8) -> bool {
+        self.birth.contains(live_neighbours)
+    }
+
+    pub fn survives(&self, live_neighbours: u8) -> bool {
+        self.survival
+            .contains(live_neighbours + u8::from(self.include_middle))
+    }
+
+    fn life_like(birth: Counts, survival: Counts, states: u8) -> Self {
+        Self {
+            birth,
+            survival,
+            states,
+            radius: 1,
+            include_middle: false,
+            isotropic: false,
+            neighbourhood: Neighbourhood::Moore,
+            family: Family::LifeLike,
+        }
+    }
+
+    fn is_larger_than_life(&self) -> bool {
+        self.radius > 1 || self.include_middle
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self::conway()
+    }
+}
+
+/// The rule's neighbourhood, out to its radius.
+impl NeighbourhoodShape for Rule {
+    fn reach(&self) -> (isize, isize) {
+        self.neighbourhood.reach(self.radius)
+    }
+
+    fn contains(&self, cell: (usize, usize), x_offset: isize, y_offset: isize) -> bool {
+        self.neighbourhood
+            .contains(cell, x_offset, y_offset, self.radius)
+    }
+}
+
+/// Decides whether a cell lives, for rules that `Rule` can't describe. Give
+/// one to `World::set_cell_rule` to run it over the Moore neighbourhood.
+pub trait CellRule: Send + Sync {
+    /// Whether a cell is alive next generation, given whether it's alive now
+    /// and how many of its eight neighbours are.
+    fn next(&self, alive: bool, live_neighbours: u8) -> bool;
+}
+
+/// Conway's Game of Life as a `CellRule`: the same as the `Rule` worlds
+/// start with.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConwayRule;
+
+impl CellRule for ConwayRule {
+    fn next(&self, alive: bool, live_neighbours: u8) -> bool {
+        live_neighbours == 3 || (alive && live_neighbours == 2)
+    }
+}
+
+/// Totalistic rules work as a `CellRule` too.
+impl CellRule for Rule {
+    fn next(&self, alive: bool, live_neighbours: u8) -> bool {
+        if alive {
+            self.survives(live_neighbours)
+        } else {
+            self.births(live_neighbours)
+        }
+    }
+}
+
+/// A set of neighbour counts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+struct Counts([u64; 4]);
+
+impl Counts {
+    fn contains(&self, n: u8) -> bool {
+        self.0[usize::from(n / 64)] & 1 << (n % 64) != 0
+    }
+
+    fn insert(&mut self, n: u8) {
+        self.0[usize::from(n / 64)] |= 1 << (n % 64);
+    }
+
+    fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..=255).filter(move |n| self.contains(*n))
+    }
+}
+
+/// Parses the counts of one half of a B/S rule, with optional Hensel letters,
+/// into the set of neighbourhood configurations it covers. Also returns
+/// whether any letters were used.
+fn parse_counts(counts: &str, s: &str) -> Result<(Counts, bool), String> {
+    let invalid = || format!("invalid rule: {}", s);
+    let mut configurations = Counts::default();
+    let mut has_letters = false;
+    let mut chars = counts.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let count = match c.to_digit(10) {
+            Some(n) if n <= 8 => n as u8,
+            _ => return Err(invalid()),
+        };
+
+        let negated = chars.peek() == Some(&'-');
+        if negated {
+            chars.next();
+        }
+        let mut letters = Vec::new();
+        while let Some(letter) = chars.peek().filter(|c| c.is_ascii_alphabetic()) {
+            letters.push(*letter);
+            chars.next();
+        }
+        if negated && letters.is_empty() {
+            return Err(invalid());
+        }
+        has_letters |= !letters.is_empty();
+
+        let mut selected = Counts::default();
+        for letter in &letters {
+            for configuration in hensel_configurations(count, *letter).ok_or_else(invalid)? {
+                selected.insert(configuration);
+            }
+        }
+        for configuration in 0..=255u8 {
+            let matches_count = configuration.count_ones() == u32::from(count);
+            if matches_count && (letters.is_empty() || selected.contains(configuration) != negated)
+            {
+                configurations.insert(configuration);
+            }
+        }
+    }
+
+    Ok((configurations, has_letters))
+}
+
+/// The neighbour counts covered by a set of configurations.
+fn totals(configurations: Counts) -> Counts {
+    let mut counts = Counts::default();
+    configurations
+        .iter()
+        .for_each(|configuration| counts.insert(configuration.count_ones() as u8));
+    counts
+}
+
+fn write_counts(f: &mut fmt::Formatter, counts: Counts) -> fmt::Result {
+    counts.iter().try_for_each(|n| write!(f, "{}", n))
+}
+
+/// Writes configurations in Hensel notation, using whichever of the letters
+/// present or the letters absent (after a `-`) is shorter.
+fn write_hensel(f: &mut fmt::Formatter, configurations: Counts) -> fmt::Result {
+    for count in 0..=8u8 {
+        let (letters, _) = HENSEL_LETTERS[usize::from(count.min(8 - count))];
+        let (present, absent): (String, String) = letters.chars().partition(|letter| {
+            hensel_configurations(count, *letter)
+                .unwrap()
+                .iter()
+                .all(|configuration| configurations.contains(*configuration))
+        });
+
+        let any =
+            (0..=255u8).any(|c| c.count_ones() == u32::from(count) && configurations.contains(c));
+        if !any {
+            continue;
+        }
+
+        write!(f, "{}", count)?;
+        if !absent.is_empty() {
+            if present.len() <= absent.len() {
+                write!(f, "{}", present)?;
+            } else {
+                write!(f, "-{}", absent)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parses a Larger than Life rule, `R<radius>,C<states>,M<0|1>,S<min>..<max>,B<min>..<max>,NM`.
+fn parse_larger_than_life(s: &str) -> Result<Rule, String> {
+    let invalid = || format!("invalid rule: {}", s);
+    let number = |value: &str| value.parse::<u8>().map_err(|_| invalid());
+    let range = |value: &str| -> Result<Counts, String> {
+        let mut bounds = value.splitn(2, "..");
+        let min = number(bounds.next().unwrap_or(""))?;
+        let max = number(bounds.next().ok_or_else(invalid)?)?;
+        let mut counts = Counts::default();
+        (min..=max).for_each(|n| counts.insert(n));
+        Ok(counts)
+    };
+
+    let mut rule = Rule {
+        radius: 0,
+        ..Rule::conway()
+    };
+    for part in s.split(',') {
+        let part = part.trim();
+        let value = part.get(1..).ok_or_else(invalid)?;
+        match part.chars().next().map(|c| c.to_ascii_uppercase()) {
+            Some('R') => rule.radius = number(value)?,
+            Some('C') => rule.states = number(value)?.max(2),
+            Some('M') => rule.include_middle = number(value)? == 1,
+            Some('S') => rule.survival = range(value)?,
+            Some('B') => rule.birth = range(value)?,
+            Some('N') if value.eq_ignore_ascii_case("M") => {}
+            Some('N') if value.eq_ignore_ascii_case("N") => {
+                rule.neighbourhood = Neighbourhood::VonNeumann
+            }
+            Some('N') if value.eq_ignore_ascii_case("H") => {
+                rule.neighbourhood = Neighbourhood::Hexagonal
+            }
+            _ => return Err(invalid()),
+        }
+    }
+
+    if rule.radius == 0 || rule.radius > MAX_RADIUS {
+        return Err(format!(
+            "invalid rule: {} (the radius must be 1 to {})",
+            s, MAX_RADIUS
+        ));
+    }
+    Ok(rule)
+}
+
+/// Parses a preset name, `B3/S23` (in either order, any case) or the older
+/// survival-first `23/3` notation. Either can end with a state count for
+/// Generations rules, e.g. `B2/S345/C4` or `345/2/4`, and a `V` for the von
+/// Neumann neighbourhood, an `H` for the hexagonal one or an `L` for the
+/// triangular one. Larger than Life rules use their own comma
+/// separated notation.
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((_, rule)) = PRESETS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(s.trim()))
+        {
+            return rule.parse();
+        }
+
+        if s.trim().eq_ignore_ascii_case("wireworld") {
+            return Ok(Self::wireworld());
+        }
+
+        if s.contains(',') {
+            return parse_larger_than_life(s.trim());
+        }
+
+        let trimmed = s.trim();
+        let (rule, neighbourhood) = if let Some(rule) = trimmed.strip_suffix(['V', 'v']) {
+            (rule, Neighbourhood::VonNeumann)
+        } else if let Some(rule) = trimmed.strip_suffix(['H', 'h']) {
+            (rule, Neighbourhood::Hexagonal)
+        } else if let Some(rule) = trimmed.strip_suffix(['L', 'l']) {
+            (rule, Neighbourhood::Triangular)
+        } else {
+            (trimmed, Neighbourhood::Moore)
+        };
+
+        if rule.eq_ignore_ascii_case("immigration") {
+            return Self::immigration().with_neighbourhood(neighbourhood);
+        }
+
+        if rule.eq_ignore_ascii_case("quadlife") {
+            return Self::quadlife().with_neighbourhood(neighbourhood);
+        }
+
+        if rule.len() >= 6 && rule[..6].eq_ignore_ascii_case("cyclic") {
+            return parse_cyclic(&rule[6..], s)?.with_neighbourhood(neighbourhood);
+        }
+
+        let mut parts: Vec<&str> = rule.split('/').collect();
+        let states = match parts.len() {
+            2 => 2,
+            3 => {
+                let states = parts.pop().unwrap();
+                let states = states.trim_start_matches(['C', 'c']);
+                match states.parse() {
+                    Ok(states) if states >= 2 => states,
+                    _ => return Err(format!("invalid rule: {}", s)),
+                }
+            }
+            _ => return Err(format!("invalid rule: {}", s)),
+        };
+
+        let mut birth = None;
+        let mut survival = None;
+        for part in &parts {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some('B') | Some('b') => birth = Some(parse_counts(chars.as_str(), s)?),
+                Some('S') | Some('s') => survival = Some(parse_counts(chars.as_str(), s)?),
+                _ => {}
+            }
+        }
+
+        let ((birth, birth_letters), (survival, survival_letters)) = match (birth, survival) {
+            (Some(birth), Some(survival)) => (birth, survival),
+            (None, None) => (parse_counts(parts[1], s)?, parse_counts(parts[0], s)?),
+            _ => return Err(format!("invalid rule: {}", s)),
+        };
+
+        let rule = if birth_letters || survival_letters {
+            Self {
+                isotropic: true,
+                ..Self::life_like(birth, survival, states)
+            }
+        } else {
+            Self::life_like(totals(birth), totals(survival), states)
+        };
+
+        let max_neighbours = neighbourhood.max_neighbours();
+        if !rule.isotropic
+            && (rule.birth.iter().chain(rule.survival.iter())).any(|n| n > max_neighbours)
+        {
+            return Err(format!(
+                "invalid rule: {} (at most {} neighbours)",
+                s, max_neighbours
+            ));
+        }
+        rule.with_neighbourhood(neighbourhood)
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.family == Family::Wireworld {
+            return write!(f, "Wireworld");
+        }
+
+        if self.family == Family::Immigration || self.family == Family::QuadLife {
+            write!(f, "{:?}", self.family)?;
+            return write_neighbourhood_suffix(f, self.neighbourhood);
+        }
+
+        if self.family == Family::Cyclic {
+            write!(
+                f,
+                "Cyclic/C{}/T{}",
+                self.states,
+                self.birth.iter().next().unwrap_or(0)
+            )?;
+            return write_neighbourhood_suffix(f, self.neighbourhood);
+        }
+
+        if self.is_larger_than_life() {
+            let range = |counts: Counts| {
+                let min = counts.iter().next().unwrap_or(0);
+                let max = counts.iter().last().unwrap_or(0);
+                format!("{}..{}", min, max)
+            };
+
+            return write!(
+                f,
+                "R{},C{},M{},S{},B{},N{}",
+                self.radius,
+                if self.states > 2 { self.states } else { 0 },
+                u8::from(self.include_middle),
+                range(self.survival),
+                range(self.birth),
+                match self.neighbourhood {
+                    Neighbourhood::Moore => "M",
+                    Neighbourhood::VonNeumann => "N",
+                    Neighbourhood::Hexagonal => "H",
+                    Neighbourhood::Triangular => "L",
+                }
+            );
+        }
+
+        let write = if self.isotropic {
+            write_hensel
+        } else {
+            write_counts
+        };
+        write!(f, "B")?;
+        write(f, self.birth)?;
+        write!(f, "/S")?;
+        write(f, self.survival)?;
+        if self.states > 2 {
+            write!(f, "/C{}", self.states)?;
+        }
+        write_neighbourhood_suffix(f, self.neighbourhood)
+    }
+}
+
+fn write_neighbourhood_suffix(f: &mut fmt::Formatter, neighbourhood: Neighbourhood) -> fmt::Result {
+    match neighbourhood {
+        Neighbourhood::Moore => Ok(()),
+        Neighbourhood::VonNeumann => write!(f, "V"),
+        Neighbourhood::Hexagonal => write!(f, "H"),
+        Neighbourhood::Triangular => write!(f, "L"),
+    }
+}
+
+/// Parses the `/C16/T1` after `Cyclic`, for the number of states and the
+/// threshold. Either can be left out, for 16 states and a threshold of one.
+fn parse_cyclic(parameters: &str, s: &str) -> Result<Rule, String> {
+    let invalid = || format!("invalid rule: {}", s);
+    if !parameters.is_empty() && !parameters.starts_with('/') {
+        return Err(invalid());
+    }
+
+    let (mut states, mut threshold) = (16, 1);
+    for part in parameters.split('/').skip(1) {
+        let mut chars = part.chars();
+        let value = chars.next().ok_or_else(invalid)?;
+        let number = chars.as_str().parse().map_err(|_| invalid())?;
+        match value {
+            'C' | 'c' if number >= 2 => states = number,
+            'T' | 't' if number >= 1 => threshold = number,
+            _ => return Err(invalid()),
+        }
+    }
+
+    Ok(Rule::cyclic(states, threshold))
+}
+
+#[cfg(feature = "serde")]
+impl From<Rule> for String {
+    fn from(rule: Rule) -> Self {
+        rule.to_string()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<String> for Rule {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let highlife: Rule = "B36/S23".parse().unwrap();
+
+        assert!(highlife.births(3));
+        assert!(highlife.births(6));
+        assert!(!highlife.births(2));
+        assert!(highlife.survives(2));
+        assert!(highlife.survives(3));
+        assert!(!highlife.survives(4));
+
+        assert_eq!("s23/b36".parse::<Rule>().unwrap(), highlife);
+        assert_eq!("23/36".parse::<Rule>().unwrap(), highlife);
+        assert_eq!("B3/S23".parse::<Rule>().unwrap(), Rule::conway());
+        assert_eq!("B2/S".parse::<Rule>().unwrap().to_string(), "B2/S");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!("B3".parse::<Rule>().is_err());
+        assert!("B9/S23".parse::<Rule>().is_err());
+        assert!("B3/23".parse::<Rule>().is_err());
+        assert!("lief".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn test_generations() {
+        let star_wars: Rule = "345/2/4".parse().unwrap();
+
+        assert_eq!(star_wars.states(), 4);
+        assert!(star_wars.births(2));
+        assert!(star_wars.survives(5));
+        assert_eq!(star_wars.to_string(), "B2/S345/C4");
+        assert_eq!("B2/S345/C4".parse::<Rule>().unwrap(), star_wars);
+        assert_eq!(Rule::conway().states(), 2);
+
+        assert!("B2/S345/C1".parse::<Rule>().is_err());
+        assert!("B2/S345/Cx".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn test_larger_than_life() {
+        let bosco: Rule = "R5,C0,M1,S34..58,B34..45,NM".parse().unwrap();
+
+        assert_eq!(bosco.radius(), 5);
+        assert_eq!(bosco.states(), 2);
+        assert!(bosco.births(34));
+        assert!(bosco.births(45));
+        assert!(!bosco.births(46));
+        // The middle cell counts towards survival.
+        assert!(bosco.survives(33));
+        assert!(!bosco.survives(58));
+        assert_eq!(bosco.to_string(), "R5,C0,M1,S34..58,B34..45,NM");
+
+        assert!("R8,C0,M0,S1..2,B1..2,NM".parse::<Rule>().is_err());
+        assert!("R2,C0,M0,S1..2,B1,NM".parse::<Rule>().is_err());
+        assert!("R2,C0,M0,S1..2,B1..2,NX".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn test_hensel() {
+        let rule: Rule = "B2-a/S12".parse().unwrap();
+        assert!(rule.is_isotropic());
+
+        // Two neighbours on opposite corners (2n) give a birth, two adjacent
+        // ones (2a) don't.
+        assert!(rule.births_configuration(0b1000_0001));
+        assert!(!rule.births_configuration(0b0000_0011));
+        assert!(!rule.births_configuration(0b0000_0111));
+        assert!(rule.survives_configuration(0b0100_0000));
+        assert_eq!(rule.to_string(), "B2-a/S12");
+
+        // Letters above four use the complements of the ones below.
+        let rule: Rule = "B3/S5c".parse().unwrap();
+        assert!(rule.survives_configuration(!0b0010_0101));
+        assert!(!rule.survives_configuration(!0b0001_1010));
+        assert_eq!(rule.to_string(), "B3/S5c");
+
+        // Letters covering every configuration are the same as no letters.
+        assert_eq!(
+            "B2ceaikn/S23".parse::<Rule>().unwrap().to_string(),
+            "B2/S23"
+        );
+        assert!(!"B3/S23".parse::<Rule>().unwrap().is_isotropic());
+
+        assert!("B2x/S23".parse::<Rule>().is_err());
+        assert!("B2-/S23".parse::<Rule>().is_err());
+        assert!("B0c/S23".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn test_von_neumann() {
+        let rule: Rule = "B2/S013V".parse().unwrap();
+        assert_eq!(rule.neighbourhood(), Neighbourhood::VonNeumann);
+        assert!(rule.births(2));
+        assert_eq!(rule.to_string(), "B2/S013V");
+
+        let ltl: Rule = "R2,C0,M0,S1..3,B2..2,NN".parse().unwrap();
+        assert_eq!(ltl.neighbourhood(), Neighbourhood::VonNeumann);
+        assert_eq!(ltl.to_string(), "R2,C0,M0,S1..3,B2..2,NN");
+
+        assert!(Neighbourhood::VonNeumann.contains((0, 0), 0, -1, 1));
+        assert!(!Neighbourhood::VonNeumann.contains((0, 0), 1, 1, 1));
+        assert!(Neighbourhood::VonNeumann.contains((0, 0), 1, 1, 2));
+        assert!(Neighbourhood::Moore.contains((0, 0), 1, 1, 1));
+        assert!(!Neighbourhood::Moore.contains((0, 0), 0, 0, 1));
+
+        assert!("B5/S23V".parse::<Rule>().is_err());
+        assert!("B2a/S23V".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn test_hexagonal() {
+        let rule: Rule = "B2/S34H".parse().unwrap();
+        assert_eq!(rule.neighbourhood(), Neighbourhood::Hexagonal);
+        assert_eq!(rule.to_string(), "B2/S34H");
+        assert!("B7/S34H".parse::<Rule>().is_err());
+
+        let neighbours: Vec<(isize, isize)> = (-1..=1)
+            .flat_map(|y| (-1..=1).map(move |x| (x, y)))
+            .filter(|&(x, y)| Neighbourhood::Hexagonal.contains((0, 0), x, y, 1))
+            .collect();
+        assert_eq!(
+            neighbours,
+            vec![(-1, -1), (0, -1), (-1, 0), (1, 0), (0, 1), (1, 1)]
+        );
+    }
+
+    #[test]
+    fn test_triangular() {
+        let rule: Rule = "B45/S34L".parse().unwrap();
+        assert_eq!(rule.neighbourhood(), Neighbourhood::Triangular);
+        assert_eq!(rule.to_string(), "B45/S34L");
+        assert!("R2,C0,M0,S1..2,B1..2,NM"
+            .parse::<Rule>()
+            .unwrap()
+            .with_neighbourhood(Neighbourhood::Triangular)
+            .is_err());
+
+        let neighbours = |cell| {
+            (-1..=1)
+                .flat_map(|y| (-2..=2).map(move |x| (x, y)))
+                .filter(|&(x, y)| Neighbourhood::Triangular.contains(cell, x, y, 1))
+                .collect::<Vec<(isize, isize)>>()
+        };
+        // Pointing up, with three neighbours above and five below.
+        let up = neighbours((0, 0));
+        assert_eq!(up.len(), 12);
+        assert!(up
+            .iter()
+            .filter(|(_, y)| *y == -1)
+            .all(|(x, _)| x.abs() <= 1));
+        // Pointing down, the other way around.
+        let down = neighbours((1, 0));
+        assert_eq!(down.len(), 12);
+        assert!(down
+            .iter()
+            .filter(|(_, y)| *y == 1)
+            .all(|(x, _)| x.abs() <= 1));
+    }
+
+    #[test]
+    fn test_wireworld() {
+        let rule: Rule = "WireWorld".parse().unwrap();
+
+        assert_eq!(rule, Rule::wireworld());
+        assert_eq!(rule.family(), Family::Wireworld);
+        assert_eq!(rule.states(), 4);
+        assert_eq!(rule.to_string(), "Wireworld");
+        assert_eq!(Rule::conway().family(), Family::LifeLike);
+    }
+
+    #[test]
+    fn test_neighbourhood_shape() {
+        let rule: Rule = "B2/S013V".parse().unwrap();
+        assert_eq!(rule.offsets((1, 1)), vec![(0, -1), (-1, 0), (1, 0), (0, 1)]);
+        assert_eq!(Rule::conway().offsets((1, 1)).len(), 8);
+        assert_eq!("B2/S34H".parse::<Rule>().unwrap().offsets((1, 1)).len(), 6);
+    }
+
+    #[test]
+    fn test_conway_cell_rule() {
+        for alive in &[false, true] {
+            for count in 0..=8 {
+                assert_eq!(
+                    ConwayRule.next(*alive, count),
+                    Rule::conway().next(*alive, count)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_immigration() {
+        let rule: Rule = "immigration".parse().unwrap();
+
+        assert_eq!(rule, Rule::immigration());
+        assert_eq!(rule.family(), Family::Immigration);
+        assert_eq!(rule.teams(), 2);
+        assert!(rule.births(3) && rule.survives(2) && !rule.survives(4));
+        assert_eq!(rule.to_string(), "Immigration");
+        assert_eq!(
+            "ImmigrationH".parse::<Rule>().unwrap().to_string(),
+            "ImmigrationH"
+        );
+        assert_eq!(Rule::conway().teams(), 1);
+    }
+
+    #[test]
+    fn test_quadlife() {
+        let rule: Rule = "QuadLife".parse().unwrap();
+
+        assert_eq!(rule, Rule::quadlife());
+        assert_eq!(rule.teams(), 4);
+        assert_eq!(rule.to_string(), "QuadLife");
+    }
+
+    #[test]
+    fn test_cyclic() {
+        let rule: Rule = "cyclic".parse().unwrap();
+        assert_eq!(rule, Rule::cyclic(16, 1));
+        assert_eq!(rule.family(), Family::Cyclic);
+        assert_eq!(rule.to_string(), "Cyclic/C16/T1");
+
+        let rule: Rule = "Cyclic/C8/T3V".parse().unwrap();
+        assert_eq!(rule.states(), 8);
+        assert!(!rule.births(2));
+        assert!(rule.births(3));
+        assert_eq!(rule.neighbourhood(), Neighbourhood::VonNeumann);
+        assert_eq!(rule.to_string(), "Cyclic/C8/T3V");
+
+        assert!("cyclic/C1".parse::<Rule>().is_err());
+        assert!("cyclic/T0".parse::<Rule>().is_err());
+        assert!("cyclicC4".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn test_presets() {
+        for (name, rule) in PRESETS {
+            assert_eq!(name.parse::<Rule>(), rule.parse::<Rule>());
+            assert_eq!(name.parse::<Rule>().unwrap().to_string(), *rule);
+        }
+
+        assert_eq!("HighLife".parse::<Rule>().unwrap().to_string(), "B36/S23");
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Rule::conway().to_string(), "B3/S23");
+        assert_eq!("23/36".parse::<Rule>().unwrap().to_string(), "B36/S23");
+    }
+}
@@ -0,0 +1,83 @@
+//! Rules written as Rhai scripts, for experimenting without recompiling.
+
+use crate::rule::CellRule;
+use rhai::{Engine, Scope, AST};
+
+/// A rule from a script defining `next_state(alive, neighbours)`, which
+/// returns whether a cell with `neighbours` live neighbours is alive next
+/// generation, e.g.
+///
+/// ```text
+/// fn next_state(alive, neighbours) {
+///     neighbours == 3 || (alive && neighbours == 2)
+/// }
+/// ```
+///
+/// There are only eighteen possible inputs, so the script runs once for each
+/// of them up front rather than for every cell.
+#[derive(Clone, Debug)]
+pub struct ScriptRule {
+    next_states: [[bool; 9]; 2],
+}
+
+impl ScriptRule {
+    pub fn from_script(script: &str) -> Result<Self, String> {
+        let engine = Engine::new();
+        let ast = engine.compile(script).map_err(|e| e.to_string())?;
+        let mut next_states = [[false; 9]; 2];
+
+        for (alive, next_states) in next_states.iter_mut().enumerate() {
+            for (neighbours, next_state) in next_states.iter_mut().enumerate() {
+                *next_state = call(&engine, &ast, alive == 1, neighbours as i64)?;
+            }
+        }
+
+        Ok(Self { next_states })
+    }
+}
+
+fn call(engine: &Engine, ast: &AST, alive: bool, neighbours: i64) -> Result<bool, String> {
+    engine
+        .call_fn(&mut Scope::new(), ast, "next_state", (alive, neighbours))
+        .map_err(|e| format!("next_state({}, {}): {}", alive, neighbours, e))
+}
+
+impl CellRule for ScriptRule {
+    fn next(&self, alive: bool, live_neighbours: u8) -> bool {
+        self.next_states[usize::from(alive)][usize::from(live_neighbours)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::Rule;
+
+    #[test]
+    fn test_from_script() {
+        let script = "
+            fn next_state(alive, neighbours) {
+                if alive {
+                    neighbours == 2 || neighbours == 3
+                } else {
+                    neighbours == 3 || neighbours == 6
+                }
+            }
+        ";
+        let rule = ScriptRule::from_script(script).unwrap();
+        let highlife: Rule = "highlife".parse().unwrap();
+
+        for alive in &[false, true] {
+            for count in 0..=8 {
+                assert_eq!(rule.next(*alive, count), highlife.next(*alive, count));
+            }
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(ScriptRule::from_script("fn next_state(").is_err());
+        assert!(ScriptRule::from_script("fn other() { true }").is_err());
+        assert!(ScriptRule::from_script("fn next_state(alive, neighbours) { 1 }").is_err());
+    }
+}
@@ -0,0 +1,342 @@
+use crate::boundary::Boundary;
+use crate::chance::Chance;
+use crate::formats::{Format, ParseError, SeedError};
+use crate::region::Region;
+use crate::rule::Rule;
+use crate::world::World;
+
+const HEADER: &str = "#gol session";
+
+/// Everything needed to pick a run back up where it left off: the world
+/// (including its generation, rule, boundary, regions, chance and the
+/// states and teams of its cells), whether it was paused, and any edits that
+/// hadn't been applied yet. Rules from scripts aren't kept.
+///
+/// Sessions are written as a few `key value` lines followed by the world's
+/// live cells as RLE, so they can be inspected and edited by hand.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Session {
+    pub world: World,
+    pub pending_edits: Vec<(usize, usize)>,
+    pub paused: bool,
+}
+
+impl Session {
+    pub fn new(world: World) -> Self {
+        Self {
+            world,
+            pending_edits: Vec::new(),
+            paused: false,
+        }
+    }
+
+    /// Chance is only carried on exactly if it was seeded, which
+    /// `Chance::reseed` does just before saving.
+    pub fn write(&self) -> String {
+        let world = &self.world;
+        let mut output = format!("{}\n", HEADER);
+        output.push_str(&format!("generation {}\n", world.generation()));
+        output.push_str(&format!("rule {}\n", world.rule()));
+        output.push_str(&format!("boundary {}\n", world.boundary()));
+        for region in world.regions() {
+            output.push_str(&format!("region {}\n", region));
+        }
+        if let Some(chance) = world.chance() {
+            let (birth, survival, noise) = chance.probabilities();
+            output.push_str(&format!("chance {} {} {}\n", birth, survival, noise));
+            if let Some(seed) = chance.seed() {
+                output.push_str(&format!("seed {}\n", seed));
+            }
+        }
+        if self.paused {
+            output.push_str("paused true\n");
+        }
+
+        // Live cells are in the RLE, but not their teams or the states of
+        // the others.
+        let states: Vec<String> = world
+            .cells_with_coords()
+            .filter(|(_, cell)| !cell.alive && cell.state() > 1)
+            .map(|((x, y), cell)| format!("{},{}:{}", x, y, cell.state()))
+            .collect();
+        if !states.is_empty() {
+            output.push_str(&format!("states {}\n", states.join(" ")));
+        }
+        let teams: Vec<String> = world
+            .cells_with_coords()
+            .filter(|(_, cell)| cell.alive && cell.team() > 0)
+            .map(|((x, y), cell)| format!("{},{}:{}", x, y, cell.team()))
+            .collect();
+        if !teams.is_empty() {
+            output.push_str(&format!("teams {}\n", teams.join(" ")));
+        }
+
+        if !self.pending_edits.is_empty() {
+            let edits: Vec<String> = self
+                .pending_edits
+                .iter()
+                .map(|(x, y)| format!("{},{}", x, y))
+                .collect();
+            output.push_str(&format!("pending {}\n", edits.join(" ")));
+        }
+
+        output.push_str(&self.world.to_rle());
+        output
+    }
+
+    pub fn parse(input: &str) -> Result<Self, SeedError> {
+        let mut lines = input.lines().enumerate();
+
+        match lines.next() {
+            Some((_, HEADER)) => {}
+            _ => return Err(ParseError::new(1, "missing session header").into()),
+        }
+
+        let mut generation = 0;
+        let mut rule = Rule::default();
+        let mut boundary = Boundary::default();
+        let mut regions = Vec::new();
+        let mut chance = None;
+        let mut seed = None;
+        let mut paused = false;
+        let mut states = Vec::new();
+        let mut teams = Vec::new();
+        let mut pending_edits = Vec::new();
+        let mut rle = String::new();
+
+        for (i, line) in lines.by_ref() {
+            let line = line.trim();
+            let mut parts = line.splitn(2, ' ');
+
+            match (parts.next(), parts.next()) {
+                (Some("generation"), Some(value)) => {
+                    generation = value
+                        .parse()
+                        .map_err(|_| ParseError::new(i + 1, "invalid generation"))?;
+                }
+                (Some("rule"), Some(value)) => {
+                    rule = value
+                        .parse()
+                        .map_err(|e: String| ParseError::new(i + 1, e))?;
+                }
+                (Some("boundary"), Some(value)) => {
+                    boundary = value
+                        .parse()
+                        .map_err(|e: String| ParseError::new(i + 1, e))?;
+                }
+                (Some("region"), Some(value)) => {
+                    let region: Region = value
+                        .parse()
+                        .map_err(|e: String| ParseError::new(i + 1, e))?;
+                    regions.push((i + 1, region));
+                }
+                (Some("chance"), Some(value)) => {
+                    let probabilities: Vec<f64> = value
+                        .split_whitespace()
+                        .map(str::parse)
+                        .collect::<Result<_, _>>()
+                        .ok()
+                        .filter(|probabilities: &Vec<f64>| {
+                            probabilities.len() == 3
+                                && probabilities.iter().all(|p| (0.0..=1.0).contains(p))
+                        })
+                        .ok_or_else(|| ParseError::new(i + 1, "invalid chance"))?;
+                    chance = Some(probabilities);
+                }
+                (Some("seed"), Some(value)) => {
+                    seed = Some(
+                        value
+                            .parse()
+                            .map_err(|_| ParseError::new(i + 1, "invalid seed"))?,
+                    );
+                }
+                (Some("paused"), Some(value)) => {
+                    paused = value
+                        .parse()
+                        .map_err(|_| ParseError::new(i + 1, "invalid paused"))?;
+                }
+                (Some(key @ "states"), Some(value)) | (Some(key @ "teams"), Some(value)) => {
+                    let cells = if key == "states" {
+                        &mut states
+                    } else {
+                        &mut teams
+                    };
+                    for cell in value.split_whitespace() {
+                        let cell = parse_cell_value(cell).ok_or_else(|| {
+                            ParseError::new(i + 1, format!("invalid {}: {}", key, cell))
+                        })?;
+                        cells.push((i + 1, cell));
+                    }
+                }
+                (Some("pending"), Some(value)) => {
+                    for edit in value.split_whitespace() {
+                        pending_edits.push(parse_coordinate(edit).ok_or_else(|| {
+                            ParseError::new(i + 1, format!("invalid pending edit: {}", edit))
+                        })?);
+                    }
+                }
+                _ => {
+                    rle.push_str(line);
+                    rle.push('\n');
+                    break;
+                }
+            }
+        }
+
+        for (_, line) in lines {
+            rle.push_str(line);
+            rle.push('\n');
+        }
+
+        let pattern = Format::Rle.parse(&rle)?;
+        let mut world = World::new(pattern.width, pattern.height);
+        world.seed_pattern(&pattern)?;
+        world.set_generation(generation);
+        world.set_rule(rule);
+        world.set_boundary(boundary);
+        for (line, region) in regions {
+            world
+                .add_region(region)
+                .map_err(|e| ParseError::new(line, e))?;
+        }
+        if let Some(probabilities) = chance {
+            world.set_chance(
+                Chance::new(probabilities[0], probabilities[1], seed).with_noise(probabilities[2]),
+            );
+        }
+
+        let outside = |x: usize, y: usize| x >= world.width || y >= world.height;
+        if let Some((line, ((x, y), _))) = states
+            .iter()
+            .chain(&teams)
+            .find(|(_, ((x, y), _))| outside(*x, *y))
+        {
+            let message = format!("{},{} is outside the world", x, y);
+            return Err(ParseError::new(*line, message).into());
+        }
+        for (_, ((x, y), state)) in states {
+            world.set_cell_state(x, y, state);
+        }
+        for (_, ((x, y), team)) in teams {
+            world.set_cell_team(x, y, team);
+        }
+
+        Ok(Self {
+            world,
+            pending_edits,
+            paused,
+        })
+    }
+}
+
+/// A cell's coordinates and its state or team, as `X,Y:N`.
+fn parse_cell_value(s: &str) -> Option<((usize, usize), u8)> {
+    let (coordinate, value) = s.split_once(':')?;
+    Some((parse_coordinate(coordinate)?, value.parse().ok()?))
+}
+
+fn parse_coordinate(s: &str) -> Option<(usize, usize)> {
+    let mut parts = s.splitn(2, ',');
+    let x = parts.next()?.parse().ok()?;
+    let y = parts.next()?.parse().ok()?;
+    Some((x, y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut world = World::new(5, 4);
+        world.seed_from_string("# # #".to_string(), (1, 1));
+        world.set_rule("B36/S23".parse().unwrap());
+        world.simulate();
+
+        let session = Session {
+            world,
+            pending_edits: vec![(0, 0), (4, 3)],
+            paused: false,
+        };
+        let output = session.write();
+
+        assert_eq!(
+            output,
+            "#gol session\ngeneration 1\nrule B36/S23\nboundary wrap\npending 0,0 4,3\nx = 5, y = 4\n2bo$2bo$2bo!\n"
+        );
+
+        let restored = Session::parse(&output).unwrap();
+        assert_eq!(restored, session);
+        assert_eq!(restored.world.generation(), 1);
+        assert_eq!(restored.world.rule(), session.world.rule());
+    }
+
+    #[test]
+    fn test_round_trip_everything() {
+        let mut world = World::new(6, 5);
+        world.set_rule("B36/S23".parse().unwrap());
+        world.set_boundary(Boundary::Dead);
+        world.add_region("0,0,3,5:seeds".parse().unwrap()).unwrap();
+        world.set_chance(Chance::new(0.5, 0.75, None).with_noise(0.125));
+        world.seed_from_string("# # #".to_string(), (1, 1));
+        world.simulate();
+        let seed = world.chance_mut().unwrap().reseed();
+
+        let session = Session {
+            world,
+            pending_edits: vec![(2, 2)],
+            paused: true,
+        };
+        let restored = Session::parse(&session.write()).unwrap();
+        assert_eq!(restored, session);
+        let world = &restored.world;
+        assert_eq!(world.generation(), 1);
+        assert_eq!(world.boundary(), Boundary::Dead);
+        assert_eq!(world.regions(), session.world.regions());
+        let chance = world.chance().unwrap();
+        assert_eq!(chance.probabilities(), (0.5, 0.75, 0.125));
+        assert_eq!(chance.seed(), Some(seed));
+        assert!(restored.paused);
+
+        // Both carry on the same.
+        let (mut original, mut restored) = (session.world, restored.world);
+        for _ in 0..5 {
+            original.simulate();
+            restored.simulate();
+        }
+        assert_eq!(original, restored);
+    }
+
+    #[test]
+    fn test_round_trip_states_and_teams() {
+        let mut world = World::new(4, 3);
+        world.set_rule("briansbrain".parse().unwrap());
+        world.set_cell_state(1, 1, 1);
+        world.set_cell_state(2, 1, 2);
+        let restored = Session::parse(&Session::new(world.clone()).write()).unwrap();
+        assert_eq!(restored.world.row(1)[2].state(), 2);
+        assert_eq!(restored.world, world);
+        assert_eq!(restored.world.state_hash(), world.state_hash());
+
+        let mut world = World::new(4, 3);
+        world.set_rule("immigration".parse().unwrap());
+        world.set_cell_team(0, 0, 0);
+        world.set_cell_team(3, 2, 1);
+        let restored = Session::parse(&Session::new(world.clone()).write()).unwrap();
+        assert_eq!(restored.world.row(2)[3].team(), 1);
+        assert_eq!(restored.world, world);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Session::parse("x = 1, y = 1\no!\n").is_err());
+        assert!(Session::parse("#gol session\ngeneration x\nx = 1, y = 1\no!\n").is_err());
+        assert!(Session::parse("#gol session\nrule B9\nx = 1, y = 1\no!\n").is_err());
+        assert!(Session::parse("#gol session\npending 1\nx = 1, y = 1\no!\n").is_err());
+        assert!(Session::parse("#gol session\nboundary sphere\nx = 1, y = 1\no!\n").is_err());
+        assert!(Session::parse("#gol session\nchance 2 1 0\nx = 1, y = 1\no!\n").is_err());
+        assert!(Session::parse("#gol session\nseed x\nx = 1, y = 1\no!\n").is_err());
+        assert!(Session::parse("#gol session\npaused 1\nx = 1, y = 1\no!\n").is_err());
+        assert!(Session::parse("#gol session\nstates 5,0:2\nx = 1, y = 1\no!\n").is_err());
+    }
+}
@@ -0,0 +1,270 @@
+//! Moving a world on in a thread of its own, so a slow generation never
+//! holds up drawing the world or handling input.
+
+use crate::pacer::Pacer;
+use crate::world::World;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// What the simulation thread is told.
+enum Message {
+    /// Carry on from this world instead, which has been edited since it was
+    /// sent back. The number counts the replacements so far.
+    Replace(Box<World>, u64),
+    /// Move on this many generations between snapshots.
+    SetSteps(usize),
+    /// Stop moving on, or start again, without catching up.
+    SetPaused(bool),
+    /// Move on a single generation while paused.
+    Step,
+}
+
+/// A generation, as the simulation thread sends it back.
+struct Snapshot {
+    world: World,
+    /// How many replacements it was worked out after.
+    replacements: u64,
+    /// How long it took to work out.
+    took: Duration,
+}
+
+/// A world being moved on by another thread, which sends back a copy of
+/// each generation, or of the last of each batch when it takes several
+/// steps at a time. Edits are made to a copy and sent over whole, so the
+/// thread never has to wait on the window or the window on the thread.
+/// The thread stops when this is dropped.
+pub struct Simulator {
+    messages: Option<Sender<Message>>,
+    snapshots: Receiver<Snapshot>,
+    thread: Option<JoinHandle<()>>,
+    replacements: u64,
+    /// How long the generations sent back since `take_time` took to work
+    /// out.
+    time: Duration,
+}
+
+impl Simulator {
+    /// Starts moving `world` on whenever `pacer` says generations are due.
+    pub fn spawn(world: World, pacer: Pacer) -> Self {
+        let (messages, inbox) = mpsc::channel();
+        let (outbox, snapshots) = mpsc::channel();
+        let thread = thread::spawn(move || run(world, pacer, inbox, outbox));
+
+        Self {
+            messages: Some(messages),
+            snapshots,
+            thread: Some(thread),
+            replacements: 0,
+            time: Duration::ZERO,
+        }
+    }
+
+    /// The newest generation sent back since the last call, if there's been
+    /// one since the world was last replaced.
+    pub fn latest(&mut self) -> Option<World> {
+        let mut latest = None;
+        for snapshot in self.snapshots.try_iter() {
+            self.time += snapshot.took;
+            // Generations of a world that's been replaced since would undo
+            // the edits.
+            if snapshot.replacements == self.replacements {
+                latest = Some(snapshot.world);
+            }
+        }
+        latest
+    }
+
+    /// How long the thread spent working out the generations it's sent back
+    /// since the last call.
+    pub fn take_time(&mut self) -> Duration {
+        std::mem::replace(&mut self.time, Duration::ZERO)
+    }
+
+    /// Has the thread carry on from `world` instead of the world it has.
+    pub fn replace(&mut self, world: World) {
+        self.replacements += 1;
+        self.send(Message::Replace(Box::new(world), self.replacements));
+    }
+
+    /// Has the thread move on `steps` generations for each one that's due,
+    /// sending back only the last.
+    pub fn set_steps(&mut self, steps: usize) {
+        self.send(Message::SetSteps(steps));
+    }
+
+    /// Stops the thread moving the world on, or starts it again. Generations
+    /// that would have been due in between are skipped rather than made up.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.send(Message::SetPaused(paused));
+    }
+
+    /// Has the thread move on exactly one generation while it's paused.
+    pub fn step(&mut self) {
+        self.send(Message::Step);
+    }
+
+    fn send(&self, message: Message) {
+        if let Some(messages) = &self.messages {
+            // The thread only stops once this is dropped.
+            let _ = messages.send(message);
+        }
+    }
+}
+
+impl Drop for Simulator {
+    fn drop(&mut self) {
+        // Hanging up tells the thread to stop.
+        self.messages = None;
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run(mut world: World, mut pacer: Pacer, inbox: Receiver<Message>, outbox: Sender<Snapshot>) {
+    let mut replacements = 0;
+    let mut steps = 1;
+    let mut paused = false;
+    loop {
+        // Take in messages until the next generation's due.
+        let wait = pacer.next_due().saturating_duration_since(Instant::now());
+        let generations = match inbox.recv_timeout(wait) {
+            Ok(Message::Replace(replacement, count)) => {
+                world = *replacement;
+                replacements = count;
+                continue;
+            }
+            Ok(Message::SetSteps(count)) => {
+                steps = count;
+                continue;
+            }
+            Ok(Message::SetPaused(pause)) => {
+                paused = pause;
+                continue;
+            }
+            Ok(Message::Step) if paused => 1,
+            Ok(Message::Step) => continue,
+            Err(RecvTimeoutError::Timeout) => {
+                // Generations still come due while paused, so none are owed
+                // after.
+                let due = pacer.take_due(Instant::now()) as usize;
+                if due == 0 || paused {
+                    continue;
+                }
+                due * steps
+            }
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+
+        let start = Instant::now();
+        for _ in 0..generations {
+            world.simulate();
+        }
+        let snapshot = Snapshot {
+            world: world.clone(),
+            replacements,
+            took: start.elapsed(),
+        };
+        if outbox.send(snapshot).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pacer() -> Pacer {
+        Pacer::new(1000.0, Instant::now())
+    }
+
+    /// Waits for the simulator to send back a generation.
+    fn next_world(simulator: &mut Simulator) -> World {
+        loop {
+            if let Some(world) = simulator.latest() {
+                return world;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_simulates() {
+        let mut world = World::new(10, 10);
+        world.seed_from_string("- # -\n- # -\n- # -".to_string(), (3, 3));
+        let mut simulator = Simulator::spawn(world.clone(), pacer());
+
+        let latest = next_world(&mut simulator);
+        assert!(latest.generation() > 0);
+        assert!(simulator.take_time() > Duration::ZERO);
+        assert_eq!(simulator.take_time(), Duration::ZERO);
+        for _ in 0..latest.generation() {
+            world.simulate();
+        }
+        assert_eq!(latest, world);
+    }
+
+    #[test]
+    fn test_replace() {
+        let mut world = World::new(10, 10);
+        world.seed_from_string("- # -\n- # -\n- # -".to_string(), (3, 3));
+        let mut simulator = Simulator::spawn(world, pacer());
+        next_world(&mut simulator);
+
+        let mut empty = World::new(10, 10);
+        empty.set_generation(100);
+        simulator.replace(empty);
+        let latest = next_world(&mut simulator);
+        assert!(latest.generation() > 100);
+        assert_eq!(latest.population(), 0);
+    }
+
+    #[test]
+    fn test_set_paused() {
+        let mut simulator = Simulator::spawn(World::new(5, 5), pacer());
+        simulator.set_paused(true);
+        simulator.replace(World::new(5, 5));
+        thread::sleep(Duration::from_millis(20));
+        assert!(simulator.latest().is_none());
+
+        simulator.set_paused(false);
+        assert!(next_world(&mut simulator).generation() > 0);
+    }
+
+    #[test]
+    fn test_step() {
+        let mut simulator = Simulator::spawn(World::new(5, 5), pacer());
+        simulator.set_steps(10);
+        simulator.set_paused(true);
+        simulator.replace(World::new(5, 5));
+        simulator.step();
+        simulator.step();
+        let mut generation = next_world(&mut simulator).generation();
+        // Both steps may or may not have been sent back by now.
+        if generation == 1 {
+            generation = next_world(&mut simulator).generation();
+        }
+        assert_eq!(generation, 2);
+        thread::sleep(Duration::from_millis(20));
+        assert!(simulator.latest().is_none());
+    }
+
+    #[test]
+    fn test_set_steps() {
+        let mut simulator = Simulator::spawn(World::new(5, 5), pacer());
+        simulator.set_steps(10);
+        // Only generations from after the replacement, and so the change,
+        // are sent back.
+        simulator.replace(World::new(5, 5));
+        for _ in 0..3 {
+            let generation = next_world(&mut simulator).generation();
+            assert!(
+                generation > 0 && generation.is_multiple_of(10),
+                "{}",
+                generation
+            );
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! A world which only stores where its live cells are, for large worlds
+//! which are mostly empty.
+
+use crate::boundary::Boundary;
+use crate::grid::{hash_bytes, Grid};
+use crate::rule::{NeighbourhoodShape, Rule};
+use crate::world::{dependents, neighbours_symmetric, World};
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+/// A world of two-state cells kept as a set of live coordinates. Simulating
+/// it takes time in proportion to the number of live cells rather than the
+/// size of the world.
+#[derive(Clone, Debug)]
+pub struct SparseWorld {
+    pub width: usize,
+    pub height: usize,
+    live_cells: HashSet<(usize, usize)>,
+    rule: Rule,
+    boundary: Boundary,
+    generation: usize,
+}
+
+impl SparseWorld {
+    pub fn new(width: usize, height: usize, rule: Rule) -> Result<Self, String> {
+        rule.check_two_state()?;
+
+        if rule.births(0) {
+            Err(format!("{} births cells out of nothing, everywhere", rule))
+        } else {
+            Ok(Self {
+                width,
+                height,
+                live_cells: HashSet::new(),
+                rule,
+                boundary: Boundary::default(),
+                generation: 0,
+            })
+        }
+    }
+
+    /// A sparse world with the same size, rule, boundary and live cells as
+    /// `world`. Regions and chance aren't carried over.
+    pub fn from_world(world: &World) -> Result<Self, String> {
+        let mut sparse = Self::new(world.width, world.height, world.rule())?;
+        sparse.boundary = world.boundary();
+        sparse.live_cells = world.live_cells().collect();
+        sparse.generation = world.generation();
+        Ok(sparse)
+    }
+
+    pub fn set_boundary(&mut self, boundary: Boundary) {
+        self.boundary = boundary;
+    }
+
+    pub fn live_cells_count(&self) -> usize {
+        self.live_cells.len()
+    }
+}
+
+impl Grid for SparseWorld {
+    fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn generation(&self) -> usize {
+        self.generation
+    }
+
+    fn is_alive(&self, x: usize, y: usize) -> bool {
+        self.live_cells.contains(&(x, y))
+    }
+
+    fn set_alive(&mut self, x: usize, y: usize, alive: bool) {
+        if alive {
+            self.live_cells.insert((x, y));
+        } else {
+            self.live_cells.remove(&(x, y));
+        }
+    }
+
+    fn live_cells(&self) -> Vec<(usize, usize)> {
+        self.live_cells.iter().cloned().collect()
+    }
+
+    fn population(&self) -> usize {
+        self.live_cells_count()
+    }
+
+    fn memory_usage(&self) -> usize {
+        mem::size_of::<Self>() + hash_bytes::<(usize, usize)>(self.live_cells.capacity())
+    }
+
+    /// Each live cell adds one to the count of each cell it's a neighbour
+    /// of, so only cells next to live ones are ever looked at.
+    fn simulate(&mut self) {
+        let size = (self.width, self.height);
+        let mut counts: HashMap<(usize, usize), u8> = HashMap::new();
+        let symmetric = neighbours_symmetric(self.rule.neighbourhood(), self.boundary, size);
+
+        for &(x, y) in &self.live_cells {
+            if !symmetric {
+                for neighbour in dependents(&self.rule, self.boundary, size, x, y) {
+                    *counts.entry(neighbour).or_insert(0) += 1;
+                }
+                continue;
+            }
+            for (x_offset, y_offset) in self.rule.offsets((x, y)) {
+                let position = (x as isize + x_offset, y as isize + y_offset);
+                if let Some(neighbour) = self.boundary.resolve(position, size) {
+                    *counts.entry(neighbour).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let mut next: HashSet<(usize, usize)> = counts
+            .iter()
+            .filter(|(cell, count)| {
+                if self.live_cells.contains(cell) {
+                    self.rule.survives(**count)
+                } else {
+                    self.rule.births(**count)
+                }
+            })
+            .map(|(cell, _)| *cell)
+            .collect();
+        if self.rule.survives(0) {
+            next.extend(
+                self.live_cells
+                    .iter()
+                    .filter(|cell| !counts.contains_key(cell)),
+            );
+        }
+
+        self.live_cells = next;
+        self.generation += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_world() {
+        for rule in &["B3/S23", "B2/S0", "B2/S013V", "B2/S34H", "B2/S13L"] {
+            for boundary in &[
+                Boundary::Wrap,
+                Boundary::Dead,
+                Boundary::Mirror,
+                Boundary::Klein,
+                Boundary::CrossSurface,
+            ] {
+                let mut world = World::new(12, 10);
+                world.set_rule(rule.parse().unwrap());
+                world.set_boundary(*boundary);
+                world.seed_from_string(
+                    "- # # - -
+                     # # - - #
+                     - # - - -"
+                        .to_string(),
+                    (0, 0),
+                );
+                world.toggle_cell(11, 9);
+                world.toggle_cell(10, 9);
+                let mut sparse = SparseWorld::from_world(&world).unwrap();
+
+                for _ in 0..10 {
+                    world.simulate();
+                    sparse.simulate();
+
+                    let mut expected = Grid::live_cells(&world);
+                    let mut cells = sparse.live_cells();
+                    expected.sort();
+                    cells.sort();
+                    assert_eq!(cells, expected, "{} {:?}", rule, boundary);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_grid() {
+        let mut sparse = SparseWorld::new(5, 5, Rule::conway()).unwrap();
+        let blinker = crate::formats::Pattern::from_cells(vec![(0, 0), (1, 0), (2, 0)]);
+
+        sparse.seed_pattern_at(&blinker, 1, 2).unwrap();
+        assert!(sparse.seed_pattern_at(&blinker, 3, 0).is_err());
+        sparse.toggle(0, 0);
+        sparse.toggle(0, 0);
+        assert_eq!(sparse.live_cells_count(), 3);
+
+        sparse.simulate();
+        let mut cells = sparse.live_cells();
+        cells.sort();
+        assert_eq!(cells, vec![(2, 1), (2, 2), (2, 3)]);
+        assert_eq!(sparse.generation(), 1);
+    }
+
+    #[test]
+    fn test_unsupported_rules() {
+        assert!(SparseWorld::new(5, 5, "B0/S8".parse().unwrap()).is_err());
+        assert!(SparseWorld::new(5, 5, Rule::wireworld()).is_err());
+        assert!(SparseWorld::new(5, 5, "B2ce/S23".parse().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+//! Writing patterns as SVG images.
+
+use crate::formats::Pattern;
+
+pub struct SvgOptions {
+    /// Width and height of each cell, in pixels.
+    pub cell_size: usize,
+    pub alive_color: u32,
+    pub background_color: u32,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            cell_size: 10,
+            alive_color: 0xff0000,
+            background_color: 0x000000,
+        }
+    }
+}
+
+/// Renders a pattern as an SVG document with one `rect` per live cell.
+pub fn to_svg(pattern: &Pattern, options: &SvgOptions) -> String {
+    let width = pattern.width * options.cell_size;
+    let height = pattern.height * options.cell_size;
+
+    let mut output = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+        width, height, width, height
+    );
+    output.push_str(&format!(
+        "<rect width=\"{}\" height=\"{}\" fill=\"#{:06x}\"/>\n",
+        width, height, options.background_color
+    ));
+    output.push_str(&format!("<g fill=\"#{:06x}\">\n", options.alive_color));
+    for (x, y) in &pattern.cells {
+        output.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>\n",
+            x * options.cell_size,
+            y * options.cell_size,
+            options.cell_size,
+            options.cell_size
+        ));
+    }
+    output.push_str("</g>\n</svg>\n");
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_svg() {
+        let pattern = Pattern::from_cells(vec![(1, 0), (0, 1)]);
+        let options = SvgOptions {
+            cell_size: 5,
+            alive_color: 0x00ff00,
+            background_color: 0xffffff,
+        };
+
+        assert_eq!(
+            to_svg(&pattern, &options),
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10" viewBox="0 0 10 10">
+<rect width="10" height="10" fill="#ffffff"/>
+<g fill="#00ff00">
+<rect x="5" y="0" width="5" height="5"/>
+<rect x="0" y="5" width="5" height="5"/>
+</g>
+</svg>
+"##
+        );
+    }
+}
@@ -1,7 +1,13 @@
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
 pub struct WindowBuffer {
     pub buffer: Vec<u32>,
     width: usize,
     height: usize,
+    /// The left, top, right and bottom edges of the pixels changed since the
+    /// last `take_dirty`, inclusive.
+    dirty: Option<(usize, usize, usize, usize)>,
 }
 
 impl WindowBuffer {
@@ -10,16 +16,316 @@ impl WindowBuffer {
             buffer: vec![0; width * height],
             width,
             height,
+            dirty: None,
         }
     }
 
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> u32 {
+        self.buffer[y * self.width + x]
+    }
+
     pub fn set_pixel(&mut self, x: usize, y: usize, color: u32) {
         let l = y * self.width + x;
         assert!(l <= self.width * self.height);
         self.buffer[l] = color;
+        self.mark_dirty((x, y), (x, y));
     }
 
     pub fn clear(&mut self) {
-        self.buffer = vec![0; self.width * self.height];
+        self.fill(0);
+    }
+
+    /// Sets every pixel to `color`, keeping the buffer's allocation unless
+    /// it's grown.
+    pub fn fill(&mut self, color: u32) {
+        self.buffer.clear();
+        self.buffer.resize(self.width * self.height, color);
+        if self.width > 0 && self.height > 0 {
+            self.mark_dirty((0, 0), (self.width - 1, self.height - 1));
+        }
+    }
+
+    /// Sets each pixel in the rectangle with its top left corner at
+    /// `(x, y)` to `color(x, y)`, only marking the ones that change as
+    /// dirty. Rows are painted on separate threads with the `parallel`
+    /// feature, so `color` mustn't depend on the order it's called in.
+    pub fn paint(
+        &mut self,
+        ((x, y), (width, height)): ((usize, usize), (usize, usize)),
+        color: impl Fn(usize, usize) -> u32 + Sync,
+    ) {
+        let columns = x..(x + width).min(self.width);
+        // The left and right edges of the pixels changed in a row.
+        let paint_row = |(row_y, row): (usize, &mut [u32])| {
+            let mut changed: Option<(usize, usize)> = None;
+            for px in columns.clone() {
+                let color = color(px, row_y);
+                if row[px] != color {
+                    row[px] = color;
+                    changed = Some(changed.map_or((px, px), |(left, _)| (left, px)));
+                }
+            }
+            changed.map(|(left, right)| (row_y, left, right))
+        };
+
+        let row_length = self.width.max(1);
+        #[cfg(feature = "parallel")]
+        let rows = self.buffer.par_chunks_mut(row_length).enumerate();
+        #[cfg(not(feature = "parallel"))]
+        let rows = self.buffer.chunks_mut(row_length).enumerate();
+        let changes: Vec<_> = rows.skip(y).take(height).map(paint_row).collect();
+
+        for (row_y, left, right) in changes.into_iter().flatten() {
+            self.mark_dirty((left, row_y), (right, row_y));
+        }
+    }
+
+    /// Outlines the rectangle with its top left corner at `(x, y)`. Like
+    /// the other drawing, anything outside the buffer is left off.
+    pub fn draw_rect(
+        &mut self,
+        (x, y): (usize, usize),
+        (width, height): (usize, usize),
+        color: u32,
+    ) {
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let (right, bottom) = (x + width - 1, y + height - 1);
+        self.draw_line((x, y), (right, y), color);
+        self.draw_line((x, bottom), (right, bottom), color);
+        self.draw_line((x, y), (x, bottom), color);
+        self.draw_line((right, y), (right, bottom), color);
+    }
+
+    /// Draws a line between two pixels, both included, with Bresenham's
+    /// algorithm.
+    pub fn draw_line(&mut self, from: (usize, usize), to: (usize, usize), color: u32) {
+        let (mut x, mut y) = (from.0 as isize, from.1 as isize);
+        let (to_x, to_y) = (to.0 as isize, to.1 as isize);
+        let (dx, dy) = ((to_x - x).abs(), -(to_y - y).abs());
+        let (step_x, step_y) = ((to_x - x).signum(), (to_y - y).signum());
+        let mut error = dx + dy;
+
+        loop {
+            self.set_pixel_clipped(x as usize, y as usize, color);
+            if (x, y) == (to_x, to_y) {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    /// Copies `source` in with its top left corner at `(x, y)`.
+    pub fn blit(&mut self, source: &WindowBuffer, (x, y): (usize, usize)) {
+        let width = source.width.min(self.width.saturating_sub(x));
+        let height = source.height.min(self.height.saturating_sub(y));
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        for row in 0..height {
+            let from = row * source.width;
+            let to = (y + row) * self.width + x;
+            self.buffer[to..to + width].copy_from_slice(&source.buffer[from..from + width]);
+        }
+        self.mark_dirty((x, y), (x + width - 1, y + height - 1));
+    }
+
+    fn set_pixel_clipped(&mut self, x: usize, y: usize, color: u32) {
+        if x < self.width && y < self.height {
+            self.set_pixel(x, y, color);
+        }
+    }
+
+    /// The smallest rectangle holding every pixel changed since last time,
+    /// as its top left corner and size, so only that much has to be copied
+    /// on.
+    pub fn take_dirty(&mut self) -> Option<((usize, usize), (usize, usize))> {
+        self.dirty
+            .take()
+            .map(|(left, top, right, bottom)| ((left, top), (right - left + 1, bottom - top + 1)))
+    }
+
+    fn mark_dirty(&mut self, (left, top): (usize, usize), (right, bottom): (usize, usize)) {
+        self.dirty = Some(match self.dirty {
+            None => (left, top, right, bottom),
+            Some((l, t, r, b)) => (l.min(left), t.min(top), r.max(right), b.max(bottom)),
+        });
+    }
+
+    /// Draws this buffer into `target` (three times as wide and tall) as a 3x3 grid: full
+    /// brightness in the center, with dimmed copies around it to show how the world wraps.
+    pub fn draw_tiled(&self, target: &mut WindowBuffer) {
+        assert!(target.width == self.width * 3 && target.height == self.height * 3);
+
+        for tile_y in 0..3 {
+            for tile_x in 0..3 {
+                let center = tile_x == 1 && tile_y == 1;
+
+                for y in 0..self.height {
+                    for x in 0..self.width {
+                        let color = self.buffer[y * self.width + x];
+                        let color = if center { color } else { dim(color) };
+                        target.set_pixel(tile_x * self.width + x, tile_y * self.height + y, color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Halves the brightness of each color channel.
+fn dim(color: u32) -> u32 {
+    (color >> 1) & 0x7f7f7f
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dirty() {
+        let mut buffer = WindowBuffer::new(8, 6);
+        assert_eq!(buffer.take_dirty(), None);
+
+        buffer.set_pixel(5, 1, 0xff0000);
+        buffer.set_pixel(2, 3, 0xff0000);
+        assert_eq!(buffer.pixel(2, 3), 0xff0000);
+        assert_eq!(buffer.take_dirty(), Some(((2, 1), (4, 3))));
+        assert_eq!(buffer.take_dirty(), None);
+
+        buffer.clear();
+        assert_eq!(buffer.take_dirty(), Some(((0, 0), (8, 6))));
+    }
+
+    #[test]
+    fn test_fill_keeps_allocation() {
+        let mut buffer = WindowBuffer::new(4, 4);
+        let allocation = buffer.buffer.as_ptr();
+        buffer.fill(0x00ff00);
+        assert_eq!(buffer.buffer, vec![0x00ff00; 16]);
+        buffer.clear();
+        assert_eq!(buffer.buffer, vec![0; 16]);
+        assert_eq!(buffer.buffer.as_ptr(), allocation);
+    }
+
+    /// The pixels that aren't black, in reading order.
+    fn lit(buffer: &WindowBuffer) -> Vec<(usize, usize)> {
+        (0..buffer.buffer.len())
+            .filter(|i| buffer.buffer[*i] != 0)
+            .map(|i| (i % buffer.width(), i / buffer.width()))
+            .collect()
+    }
+
+    #[test]
+    fn test_draw_rect() {
+        let mut buffer = WindowBuffer::new(5, 4);
+        buffer.draw_rect((1, 1), (3, 3), 0xff0000);
+        assert_eq!(
+            lit(&buffer),
+            vec![
+                (1, 1),
+                (2, 1),
+                (3, 1),
+                (1, 2),
+                (3, 2),
+                (1, 3),
+                (2, 3),
+                (3, 3)
+            ]
+        );
+
+        // Hanging off the edge.
+        let mut buffer = WindowBuffer::new(3, 3);
+        buffer.draw_rect((1, 1), (5, 5), 0xff0000);
+        assert_eq!(lit(&buffer), vec![(1, 1), (2, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn test_draw_line() {
+        let mut buffer = WindowBuffer::new(5, 3);
+        buffer.draw_line((4, 2), (0, 0), 0xff0000);
+        assert_eq!(lit(&buffer), vec![(0, 0), (1, 0), (2, 1), (3, 1), (4, 2)]);
+        assert_eq!(buffer.take_dirty(), Some(((0, 0), (5, 3))));
+
+        let mut buffer = WindowBuffer::new(2, 2);
+        buffer.draw_line((1, 1), (1, 1), 0xff0000);
+        assert_eq!(lit(&buffer), vec![(1, 1)]);
+    }
+
+    #[test]
+    fn test_blit() {
+        let mut source = WindowBuffer::new(2, 2);
+        source.fill(0xff0000);
+
+        let mut buffer = WindowBuffer::new(3, 3);
+        buffer.blit(&source, (2, 1));
+        assert_eq!(lit(&buffer), vec![(2, 1), (2, 2)]);
+        assert_eq!(buffer.take_dirty(), Some(((2, 1), (1, 2))));
+
+        buffer.blit(&source, (3, 0));
+        assert_eq!(buffer.take_dirty(), None);
+    }
+
+    #[test]
+    fn test_paint() {
+        let mut buffer = WindowBuffer::new(6, 5);
+        buffer.set_pixel(2, 1, 0xff0000);
+        buffer.take_dirty();
+
+        // Past the right edge is left off.
+        buffer.paint(((1, 1), (8, 3)), |x, y| if x == y { 0xff0000 } else { 0 });
+        assert_eq!(lit(&buffer), vec![(1, 1), (2, 2), (3, 3)]);
+        // Only where the pixels changed.
+        assert_eq!(buffer.take_dirty(), Some(((1, 1), (3, 3))));
+
+        let color = |x: usize, y: usize| (x * 10 + y) as u32;
+        buffer.paint(((0, 0), (6, 5)), color);
+        assert_eq!(buffer.pixel(4, 3), 43);
+        buffer.take_dirty();
+        buffer.paint(((0, 0), (6, 5)), color);
+        assert_eq!(buffer.take_dirty(), None);
+    }
+
+    #[test]
+    fn test_draw_tiled() {
+        let mut buffer = WindowBuffer::new(2, 2);
+        buffer.set_pixel(1, 0, 0xff0000);
+
+        let mut tiled = WindowBuffer::new(6, 6);
+        buffer.draw_tiled(&mut tiled);
+
+        assert_eq!(tiled.buffer[2 * 6 + 3], 0xff0000);
+        for (x, y) in &[
+            (1, 0),
+            (3, 0),
+            (5, 0),
+            (1, 2),
+            (5, 2),
+            (1, 4),
+            (3, 4),
+            (5, 4),
+        ] {
+            assert_eq!(tiled.buffer[y * 6 + x], 0x7f0000);
+        }
+        assert_eq!(tiled.buffer.iter().filter(|color| **color != 0).count(), 9);
     }
 }
@@ -1,16 +1,53 @@
+use crate::bitpacked::BitWorld;
+use crate::boundary::Boundary;
+use crate::chance::Chance;
+use crate::formats::{native, Format, Pattern, SeedError};
+use crate::grid::{heap_bytes, Grid};
+use crate::region::Region;
+use crate::rule::{
+    CellRule, Family, Neighbourhood, NeighbourhoodShape, Rule, WIREWORLD_CONDUCTOR, WIREWORLD_HEAD,
+    WIREWORLD_TAIL,
+};
 use rand::{thread_rng, Rng};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::mem;
+use std::path::Path;
+use std::sync::Arc;
 
-#[rustfmt::skip]
-const OFFSETS: [(i8, i8); 8] = [
-    (-1, -1), (-1, 0), (-1, 1),
-    ( 0, -1),/* 0  0 */( 0, 1),
-    ( 1, -1), ( 1, 0), ( 1, 1),
-];
-
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Cell {
     pub alive: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
     live_neighbours_count: u8,
+    /// The state of a cell that isn't alive: 0 when it's dead, 2 and up for
+    /// the extra states of rules with more than two (see `Cell::state`).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) state: u8,
+    /// Which team a live cell is in, for rules with teams like Immigration.
+    #[cfg_attr(feature = "serde", serde(default))]
+    team: u8,
+    /// The generation a live cell was born in, wrapped to 32 bits, so ages
+    /// don't need updating every generation (see `World::age`).
+    #[cfg_attr(feature = "serde", serde(default))]
+    born: u32,
+}
+
+/// Cells are equal when they're in the same state, however old they are.
+impl PartialEq for Cell {
+    fn eq(&self, other: &Self) -> bool {
+        self.alive == other.alive
+            && self.live_neighbours_count == other.live_neighbours_count
+            && self.state == other.state
+            && self.team == other.team
+    }
 }
 
 impl Cell {
@@ -18,68 +55,751 @@ impl Cell {
         Cell {
             alive: false,
             live_neighbours_count: 0,
+            state: 0,
+            team: 0,
+            born: 0,
+        }
+    }
+
+    /// The cell's state: 0 is dead and 1 is alive. Rules with more states
+    /// use the rest, e.g. for dying cells under a Generations rule, or the
+    /// tails (2) and conductors (3) of Wireworld.
+    pub fn state(&self) -> u8 {
+        if self.alive {
+            1
+        } else {
+            self.state
+        }
+    }
+
+    /// The team a live cell is in, from 0 up to the rule's `teams`.
+    pub fn team(&self) -> u8 {
+        self.team
+    }
+}
+
+/// A cell was asked for outside the world.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutOfBounds {
+    pub position: (usize, usize),
+    pub size: (usize, usize),
+}
+
+impl fmt::Display for OutOfBounds {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{},{} is outside the {}x{} world",
+            self.position.0, self.position.1, self.size.0, self.size.1
+        )
+    }
+}
+
+impl std::error::Error for OutOfBounds {}
+
+/// A cell that's alive in one world and not in another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CellChange {
+    Birth(usize, usize),
+    Death(usize, usize),
+}
+
+/// A world's numbers as of its last generation, all kept up to date as
+/// cells change rather than counted when asked for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stats {
+    pub generation: usize,
+    pub population: usize,
+    /// The cells born and died since the last generation began, edits
+    /// included.
+    pub births: usize,
+    pub deaths: usize,
+    /// See `World::state_hash`.
+    pub state_hash: u64,
+}
+
+/// Which part of a world stays put when it's resized: the rows and columns
+/// on the other sides are the ones added or cropped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Centre,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// How far the cells move along each axis, as fractions of the change
+    /// in size: none, half or all of it.
+    fn shares(self) -> (isize, isize) {
+        match self {
+            Anchor::TopLeft => (0, 0),
+            Anchor::Top => (1, 0),
+            Anchor::TopRight => (2, 0),
+            Anchor::Left => (0, 1),
+            Anchor::Centre => (1, 1),
+            Anchor::Right => (2, 1),
+            Anchor::BottomLeft => (0, 2),
+            Anchor::Bottom => (1, 2),
+            Anchor::BottomRight => (2, 2),
         }
     }
 }
 
-#[derive(Clone, PartialEq)]
+/// Neighbour counts aren't serialized; they're rebuilt when deserializing.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "SerializedWorld")
+)]
 pub struct World {
-    pub cells: Vec<Vec<Cell>>,
+    /// Cells in reading order: the cell at `(x, y)` is at `y * width + x`.
+    pub(crate) cells: Vec<Cell>,
     pub width: usize,
     pub height: usize,
+    generation: usize,
+    /// The number of live cells, kept up to date as they're born and die.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    population: usize,
+    /// The XOR of `cell_key` for every cell, likewise.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    state_hash: u64,
+    /// The cells born and died since the last generation began.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    births: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    deaths: usize,
+    rule: Rule,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    chance: Option<Chance>,
+    /// Overrides `rule`'s births and survivals when set.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    cell_rule: Option<Arc<dyn CellRule>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    regions: Vec<Region>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    boundary: Boundary,
+    /// The changes a generation makes, decided on before any are made. Kept
+    /// between generations so it only allocates while the world's growing.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    changes: Vec<((usize, usize), Change)>,
+    /// The indices of the cells that have changed since the last generation
+    /// was decided. Under rules that only count neighbours, nothing outside
+    /// their neighbourhoods can turn out differently next time. `None` when
+    /// too much has changed to be worth tracking, or the rules have.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    touched: Option<Vec<usize>>,
+}
+
+/// Once more than one cell in this many has changed in a generation, it's
+/// quicker to decide every cell than just the changed ones' neighbourhoods.
+const TOUCHED_MAX_SHARE: usize = 16;
+
+/// How wide a world has to be before it's worth moving on with a packed
+/// copy: narrower ones spend more time setting up each row than they save.
+const BITS_MIN_WIDTH: usize = 256;
+
+/// How many rows of cells each thread decides at a time when simulating in
+/// parallel.
+#[cfg(feature = "parallel")]
+const PARALLEL_BAND_ROWS: usize = 16;
+
+/// A change to a cell that affects its neighbours' next states.
+#[derive(Clone, Copy, Debug)]
+enum Change {
+    /// Born, into a team for rules with them.
+    Birth(Option<u8>),
+    /// Died, leaving the cell in a state.
+    Death(u8),
+    State(u8),
+}
+
+/// Worlds are equal when their cells are, regardless of how many generations it took to get there.
+impl PartialEq for World {
+    fn eq(&self, other: &Self) -> bool {
+        self.width == other.width && self.height == other.height && self.cells == other.cells
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct SerializedWorld {
+    cells: Vec<Cell>,
+    width: usize,
+    height: usize,
+    generation: usize,
+    #[serde(default)]
+    rule: Rule,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<SerializedWorld> for World {
+    type Error = String;
+
+    fn try_from(serialized: SerializedWorld) -> Result<Self, Self::Error> {
+        if serialized.cells.len() != serialized.width * serialized.height {
+            return Err(format!(
+                "cells don't match a {}x{} world",
+                serialized.width, serialized.height
+            ));
+        }
+
+        let mut world = World {
+            cells: serialized.cells,
+            width: serialized.width,
+            height: serialized.height,
+            generation: serialized.generation,
+            population: 0,
+            state_hash: 0,
+            births: 0,
+            deaths: 0,
+            rule: serialized.rule,
+            chance: None,
+            cell_rule: None,
+            regions: Vec::new(),
+            boundary: Boundary::default(),
+            changes: Vec::new(),
+            touched: None,
+        };
+        world.recount_neighbours();
+        Ok(world)
+    }
 }
 
 impl World {
     pub fn new(width: usize, height: usize) -> Self {
         Self {
-            cells: vec![vec![Cell::new(); width]; height],
+            cells: vec![Cell::new(); width * height],
             width,
             height,
+            generation: 0,
+            population: 0,
+            state_hash: 0,
+            births: 0,
+            deaths: 0,
+            rule: Rule::default(),
+            chance: None,
+            cell_rule: None,
+            regions: Vec::new(),
+            boundary: Boundary::default(),
+            changes: Vec::new(),
+            touched: None,
         }
     }
 
-    pub fn seed_from_string(&mut self, seed: String) {
-        for (y, row) in seed.trim().split('\n').enumerate() {
-            for (x, cell) in row.trim().split(' ').enumerate() {
-                if cell == "#" {
-                    self.birth_cell(x, y);
+    /// The number of times `simulate` has been called.
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// The number of live cells.
+    pub fn population(&self) -> usize {
+        self.population
+    }
+
+    /// Roughly how many bytes the world takes: its cells, and the space it
+    /// keeps between generations.
+    pub fn memory_usage(&self) -> usize {
+        mem::size_of::<Self>()
+            + heap_bytes::<Cell>(self.cells.capacity())
+            + heap_bytes::<((usize, usize), Change)>(self.changes.capacity())
+            + self
+                .touched
+                .as_ref()
+                .map_or(0, |touched| heap_bytes::<usize>(touched.capacity()))
+            + heap_bytes::<Region>(self.regions.capacity())
+    }
+
+    /// A hash of every cell's state, and the teams of live ones, the same
+    /// for the same cells on every run, to spot a world repeating itself.
+    /// It's kept up to date as cells change, so costs nothing to ask for.
+    pub fn state_hash(&self) -> u64 {
+        self.state_hash
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats {
+            generation: self.generation,
+            population: self.population,
+            births: self.births,
+            deaths: self.deaths,
+            state_hash: self.state_hash,
+        }
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.cell_rule = None;
+        let neighbours_changed = rule.radius() != self.rule.radius()
+            || rule.neighbourhood() != self.rule.neighbourhood();
+        self.rule = rule;
+        self.touched = None;
+
+        if neighbours_changed {
+            self.regions.clear();
+            self.recount_neighbours();
+        }
+    }
+
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    pub fn set_boundary(&mut self, boundary: Boundary) {
+        self.boundary = boundary;
+        self.recount_neighbours();
+    }
+
+    /// Runs a different rule inside a rectangle of the world, over the rule
+    /// of the world and any earlier regions. Only Life-like rules counting
+    /// the same neighbours as the world's rule can share it, and changing
+    /// the world's rule to count different neighbours removes every region.
+    pub fn add_region(&mut self, region: Region) -> Result<(), String> {
+        if region.rule.family() != Family::LifeLike || self.rule.family() != Family::LifeLike {
+            return Err(format!("{} can't run in a region", region.rule));
+        }
+        if region.rule.radius() != self.rule.radius()
+            || region.rule.neighbourhood() != self.rule.neighbourhood()
+        {
+            return Err(format!(
+                "{} counts different neighbours to {}",
+                region.rule, self.rule
+            ));
+        }
+
+        if region.width == 0
+            || region.height == 0
+            || region.x + region.width > self.width
+            || region.y + region.height > self.height
+        {
+            return Err(format!(
+                "region doesn't fit in a {}x{} world",
+                self.width, self.height
+            ));
+        }
+
+        self.regions.push(region);
+        self.touched = None;
+        Ok(())
+    }
+
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    /// The rule a cell runs: its region's, or the world's outside them.
+    pub fn rule_at(&self, x: usize, y: usize) -> Rule {
+        rule_at(&self.regions, self.rule, x, y)
+    }
+
+    /// Runs a rule of your own, counting the Moore neighbourhood, until the
+    /// next `set_rule`.
+    pub fn set_cell_rule(&mut self, cell_rule: impl CellRule + 'static) {
+        self.set_rule(Rule::conway());
+        self.cell_rule = Some(Arc::new(cell_rule));
+    }
+
+    /// Makes births and survivals only happen some of the time.
+    pub fn set_chance(&mut self, chance: Chance) {
+        self.chance = Some(chance);
+    }
+
+    pub fn chance(&self) -> Option<&Chance> {
+        self.chance.as_ref()
+    }
+
+    pub fn chance_mut(&mut self) -> Option<&mut Chance> {
+        self.chance.as_mut()
+    }
+
+    /// Moves the generation counter, keeping every cell's age.
+    pub(crate) fn set_generation(&mut self, generation: usize) {
+        let shift = generation.wrapping_sub(self.generation) as u32;
+        for cell in &mut self.cells {
+            cell.born = cell.born.wrapping_add(shift);
+        }
+        self.generation = generation;
+    }
+
+    /// Seeds the world from the native `#`/`-` grid with its top-left corner at `origin`.
+    pub fn seed_from_string(&mut self, seed: String, origin: (usize, usize)) {
+        self.seed_pattern_at(&native::parse(&seed), origin.0, origin.1)
+            .unwrap_or_else(|e| panic!("{}", e));
+    }
+
+    /// Seeds the world from a Run Length Encoded pattern, as used by Golly and LifeWiki.
+    pub fn seed_from_rle(&mut self, rle: &str) -> Result<(), SeedError> {
+        self.seed_pattern(&Format::Rle.parse(rle)?)
+    }
+
+    /// Encodes the world as a Run Length Encoded pattern.
+    pub fn to_rle(&self) -> String {
+        Format::Rle.write(&self.to_pattern())
+    }
+
+    /// Seeds the world from a file in any format `Format::detect` recognises.
+    pub fn seed_from_file_auto(&mut self, path: &Path) -> Result<(), SeedError> {
+        self.seed_from_reader(File::open(path)?)
+    }
+
+    /// Seeds the world from a reader (e.g. stdin) in any format `Format::detect` recognises.
+    pub fn seed_from_reader(&mut self, reader: impl Read) -> Result<(), SeedError> {
+        self.seed_pattern(&Pattern::from_reader(reader)?)
+    }
+
+    pub fn seed_pattern(&mut self, pattern: &Pattern) -> Result<(), SeedError> {
+        self.seed_pattern_at(pattern, 0, 0)
+    }
+
+    /// Births the pattern's cells with its top-left corner at `(x, y)`, leaving the world
+    /// untouched if any of them would fall outside it.
+    pub fn seed_pattern_at(
+        &mut self,
+        pattern: &Pattern,
+        x: usize,
+        y: usize,
+    ) -> Result<(), SeedError> {
+        if pattern
+            .cells
+            .iter()
+            .any(|(cell_x, cell_y)| x + cell_x >= self.width || y + cell_y >= self.height)
+        {
+            return Err(SeedError::TooLarge {
+                pattern_size: (x + pattern.width, y + pattern.height),
+                world_size: (self.width, self.height),
+            });
+        }
+
+        for (cell_x, cell_y) in &pattern.cells {
+            if !self.cell(x + cell_x, y + cell_y).alive {
+                self.birth_cell(x + cell_x, y + cell_y);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn to_pattern(&self) -> Pattern {
+        Pattern {
+            width: self.width,
+            height: self.height,
+            cells: self.live_cells().collect(),
+            ..Pattern::default()
+        }
+    }
+
+    /// The cells born and the cells that died going from this world to
+    /// `other`, in reading order. Cells outside either world count as dead.
+    pub fn diff(&self, other: &World) -> Vec<CellChange> {
+        let alive = |world: &World, x, y| world.get(x, y).is_some_and(|cell| cell.alive);
+        let mut changes = Vec::new();
+
+        for y in 0..self.height.max(other.height) {
+            for x in 0..self.width.max(other.width) {
+                match (alive(self, x, y), alive(other, x, y)) {
+                    (false, true) => changes.push(CellChange::Birth(x, y)),
+                    (true, false) => changes.push(CellChange::Death(x, y)),
+                    _ => {}
                 }
             }
         }
+        changes
+    }
+
+    /// The cells that look different in `other`, which has to be the same
+    /// size: born, died, or in another state or team. Unlike `diff`, it's
+    /// for redrawing just those cells.
+    pub fn changed_cells(&self, other: &World) -> Vec<(usize, usize)> {
+        assert!(self.width == other.width && self.height == other.height);
+
+        self.cells
+            .iter()
+            .zip(&other.cells)
+            .enumerate()
+            .filter(|(_, (a, b))| {
+                a.alive != b.alive || a.state() != b.state() || a.team() != b.team()
+            })
+            .map(|(i, _)| (i % self.width, i / self.width))
+            .collect()
+    }
+
+    /// The smallest rectangle holding every live cell, as its top left
+    /// corner and size, or `None` if there aren't any. Only the rows between
+    /// the top and bottom live cells are scanned for the sides.
+    pub fn bounding_box(&self) -> Option<((usize, usize), (usize, usize))> {
+        if self.population == 0 {
+            return None;
+        }
+
+        let has_live_cells = |y: &usize| self.row(*y).iter().any(|cell| cell.alive);
+        let top = (0..self.height).find(has_live_cells)?;
+        let bottom = (top..self.height).rev().find(has_live_cells)?;
+        let left = (top..=bottom)
+            .filter_map(|y| self.row(y).iter().position(|cell| cell.alive))
+            .min()?;
+        let right = (top..=bottom)
+            .filter_map(|y| self.row(y).iter().rposition(|cell| cell.alive))
+            .max()?;
+
+        Some(((left, top), (right - left + 1, bottom - top + 1)))
+    }
+
+    /// The live cells inside a rectangle, relative to its top left corner.
+    /// The rectangle is clipped to the world.
+    pub fn region_pattern(&self, x: usize, y: usize, width: usize, height: usize) -> Pattern {
+        let width = width.min(self.width.saturating_sub(x));
+        let height = height.min(self.height.saturating_sub(y));
+
+        Pattern {
+            width,
+            height,
+            cells: self
+                .live_cells()
+                .filter(|&(cx, cy)| cx >= x && cx < x + width && cy >= y && cy < y + height)
+                .map(|(cx, cy)| (cx - x, cy - y))
+                .collect(),
+            ..Pattern::default()
+        }
     }
 
     pub fn seed_random(&mut self) {
         let mut rng = thread_rng();
 
-        for y in 0..(self.height - 1) {
-            for x in 0..(self.width - 1) {
-                if rng.gen_bool(0.5) {
-                    self.birth_cell(x as usize, y as usize);
+        if self.rule.family() == Family::Cyclic {
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    self.set_cell_state(x, y, rng.gen_range(0, self.rule.states()));
+                }
+            }
+            return;
+        }
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if rng.gen() {
+                    self.set_cell_team(x, y, rng.gen_range(0, self.rule.teams()));
                 }
             }
         }
     }
 
-    fn cell(&self, x: usize, y: usize) -> &Cell {
-        &self.cells[y][x]
+    /// The coordinates of the live cells, in reading order.
+    pub fn live_cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.cells_with_coords()
+            .filter(|(_, cell)| cell.alive)
+            .map(|(position, _)| position)
+    }
+
+    /// Every cell with its coordinates, in reading order.
+    pub fn cells_with_coords(&self) -> impl Iterator<Item = ((usize, usize), &Cell)> + '_ {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| ((i % self.width, i / self.width), cell))
+    }
+
+    /// Live cells as a JSON array of `[x, y]` pairs, e.g. `[[1,0],[2,1]]`.
+    pub fn to_coords_json(&self) -> String {
+        let pairs: Vec<String> = self
+            .live_cells()
+            .map(|(x, y)| format!("[{},{}]", x, y))
+            .collect();
+
+        format!("[{}]", pairs.join(","))
+    }
+
+    /// Builds a world from the output of `to_coords_json`.
+    pub fn from_coords_json(width: usize, height: usize, json: &str) -> Result<Self, String> {
+        let mut world = World::new(width, height);
+        let json: String = json.chars().filter(|c| !c.is_whitespace()).collect();
+
+        if !json.starts_with('[') || !json.ends_with(']') || json.len() < 2 {
+            return Err("expected a JSON array".to_string());
+        }
+
+        let inner = &json[1..json.len() - 1];
+        if inner.is_empty() {
+            return Ok(world);
+        }
+        if !inner.starts_with('[') || !inner.ends_with(']') {
+            return Err("expected an array of [x, y] pairs".to_string());
+        }
+
+        for pair in inner[1..inner.len() - 1].split("],[") {
+            let coords: Vec<&str> = pair.split(',').collect();
+            if coords.len() != 2 {
+                return Err(format!("invalid pair: [{}]", pair));
+            }
+
+            let x: usize = coords[0]
+                .parse()
+                .map_err(|_| format!("invalid x coordinate: {}", coords[0]))?;
+            let y: usize = coords[1]
+                .parse()
+                .map_err(|_| format!("invalid y coordinate: {}", coords[1]))?;
+
+            if x >= width || y >= height {
+                return Err(format!("cell out of bounds: [{},{}]", x, y));
+            }
+            if !world.cell(x, y).alive {
+                world.birth_cell(x, y);
+            }
+        }
+
+        Ok(world)
+    }
+
+    /// Returns the translation `(dx, dy)` that maps this world's live cells onto `other`'s,
+    /// treating both worlds as a torus, or `None` if no such translation exists.
+    pub fn equals_shifted(&self, other: &World) -> Option<(isize, isize)> {
+        if self.width != other.width || self.height != other.height {
+            return None;
+        }
+
+        let cells: Vec<(usize, usize)> = self.live_cells().collect();
+        let other_cells: HashSet<(usize, usize)> = other.live_cells().collect();
+
+        if cells.len() != other_cells.len() {
+            return None;
+        }
+
+        let (first_x, first_y) = match cells.first() {
+            Some(cell) => *cell,
+            None => return Some((0, 0)),
+        };
+
+        for (other_x, other_y) in &other_cells {
+            let dx = (other_x + self.width - first_x) % self.width;
+            let dy = (other_y + self.height - first_y) % self.height;
+
+            let matches = cells.iter().all(|(x, y)| {
+                other_cells.contains(&((x + dx) % self.width, (y + dy) % self.height))
+            });
+
+            if matches {
+                return Some((
+                    shortest_offset(dx, self.width),
+                    shortest_offset(dy, self.height),
+                ));
+            }
+        }
+
+        None
+    }
+
+    /// The cell at `(x, y)`, or `None` if that's outside the world.
+    pub fn get(&self, x: usize, y: usize) -> Option<&Cell> {
+        (x < self.width && y < self.height).then(|| self.cell(x, y))
+    }
+
+    /// How many generations in a row the cell at `(x, y)` has survived: 0
+    /// when it's just been born, and 0 again once it's died. `None` if
+    /// that's outside the world.
+    pub fn age(&self, x: usize, y: usize) -> Option<u32> {
+        self.get(x, y).map(|cell| {
+            if cell.alive {
+                (self.generation as u32).wrapping_sub(cell.born)
+            } else {
+                0
+            }
+        })
+    }
+
+    /// Brings a cell to life or kills it, keeping its neighbours' counts up
+    /// to date.
+    pub fn set_alive(&mut self, x: usize, y: usize, alive: bool) -> Result<(), OutOfBounds> {
+        let cell = self.get(x, y).ok_or(OutOfBounds {
+            position: (x, y),
+            size: (self.width, self.height),
+        })?;
+
+        match (cell.alive, alive) {
+            (false, true) => self.birth_cell(x, y),
+            (true, false) => self.kill_cell(x, y),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub(crate) fn cell(&self, x: usize, y: usize) -> &Cell {
+        &self.cells[y * self.width + x]
+    }
+
+    /// Replaces a cell, keeping its neighbours' counts up to date.
+    pub fn set(&mut self, x: usize, y: usize, cell: Cell) {
+        match (self.cell(x, y).alive, cell.alive) {
+            (false, true) => self.birth_cell(x, y),
+            (true, false) => self.kill_cell(x, y),
+            _ => {}
+        }
+
+        self.update_cell(x, y, |target| {
+            target.state = cell.state;
+            target.team = cell.team;
+            target.born = cell.born;
+        });
+        self.touch(x, y);
+    }
+
+    /// The cells of row `y`, from west to east.
+    pub fn row(&self, y: usize) -> &[Cell] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    fn cell_mut(&mut self, x: usize, y: usize) -> &mut Cell {
+        &mut self.cells[y * self.width + x]
+    }
+
+    /// Changes a cell with `f`, keeping the state hash up to date.
+    fn update_cell(&mut self, x: usize, y: usize, f: impl FnOnce(&mut Cell)) {
+        let cell = &mut self.cells[y * self.width + x];
+        let before = cell_key(x, y, cell);
+        f(cell);
+        self.state_hash ^= before ^ cell_key(x, y, cell);
     }
 
     fn birth_cell(&mut self, x: usize, y: usize) {
-        self.cells[y][x].alive = true;
+        let born = self.generation as u32;
+        self.update_cell(x, y, |cell| {
+            cell.alive = true;
+            cell.state = 0;
+            cell.born = born;
+        });
+        self.population += 1;
+        self.births += 1;
+        self.touch(x, y);
 
-        self.for_each_neighbour(x, y, |world, x, y| {
-            world.cells[y][x].live_neighbours_count += 1
+        self.for_each_dependent(x, y, |world, x, y| {
+            world.cell_mut(x, y).live_neighbours_count += 1
         });
     }
 
     fn kill_cell(&mut self, x: usize, y: usize) {
-        self.cells[y][x].alive = false;
+        self.update_cell(x, y, |cell| cell.alive = false);
+        self.population -= 1;
+        self.deaths += 1;
+        self.touch(x, y);
 
-        self.for_each_neighbour(x, y, |world, x, y| {
-            world.cells[y][x].live_neighbours_count -= 1
+        self.for_each_dependent(x, y, |world, x, y| {
+            world.cell_mut(x, y).live_neighbours_count -= 1
         });
     }
 
+    fn touch(&mut self, x: usize, y: usize) {
+        let limit = self.cells.len() / TOUCHED_MAX_SHARE;
+        touch(&mut self.touched, y * self.width + x, limit);
+    }
+
     pub fn toggle_cell(&mut self, x: usize, y: usize) {
         if self.cell(x, y).alive {
             self.kill_cell(x, y);
@@ -88,94 +808,1998 @@ impl World {
         }
     }
 
-    fn for_each_neighbour<F: Fn(&mut World, usize, usize)>(&mut self, x: usize, y: usize, f: F) {
-        for (x_offset, y_offset) in &OFFSETS {
-            let x = add_offset(x, *x_offset);
-            let y = add_offset(y, *y_offset);
-
-            if x < self.width && y < self.height {
-                f(self, x, y);
+    /// Sets a cell to any of the rule's states, e.g. to paint Wireworld
+    /// conductors.
+    pub fn set_cell_state(&mut self, x: usize, y: usize, state: u8) {
+        match (self.cell(x, y).alive, state) {
+            (false, 1) => self.birth_cell(x, y),
+            (true, 1) => {}
+            (true, _) => {
+                self.kill_cell(x, y);
+                self.update_cell(x, y, |cell| cell.state = state);
+            }
+            (false, _) => {
+                self.update_cell(x, y, |cell| cell.state = state);
+                self.touch(x, y);
             }
         }
     }
 
-    pub fn simulate(&mut self) {
-        let old_world = self.clone();
+    /// Brings a cell to life in a team, or moves it to that team if it's
+    /// already alive.
+    pub fn set_cell_team(&mut self, x: usize, y: usize, team: u8) {
+        if !self.cell(x, y).alive {
+            self.birth_cell(x, y);
+        }
+        self.update_cell(x, y, |cell| cell.team = team);
+    }
 
-        for y in 0..(self.height - 1) {
-            for x in 0..(self.width - 1) {
-                let cell = old_world.cell(x, y);
+    /// Grows or crops the world to a new size, keeping the cells that still
+    /// fit where the anchor says. Regions move with the cells, and any that
+    /// no longer fit are removed.
+    pub fn resize(&mut self, width: usize, height: usize, anchor: Anchor) {
+        let (x_share, y_share) = anchor.shares();
+        let dx = (width as isize - self.width as isize) * x_share / 2;
+        let dy = (height as isize - self.height as isize) * y_share / 2;
 
-                if cell.alive && (cell.live_neighbours_count < 2 || cell.live_neighbours_count > 3)
-                {
-                    self.kill_cell(x as usize, y as usize);
-                } else if !cell.alive && cell.live_neighbours_count == 3 {
-                    self.birth_cell(x as usize, y as usize);
+        let mut cells = vec![Cell::new(); width * height];
+        for y in 0..height {
+            let old_y = y as isize - dy;
+            if !(0..self.height as isize).contains(&old_y) {
+                continue;
+            }
+            for x in 0..width {
+                let old_x = x as isize - dx;
+                if (0..self.width as isize).contains(&old_x) {
+                    cells[y * width + x] = self.cell(old_x as usize, old_y as usize).clone();
                 }
             }
         }
+
+        self.regions = self
+            .regions
+            .iter()
+            .filter_map(|region| {
+                let x = region.x as isize + dx;
+                let y = region.y as isize + dy;
+                (x >= 0
+                    && y >= 0
+                    && x as usize + region.width <= width
+                    && y as usize + region.height <= height)
+                    .then_some(Region {
+                        x: x as usize,
+                        y: y as usize,
+                        ..*region
+                    })
+            })
+            .collect();
+
+        self.cells = cells;
+        self.width = width;
+        self.height = height;
+        self.recount_neighbours();
     }
-}
 
-fn add_offset(n: usize, offset: i8) -> usize {
-    ((n as isize).saturating_add(isize::from(offset))) as usize
-}
+    /// Flips every cell between alive and dead.
+    pub fn invert(&mut self) {
+        for cell in &mut self.cells {
+            cell.alive = !cell.alive;
+            cell.state = 0;
+            cell.born = self.generation as u32;
+        }
 
-impl std::fmt::Debug for World {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-        writeln!(f)?;
-        for row in &self.cells {
-            for cell in row {
-                if cell.alive {
-                    write!(f, "# ")?;
-                } else {
-                    write!(f, "- ")?;
+        self.recount_neighbours();
+    }
+
+    /// Rebuilds every cell's `live_neighbours_count`, the population and the
+    /// state hash from scratch. Square neighbourhoods use a summed-area table, so cost
+    /// the same whatever the rule's radius.
+    pub(crate) fn recount_neighbours(&mut self) {
+        self.touched = None;
+        self.population = self.cells.iter().filter(|cell| cell.alive).count();
+        self.state_hash = self
+            .cells_with_coords()
+            .fold(0, |hash, ((x, y), cell)| hash ^ cell_key(x, y, cell));
+
+        if self.rule.neighbourhood() != Neighbourhood::Moore {
+            for cell in &mut self.cells {
+                cell.live_neighbours_count = 0;
+            }
+
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    if self.cell(x, y).alive {
+                        self.for_each_dependent(x, y, |world, x, y| {
+                            world.cell_mut(x, y).live_neighbours_count += 1
+                        });
+                    }
                 }
             }
-            writeln!(f)?;
+            return;
         }
 
-        Ok(())
-    }
-}
+        // The table covers the world and the radius past each edge, where
+        // the boundary decides which cells are there.
+        let radius = usize::from(self.rule.radius());
+        let (width, height) = (self.width + 2 * radius, self.height + 2 * radius);
+        let mut sums = vec![0u32; (width + 1) * (height + 1)];
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        for y in 0..height {
+            for x in 0..width {
+                let position = (x as isize - radius as isize, y as isize - radius as isize);
+                let alive = if inside(position, (self.width, self.height)) {
+                    self.cell(position.0 as usize, position.1 as usize).alive
+                } else {
+                    self.boundary
+                        .resolve(position, (self.width, self.height))
+                        .is_some_and(|(x, y)| self.cell(x, y).alive)
+                };
 
-    #[test]
-    fn test_live_neighbours_count() {
-        let mut world = World::new(3, 3);
+                sums[(y + 1) * (width + 1) + x + 1] = u32::from(alive)
+                    + sums[y * (width + 1) + x + 1]
+                    + sums[(y + 1) * (width + 1) + x]
+                    - sums[y * (width + 1) + x];
+            }
+        }
 
-        assert_eq!(world.cell(1, 1).live_neighbours_count, 0);
+        // Cell (x, y) sits at (x + radius, y + radius) in the table, so the
+        // square around it starts at (x, y).
+        let size = 2 * radius + 1;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (top, bottom, left, right) = (y, y + size, x, x + size);
+                let total = sums[bottom * (width + 1) + right] + sums[top * (width + 1) + left]
+                    - sums[top * (width + 1) + right]
+                    - sums[bottom * (width + 1) + left];
 
-        let mut i = 0;
-        for (x_offset, y_offset) in &OFFSETS {
-            let x = 1 + *x_offset;
-            let y = 1 + *y_offset;
+                let cell = self.cell_mut(x, y);
+                cell.live_neighbours_count = (total - u32::from(cell.alive)) as u8;
+            }
+        }
+    }
 
-            i += 1;
-            world.birth_cell(x as usize, y as usize);
-            assert_eq!(world.cell(1, 1).live_neighbours_count, i);
+    /// Calls `f` with each cell that has the cell at `(x, y)` among its
+    /// neighbours, once for each time it's there, to pass a change on to
+    /// them.
+    fn for_each_dependent<F: Fn(&mut World, usize, usize)>(&mut self, x: usize, y: usize, f: F) {
+        let (rule, size) = (self.rule, (self.width, self.height));
+        if neighbours_symmetric(rule.neighbourhood(), self.boundary, size) {
+            for (x, y) in neighbours(&rule, self.boundary, size, x, y) {
+                f(self, x, y);
+            }
+        } else {
+            for (x, y) in dependents(&rule, self.boundary, size, x, y) {
+                f(self, x, y);
+            }
         }
     }
 
-    #[test]
-    fn test_block() {
-        let mut world = World::new(4, 4);
+    /// How many of a cell's neighbours in any shape are alive.
+    pub fn count_neighbours<N: NeighbourhoodShape>(&self, shape: &N, x: usize, y: usize) -> u8 {
+        neighbours(shape, self.boundary, (self.width, self.height), x, y)
+            .filter(|(x, y)| self.cell(*x, *y).alive)
+            .count() as u8
+    }
 
-        world.seed_from_string(
-            "- - - -
-             - # # -
-             - # # -
-             - - - -"
-                .to_string(),
-        );
+    pub fn simulate(&mut self) {
+        self.births = 0;
+        self.deaths = 0;
+        self.add_noise();
+        let touched = self.touched.replace(Vec::new());
 
-        let old_world = world.clone();
+        if self.rule.family() == Family::Wireworld {
+            self.simulate_wireworld();
+            return;
+        }
+        if self.rule.family() == Family::Cyclic {
+            self.simulate_cyclic();
+            return;
+        }
 
-        world.simulate();
+        if self.rule.radius() > 1 {
+            self.simulate_in_bulk();
+            return;
+        }
 
-        assert_eq!(old_world, world);
+        if let Some(touched) = touched.filter(|_| self.follows_changes()) {
+            let mut changes = mem::take(&mut self.changes);
+            self.decide_touched(touched, &mut changes);
+            self.generation += 1;
+            self.apply(changes);
+            return;
+        }
+
+        if self.width >= BITS_MIN_WIDTH
+            && self.decides_by_counts()
+            && self.chance.is_none()
+            && self.regions.is_empty()
+        {
+            if let Ok(packed) = BitWorld::from_world(self) {
+                self.simulate_in_bits(packed);
+                return;
+            }
+        }
+
+        // Decaying states don't change what happens to the neighbours, so
+        // they're updated as we go; births and deaths wait
+        // until every cell's been decided.
+        let mut changes = mem::take(&mut self.changes);
+        if self.decides_by_counts() {
+            self.decide_by_counts(&mut changes);
+            self.generation += 1;
+            self.apply(changes);
+            return;
+        }
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let Cell { alive, state, .. } = *self.cell(x, y);
+                let rule = self.rule_at(x, y);
+
+                if alive {
+                    if !(self.survives(x, y) && self.chance_survives()) {
+                        let state = if rule.states() > 2 { 2 } else { 0 };
+                        changes.push(((x, y), Change::Death(state)));
+                    }
+                } else if state > 1 {
+                    let state = next_decay(state, rule.states());
+                    self.update_cell(x, y, |cell| cell.state = state);
+                } else if self.births(x, y) && self.chance_births() {
+                    let team = (self.rule.teams() > 1).then(|| self.birth_team(x, y));
+                    changes.push(((x, y), Change::Birth(team)));
+                }
+            }
+        }
+        self.generation += 1;
+        self.apply(changes);
+    }
+
+    /// Whether every cell's fate depends only on its own state and its
+    /// count of live neighbours.
+    fn decides_by_counts(&self) -> bool {
+        self.cell_rule.is_none()
+            && self.rule.teams() == 1
+            && !self.rule.is_isotropic()
+            && !self.regions.iter().any(|region| region.rule.is_isotropic())
+    }
+
+    /// Whether a cell can only turn out differently to last generation if it
+    /// or a neighbour has changed since: under two-state rules that only
+    /// count neighbours, without chance.
+    fn follows_changes(&self) -> bool {
+        self.decides_by_counts()
+            && self.chance.is_none()
+            && self.regions.is_empty()
+            && self.rule.states() == 2
+    }
+
+    /// Decides the fates of only the cells that have changed since last
+    /// generation and their neighbours, so parts of the world that have
+    /// settled down cost nothing.
+    fn decide_touched(&mut self, touched: Vec<usize>, changes: &mut Vec<((usize, usize), Change)>) {
+        let (width, size) = (self.width, (self.width, self.height));
+        let symmetric = neighbours_symmetric(self.rule.neighbourhood(), self.boundary, size);
+        let mut indices = Vec::with_capacity(touched.len() * 9);
+        for i in touched {
+            let (x, y) = (i % width, i / width);
+            indices.push(i);
+            if symmetric {
+                let around = neighbours(&self.rule, self.boundary, size, x, y);
+                indices.extend(around.map(|(x, y)| y * width + x));
+            } else {
+                let around = dependents(&self.rule, self.boundary, size, x, y);
+                indices.extend(around.into_iter().map(|(x, y)| y * width + x));
+            }
+        }
+        indices.sort_unstable();
+        indices.dedup();
+
+        for i in indices {
+            let position = (i % width, i / width);
+            let cell = &mut self.cells[i];
+            if let Some(change) = decide_by_count(
+                cell,
+                &self.rule,
+                || position,
+                &mut self.state_hash,
+                |_| true,
+            ) {
+                changes.push((position, change));
+            }
+        }
+    }
+
+    /// Moves the cells on with a packed copy of the world, which counts
+    /// neighbours for sixty-four cells at a time, then copies its cells
+    /// back along with their new neighbour counts. Only for two-state rules
+    /// that `BitWorld` can run.
+    fn simulate_in_bits(&mut self, packed: BitWorld) {
+        let mut next = packed.clone();
+        Grid::simulate(&mut next);
+        self.generation += 1;
+
+        let (width, born) = (self.width, self.generation as u32);
+        let limit = self.cells.len() / TOUCHED_MAX_SHARE;
+        for y in 0..self.height {
+            let row = &mut self.cells[y * width..(y + 1) * width];
+            let (state_hash, touched) = (&mut self.state_hash, &mut self.touched);
+            let (births, deaths) = (&mut self.births, &mut self.deaths);
+            packed.compare_row(&next, y, |x, alive, count| {
+                let cell = &mut row[x];
+                if alive != cell.alive {
+                    let before = cell_key(x, y, cell);
+                    cell.alive = alive;
+                    cell.born = born;
+                    if alive {
+                        *births += 1;
+                    } else {
+                        *deaths += 1;
+                    }
+                    *state_hash ^= before ^ cell_key(x, y, cell);
+                    touch(touched, y * width + x, limit);
+                }
+                cell.live_neighbours_count = count;
+            });
+        }
+        self.population = next.live_cells_count();
+    }
+
+    /// Decides each cell's fate from its count alone, walking the cells in
+    /// place, which is most of the work for most rules.
+    fn decide_by_counts(&mut self, changes: &mut Vec<((usize, usize), Change)>) {
+        #[cfg(feature = "parallel")]
+        {
+            if self.chance.is_none() {
+                self.decide_by_counts_in_bands(changes);
+                return;
+            }
+        }
+
+        let mut chance = self.chance.take();
+        let mut happens =
+            |chance_happens: fn(&mut Chance) -> bool| chance.as_mut().is_none_or(chance_happens);
+
+        // Dividing to find each cell's coordinates would take as long as
+        // deciding its fate, so it's only done when they're needed.
+        let width = self.width;
+        let position = |i: usize| (i % width, i / width);
+        let (world_rule, regions) = (&self.rule, &self.regions);
+        let state_hash = &mut self.state_hash;
+        for (i, cell) in self.cells.iter_mut().enumerate() {
+            let rule = rule_by_index(world_rule, regions, || position(i));
+            let cell_position = || position(i);
+            if let Some(change) =
+                decide_by_count(cell, rule, cell_position, state_hash, &mut happens)
+            {
+                changes.push((position(i), change));
+            }
+        }
+        self.chance = chance;
+    }
+
+    /// `decide_by_counts` for worlds without chance, with bands of rows
+    /// decided on separate threads. A band only writes its own cells'
+    /// decaying states; births and deaths change the neighbour counts
+    /// of the rows either side of it, so they're collected and applied
+    /// once every band's done.
+    #[cfg(feature = "parallel")]
+    fn decide_by_counts_in_bands(&mut self, changes: &mut Vec<((usize, usize), Change)>) {
+        let width = self.width;
+        let position = |i: usize| (i % width, i / width);
+        let (world_rule, regions) = (&self.rule, &self.regions);
+        let band = width * PARALLEL_BAND_ROWS;
+
+        // Each band's changes, and what its decaying cells do to the state
+        // hash.
+        let bands: Vec<(Vec<_>, u64)> = self
+            .cells
+            .par_chunks_mut(band.max(1))
+            .enumerate()
+            .map(|(b, cells)| {
+                let (mut changes, mut state_hash) = (Vec::new(), 0);
+                for (i, cell) in cells.iter_mut().enumerate() {
+                    let i = b * band + i;
+                    let rule = rule_by_index(world_rule, regions, || position(i));
+                    let cell_position = || position(i);
+                    if let Some(change) =
+                        decide_by_count(cell, rule, cell_position, &mut state_hash, |_| true)
+                    {
+                        changes.push((position(i), change));
+                    }
+                }
+                (changes, state_hash)
+            })
+            .collect();
+
+        for (band, state_hash) in bands {
+            changes.extend(band);
+            self.state_hash ^= state_hash;
+        }
+    }
+
+    /// Makes the changes a generation decided on, keeping the list for next
+    /// time.
+    fn apply(&mut self, mut changes: Vec<((usize, usize), Change)>) {
+        for ((x, y), change) in changes.drain(..) {
+            match change {
+                Change::Birth(team) => {
+                    self.birth_cell(x, y);
+                    if let Some(team) = team {
+                        self.update_cell(x, y, |cell| cell.team = team);
+                    }
+                }
+                Change::Death(state) => {
+                    self.kill_cell(x, y);
+                    self.update_cell(x, y, |cell| cell.state = state);
+                }
+                Change::State(state) => self.set_cell_state(x, y, state),
+            }
+        }
+        self.changes = changes;
+    }
+
+    /// Flips the chance's noise fraction of cells, keeping neighbour counts
+    /// up to date.
+    fn add_noise(&mut self) {
+        let mut chance = match self.chance.take() {
+            Some(chance) if chance.has_noise() => chance,
+            chance => {
+                self.chance = chance;
+                return;
+            }
+        };
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if chance.flips() {
+                    self.toggle_cell(x, y);
+                }
+            }
+        }
+        self.chance = Some(chance);
+    }
+
+    fn chance_births(&mut self) -> bool {
+        self.chance.as_mut().is_none_or(Chance::births)
+    }
+
+    fn chance_survives(&mut self) -> bool {
+        self.chance.as_mut().is_none_or(Chance::survives)
+    }
+
+    fn births(&self, x: usize, y: usize) -> bool {
+        if let Some(cell_rule) = &self.cell_rule {
+            cell_rule.next(false, self.cell(x, y).live_neighbours_count)
+        } else if self.rule_at(x, y).is_isotropic() {
+            self.rule_at(x, y)
+                .births_configuration(self.configuration(x, y))
+        } else {
+            self.rule_at(x, y)
+                .births(self.cell(x, y).live_neighbours_count)
+        }
+    }
+
+    fn survives(&self, x: usize, y: usize) -> bool {
+        if let Some(cell_rule) = &self.cell_rule {
+            cell_rule.next(true, self.cell(x, y).live_neighbours_count)
+        } else if self.rule_at(x, y).is_isotropic() {
+            self.rule_at(x, y)
+                .survives_configuration(self.configuration(x, y))
+        } else {
+            self.rule_at(x, y)
+                .survives(self.cell(x, y).live_neighbours_count)
+        }
+    }
+
+    /// The team most of a newborn cell's live neighbours are in. When no team
+    /// has the most, as when QuadLife's three parents are all in different
+    /// teams, it's the first team none of them are in.
+    fn birth_team(&self, x: usize, y: usize) -> u8 {
+        let mut counts = vec![0; usize::from(self.rule.teams())];
+        for (x, y) in neighbours(&self.rule, self.boundary, (self.width, self.height), x, y) {
+            if self.cell(x, y).alive {
+                counts[usize::from(self.cell(x, y).team)] += 1;
+            }
+        }
+
+        let most = counts.iter().max().copied().unwrap_or(0);
+        let mut teams = 0..self.rule.teams();
+        if counts.iter().filter(|count| **count == most).count() == 1 {
+            teams.find(|team| counts[usize::from(*team)] == most)
+        } else {
+            teams.find(|team| counts[usize::from(*team)] == 0)
+        }
+        .unwrap_or(0)
+    }
+
+    /// A bit for each live neighbour, in reading order from the north-west,
+    /// for isotropic rules.
+    fn configuration(&self, x: usize, y: usize) -> u8 {
+        let mut configuration = 0;
+        let mut bit = 0;
+
+        for y_offset in -1..=1 {
+            for x_offset in -1..=1 {
+                if (x_offset, y_offset) == (0, 0) {
+                    continue;
+                }
+
+                let position = (x as isize + x_offset, y as isize + y_offset);
+                if let Some((x, y)) = self.boundary.resolve(position, (self.width, self.height)) {
+                    if self.cell(x, y).alive {
+                        configuration |= 1 << bit;
+                    }
+                }
+                bit += 1;
+            }
+        }
+
+        configuration
+    }
+
+    /// Updating neighbours as each cell changes costs `radius²` per change, so
+    /// wide neighbourhoods update every cell and then recount in one go.
+    fn simulate_in_bulk(&mut self) {
+        let mut chance = self.chance.take();
+        let mut happens =
+            |chance_happens: fn(&mut Chance) -> bool| chance.as_mut().is_none_or(chance_happens);
+
+        self.generation += 1;
+        for (i, cell) in self.cells.iter_mut().enumerate() {
+            let (x, y) = (i % self.width, i / self.width);
+            let rule = rule_at(&self.regions, self.rule, x, y);
+            if cell.alive {
+                if !(rule.survives(cell.live_neighbours_count) && happens(Chance::survives)) {
+                    cell.alive = false;
+                    self.deaths += 1;
+                    if rule.states() > 2 {
+                        cell.state = 2;
+                    }
+                }
+            } else if cell.state > 1 {
+                cell.state = next_decay(cell.state, rule.states());
+            } else if rule.births(cell.live_neighbours_count) && happens(Chance::births) {
+                cell.alive = true;
+                cell.born = self.generation as u32;
+                self.births += 1;
+            }
+        }
+        self.chance = chance;
+
+        self.recount_neighbours();
+    }
+
+    /// Electron heads (live cells) become tails, tails become conductors, and
+    /// conductors become heads next to one or two heads.
+    fn simulate_wireworld(&mut self) {
+        let mut changes = mem::take(&mut self.changes);
+        for ((x, y), cell) in self.cells_with_coords() {
+            let next = match cell.state() {
+                WIREWORLD_HEAD => WIREWORLD_TAIL,
+                WIREWORLD_TAIL => WIREWORLD_CONDUCTOR,
+                WIREWORLD_CONDUCTOR if (1..=2).contains(&cell.live_neighbours_count) => {
+                    WIREWORLD_HEAD
+                }
+                _ => continue,
+            };
+            changes.push(((x, y), Change::State(next)));
+        }
+        self.generation += 1;
+        self.apply(changes);
+    }
+
+    /// Cells move on to the next state, wrapping back around to zero, when
+    /// enough of their neighbours are in it.
+    fn simulate_cyclic(&mut self) {
+        let mut changes = mem::take(&mut self.changes);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let next = (self.cell(x, y).state() + 1) % self.rule.states();
+                let count = neighbours(&self.rule, self.boundary, (self.width, self.height), x, y)
+                    .filter(|(x, y)| self.cell(*x, *y).state() == next)
+                    .count() as u8;
+
+                if self.rule.births(count) {
+                    changes.push(((x, y), Change::State(next)));
+                }
+            }
+        }
+        self.generation += 1;
+        self.apply(changes);
+    }
+
+    /// Simulates `generations` generations, calling `f` with the world after each one.
+    pub fn run_with_callback(&mut self, generations: usize, mut f: impl FnMut(&World)) {
+        for _ in 0..generations {
+            self.simulate();
+            f(self);
+        }
+    }
+}
+
+fn shortest_offset(offset: usize, size: usize) -> isize {
+    if offset > size / 2 {
+        offset as isize - size as isize
+    } else {
+        offset as isize
+    }
+}
+
+fn rule_at(regions: &[Region], rule: Rule, x: usize, y: usize) -> Rule {
+    regions
+        .iter()
+        .rev()
+        .find(|region| region.contains(x, y))
+        .map_or(rule, |region| region.rule)
+}
+
+/// The coordinates of a cell's neighbours in a shape, in a world of the
+/// given size, leaving out any past a dead edge.
+fn neighbours<'a, N: NeighbourhoodShape>(
+    shape: &'a N,
+    boundary: Boundary,
+    size: (usize, usize),
+    x: usize,
+    y: usize,
+) -> impl Iterator<Item = (usize, usize)> + 'a {
+    let (x_reach, y_reach) = shape.reach();
+    // Only cells near an edge have neighbours the boundary has to find.
+    let interior = inside((x as isize - x_reach, y as isize - y_reach), size)
+        && inside((x as isize + x_reach, y as isize + y_reach), size);
+
+    (-y_reach..=y_reach)
+        .flat_map(move |y_offset| (-x_reach..=x_reach).map(move |x_offset| (x_offset, y_offset)))
+        .filter(move |(x_offset, y_offset)| shape.contains((x, y), *x_offset, *y_offset))
+        .filter_map(move |(x_offset, y_offset)| {
+            let position = (x as isize + x_offset, y as isize + y_offset);
+            if interior {
+                Some((position.0 as usize, position.1 as usize))
+            } else {
+                boundary.resolve(position, size)
+            }
+        })
+}
+
+/// Whether every cell is a neighbour of each of its neighbours as many times
+/// as they are of it, so a change to a cell only changes what its own
+/// neighbours see. Hexagonal and triangular neighbourhoods turn into other
+/// shapes when an edge flips or mirrors them, and triangles point the wrong
+/// way across an edge after an odd number of them.
+pub(crate) fn neighbours_symmetric(
+    neighbourhood: Neighbourhood,
+    boundary: Boundary,
+    (width, height): (usize, usize),
+) -> bool {
+    match neighbourhood {
+        Neighbourhood::Moore | Neighbourhood::VonNeumann => true,
+        Neighbourhood::Hexagonal => matches!(boundary, Boundary::Wrap | Boundary::Dead),
+        Neighbourhood::Triangular => match boundary {
+            Boundary::Dead => true,
+            Boundary::Wrap => width.is_multiple_of(2) && height.is_multiple_of(2),
+            _ => false,
+        },
+    }
+}
+
+/// The cells with the cell at `(x, y)` among their neighbours, once for each
+/// time it's there, for when the neighbourhood isn't symmetric. Any such
+/// cell is in the square around it, through the boundary, so each of those
+/// is checked.
+pub(crate) fn dependents<N: NeighbourhoodShape>(
+    shape: &N,
+    boundary: Boundary,
+    size: (usize, usize),
+    x: usize,
+    y: usize,
+) -> Vec<(usize, usize)> {
+    let (x_reach, y_reach) = shape.reach();
+    let mut around: Vec<_> = (-y_reach..=y_reach)
+        .flat_map(|y_offset| (-x_reach..=x_reach).map(move |x_offset| (x_offset, y_offset)))
+        .filter_map(|(x_offset, y_offset)| {
+            boundary.resolve((x as isize + x_offset, y as isize + y_offset), size)
+        })
+        .collect();
+    around.sort_unstable();
+    around.dedup();
+
+    around
+        .into_iter()
+        .flat_map(|around| {
+            neighbours(shape, boundary, size, around.0, around.1)
+                .filter(move |neighbour| *neighbour == (x, y))
+                .map(move |_| around)
+        })
+        .collect()
+}
+
+/// Whether a position is inside a world of the given size, without going
+/// through the boundary.
+fn inside((x, y): (isize, isize), (width, height): (usize, usize)) -> bool {
+    x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height
+}
+
+/// A fixed, well-mixed key for each cell that isn't dead, from its
+/// position, its state and a live cell's team, so a world's state hash can
+/// be updated a cell at a time (SplitMix64's finalizer). Dead cells add
+/// nothing.
+fn cell_key(x: usize, y: usize, cell: &Cell) -> u64 {
+    // Live cells in the first team leave the position's key as it is, so
+    // two-state worlds hash by their live cells alone.
+    let what = if cell.alive {
+        u64::from(cell.team) << 8
+    } else if cell.state > 1 {
+        u64::from(cell.state)
+    } else {
+        return 0;
+    };
+    let mut key = ((x as u64) << 32 | y as u64)
+        .wrapping_add(0x9e37_79b9_7f4a_7c15)
+        .wrapping_add(what.wrapping_mul(0xd1b5_4a32_d192_ed03));
+    key = (key ^ (key >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    key = (key ^ (key >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    key ^ (key >> 31)
+}
+
+/// The rule for the cell at index `i`, the last region containing it or the
+/// world's. Borrowed rather than copied, as rules aren't small.
+fn rule_by_index<'a>(
+    world_rule: &'a Rule,
+    regions: &'a [Region],
+    position: impl FnOnce() -> (usize, usize),
+) -> &'a Rule {
+    if regions.is_empty() {
+        return world_rule;
+    }
+    let (x, y) = position();
+    regions
+        .iter()
+        .rev()
+        .find(|region| region.contains(x, y))
+        .map_or(world_rule, |region| &region.rule)
+}
+
+/// What a rule that only counts neighbours makes of a cell: a decaying
+/// state is moved on in place, updating `state_hash` with the cell's
+/// `position`, and a birth or death returned to be applied later. `happens`
+/// rolls the dice for chance.
+fn decide_by_count(
+    cell: &mut Cell,
+    rule: &Rule,
+    position: impl FnOnce() -> (usize, usize),
+    state_hash: &mut u64,
+    mut happens: impl FnMut(fn(&mut Chance) -> bool) -> bool,
+) -> Option<Change> {
+    if cell.alive {
+        if !(rule.survives(cell.live_neighbours_count) && happens(Chance::survives)) {
+            let state = if rule.states() > 2 { 2 } else { 0 };
+            return Some(Change::Death(state));
+        }
+    } else if cell.state > 1 {
+        let (x, y) = position();
+        let before = cell_key(x, y, cell);
+        cell.state = next_decay(cell.state, rule.states());
+        *state_hash ^= before ^ cell_key(x, y, cell);
+    } else if rule.births(cell.live_neighbours_count) && happens(Chance::births) {
+        return Some(Change::Birth(None));
+    }
+    None
+}
+
+/// Notes that the cell at index `i` has changed, or gives up noting changes
+/// once there are more than `limit`.
+fn touch(touched: &mut Option<Vec<usize>>, i: usize, limit: usize) {
+    if let Some(indices) = touched {
+        if indices.len() < limit {
+            indices.push(i);
+        } else {
+            *touched = None;
+        }
+    }
+}
+
+/// Moves a dying cell on to its next state, or to dead after the last one.
+fn next_decay(state: u8, states: u8) -> u8 {
+    if state + 1 < states {
+        state + 1
+    } else {
+        0
+    }
+}
+
+impl std::fmt::Debug for World {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        writeln!(f)?;
+        for row in self.cells.chunks(self.width.max(1)) {
+            for cell in row {
+                if cell.alive {
+                    write!(f, "# ")?;
+                } else {
+                    write!(f, "- ")?;
+                }
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_live_neighbours_count() {
+        let mut world = World::new(3, 3);
+
+        assert_eq!(world.cell(1, 1).live_neighbours_count, 0);
+
+        let mut i = 0;
+        for y in 0..3 {
+            for x in 0..3 {
+                if (x, y) == (1, 1) {
+                    continue;
+                }
+
+                i += 1;
+                world.birth_cell(x, y);
+                assert_eq!(world.cell(1, 1).live_neighbours_count, i);
+            }
+        }
+    }
+
+    #[test]
+    fn test_block() {
+        let mut world = World::new(4, 4);
+
+        world.seed_from_string(
+            "- - - -
+             - # # -
+             - # # -
+             - - - -"
+                .to_string(),
+            (0, 0),
+        );
+
+        let old_world = world.clone();
+
+        world.simulate();
+
+        assert_eq!(old_world, world);
+    }
+
+    #[test]
+    fn test_coords_json_round_trip() {
+        let mut world = World::new(5, 5);
+
+        world.seed_from_string(
+            "- - - - -
+             - - - # -
+             - # - # -
+             - - # # -
+             - - - - -"
+                .to_string(),
+            (0, 0),
+        );
+
+        let json = world.to_coords_json();
+        assert_eq!(json, "[[3,1],[1,2],[3,2],[2,3],[3,3]]");
+
+        let imported = World::from_coords_json(5, 5, &json).unwrap();
+        assert_eq!(world, imported);
+    }
+
+    #[test]
+    fn test_from_coords_json_errors() {
+        assert!(World::from_coords_json(5, 5, "[]").is_ok());
+        assert!(World::from_coords_json(5, 5, "[[1,2]").is_err());
+        assert!(World::from_coords_json(5, 5, "[[1,a]]").is_err());
+        assert!(World::from_coords_json(5, 5, "[[5,0]]").is_err());
+    }
+
+    #[test]
+    fn test_invert() {
+        let mut world = World::new(4, 4);
+        world.set_boundary(Boundary::Dead);
+
+        world.seed_from_string(
+            "- - - -
+             - # # -
+             - # - -
+             - - - -"
+                .to_string(),
+            (0, 0),
+        );
+
+        let old_world = world.clone();
+
+        world.invert();
+        assert!(!world.cell(1, 1).alive);
+        assert!(world.cell(0, 0).alive);
+        assert_eq!(world.cell(0, 0).live_neighbours_count, 2);
+        assert_eq!(world.cell(2, 2).live_neighbours_count, 5);
+
+        world.invert();
+        assert_eq!(old_world, world);
+    }
+
+    #[test]
+    fn test_equals_shifted() {
+        let mut world = World::new(20, 20);
+
+        world.seed_from_string(
+            "- - - - -
+             - - - # -
+             - # - # -
+             - - # # -
+             - - - - -"
+                .to_string(),
+            (0, 0),
+        );
+
+        let old_world = world.clone();
+        assert_eq!(old_world.equals_shifted(&world), Some((0, 0)));
+
+        world.simulate();
+        assert_eq!(old_world.equals_shifted(&world), None);
+
+        for _ in 0..3 {
+            world.simulate();
+        }
+        assert_eq!(old_world.equals_shifted(&world), Some((1, 1)));
+        assert_eq!(world.equals_shifted(&old_world), Some((-1, -1)));
+    }
+
+    #[test]
+    fn test_seed_from_string_origin() {
+        let mut world = World::new(4, 4);
+
+        world.seed_from_string("# -\n- #".to_string(), (2, 1));
+
+        assert_eq!(world.live_cells().collect::<Vec<_>>(), vec![(2, 1), (3, 2)]);
+    }
+
+    #[test]
+    fn test_seed_pattern_too_large() {
+        let mut world = World::new(3, 3);
+        let pattern = Pattern::from_cells(vec![(0, 0), (3, 1)]);
+
+        match world.seed_pattern(&pattern) {
+            Err(SeedError::TooLarge {
+                pattern_size,
+                world_size,
+            }) => {
+                assert_eq!(pattern_size, (4, 2));
+                assert_eq!(world_size, (3, 3));
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+        assert_eq!(world.live_cells().count(), 0);
+
+        world.seed_pattern_at(&pattern, 0, 1).unwrap_err();
+        world
+            .seed_pattern_at(&Pattern::from_cells(vec![(0, 0)]), 2, 2)
+            .unwrap();
+        assert_eq!(world.live_cells().collect::<Vec<_>>(), vec![(2, 2)]);
+    }
+
+    #[test]
+    fn test_seed_from_reader() {
+        let mut world = World::new(5, 5);
+        world
+            .seed_from_reader("#Life 1.06\n0 0\n1 1\n".as_bytes())
+            .unwrap();
+
+        assert_eq!(world.live_cells().collect::<Vec<_>>(), vec![(0, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn test_seed_from_file_auto() {
+        let path = std::env::temp_dir().join(format!("gol-{}-glider.rle", std::process::id()));
+        std::fs::write(&path, "x = 3, y = 3\nbo$2bo$3o!\n").unwrap();
+
+        let mut world = World::new(5, 5);
+        world.seed_from_file_auto(&path).unwrap();
+
+        assert_eq!(
+            world.live_cells().collect::<Vec<_>>(),
+            vec![(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]
+        );
+    }
+
+    #[test]
+    fn test_run_with_callback() {
+        let mut world = World::new(4, 4);
+        let mut generations = Vec::new();
+
+        world.run_with_callback(5, |world| generations.push(world.generation()));
+
+        assert_eq!(generations, vec![1, 2, 3, 4, 5]);
+        assert_eq!(world.generation(), 5);
+    }
+
+    #[test]
+    fn test_seed_from_rle() {
+        let mut world = World::new(4, 4);
+
+        world
+            .seed_from_rle(
+                "#N Block
+                 x = 2, y = 2, rule = B3/S23
+                 2o$2o!",
+            )
+            .unwrap();
+
+        assert_eq!(
+            world.live_cells().collect::<Vec<_>>(),
+            vec![(0, 0), (1, 0), (0, 1), (1, 1)]
+        );
+        assert!(world.seed_from_rle("x = 2, y = 2\n2o$2?!").is_err());
+    }
+
+    #[test]
+    fn test_to_rle() {
+        let mut world = World::new(4, 3);
+
+        world.seed_from_string(
+            "- # - -
+             - - # -
+             # # # -"
+                .to_string(),
+            (0, 0),
+        );
+
+        assert_eq!(world.to_rle(), "x = 4, y = 3\nbo$2bo$3o!\n");
+    }
+
+    #[test]
+    fn test_rule() {
+        // A HighLife replicator seed: a 6-neighbour birth only happens under B36/S23.
+        let seed = "- # #
+                    # - #
+                    # # -"
+            .to_string();
+
+        let mut life = World::new(5, 5);
+        life.seed_from_string(seed.clone(), (1, 1));
+        let mut highlife = life.clone();
+        highlife.set_rule("B36/S23".parse().unwrap());
+
+        life.simulate();
+        highlife.simulate();
+
+        assert!(!life.cell(2, 2).alive);
+        assert!(highlife.cell(2, 2).alive);
+        assert_eq!(highlife.rule().to_string(), "B36/S23");
+    }
+
+    #[test]
+    fn test_generations_rule() {
+        let mut world = World::new(5, 5);
+        world.set_rule("B2/S/C4".parse().unwrap());
+        world.seed_from_string("# - #".to_string(), (1, 2));
+
+        // Neither cell survives, so both start dying while the cells between
+        // and around them with two live neighbours are born.
+        world.simulate();
+        assert_eq!(world.cell(1, 2).state(), 2);
+        assert_eq!(world.cell(3, 2).state(), 2);
+        assert_eq!(world.cell(2, 1).state(), 1);
+        assert_eq!(world.cell(2, 3).state(), 1);
+
+        // Dying cells run through the remaining states and can't be born into.
+        world.simulate();
+        assert_eq!(world.cell(1, 2).state(), 3);
+        assert_eq!(world.cell(2, 1).state(), 2);
+
+        world.simulate();
+        assert_eq!(world.cell(1, 2).state(), 0);
+    }
+
+    #[test]
+    fn test_larger_than_life_rule() {
+        let mut world = World::new(5, 5);
+        world.set_boundary(Boundary::Dead);
+        world.set_rule("R2,C0,M0,S3..3,B3..3,NM".parse().unwrap());
+        world.seed_from_string("# - # - #".to_string(), (0, 2));
+
+        assert_eq!(world.cell(2, 0).live_neighbours_count, 3);
+        assert_eq!(world.cell(1, 2).live_neighbours_count, 2);
+
+        world.simulate();
+
+        let expected: Vec<(usize, usize)> = vec![(2, 0), (2, 1), (2, 3), (2, 4)];
+        assert_eq!(world.live_cells().collect::<Vec<_>>(), expected);
+
+        // Incremental updates agree with a full recount.
+        world.toggle_cell(0, 0);
+        let counts: Vec<u8> = world
+            .cells
+            .iter()
+            .map(|c| c.live_neighbours_count)
+            .collect();
+        world.recount_neighbours();
+        let recounted: Vec<u8> = world
+            .cells
+            .iter()
+            .map(|c| c.live_neighbours_count)
+            .collect();
+        assert_eq!(counts, recounted);
+    }
+
+    #[test]
+    fn test_isotropic_rule() {
+        // The cells beside a domino each see it as two adjacent neighbours (2a).
+        let mut world = World::new(6, 5);
+        world.seed_from_string("# #".to_string(), (2, 2));
+        let mut other_world = world.clone();
+
+        world.set_rule("B2a/S".parse().unwrap());
+        world.simulate();
+        let expected: Vec<(usize, usize)> = vec![(2, 1), (3, 1), (2, 3), (3, 3)];
+        assert_eq!(world.live_cells().collect::<Vec<_>>(), expected);
+
+        other_world.set_rule("B2-a/S".parse().unwrap());
+        other_world.simulate();
+        assert_eq!(other_world.live_cells().count(), 0);
+    }
+
+    #[test]
+    fn test_von_neumann_rule() {
+        let mut world = World::new(5, 5);
+        world.set_rule("B1/SV".parse().unwrap());
+        world.seed_from_string("#".to_string(), (2, 2));
+
+        assert_eq!(world.cell(2, 1).live_neighbours_count, 1);
+        assert_eq!(world.cell(1, 1).live_neighbours_count, 0);
+
+        world.simulate();
+
+        let expected: Vec<(usize, usize)> = vec![(2, 1), (1, 2), (3, 2), (2, 3)];
+        assert_eq!(world.live_cells().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_hexagonal_rule() {
+        let mut world = World::new(5, 5);
+        world.set_rule("B1/SH".parse().unwrap());
+        world.seed_from_string("#".to_string(), (2, 2));
+
+        world.simulate();
+
+        let expected: Vec<(usize, usize)> = vec![(1, 1), (2, 1), (1, 2), (3, 2), (2, 3), (3, 3)];
+        assert_eq!(world.live_cells().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_triangular_rule() {
+        let mut world = World::new(7, 4);
+        world.set_rule("B1/SL".parse().unwrap());
+        world.seed_from_string("#".to_string(), (3, 1));
+
+        world.simulate();
+
+        assert_eq!(world.live_cells().count(), 12);
+        assert!(world.cell(3, 2).alive);
+        assert!(!world.cell(3, 1).alive);
+        // The cell points up, so it has five neighbours below and three above.
+        assert!(world.cell(1, 2).alive);
+        assert!(!world.cell(1, 0).alive);
+    }
+
+    #[test]
+    fn test_brians_brain() {
+        let mut world = World::new(6, 6);
+        world.set_rule("briansbrain".parse().unwrap());
+        world.seed_from_string("# #".to_string(), (2, 2));
+
+        // Every live cell dies, and the dying ones block births.
+        world.simulate();
+        assert_eq!(world.cell(2, 2).state(), 2);
+        assert_eq!(world.cell(3, 2).state(), 2);
+        let born: Vec<(usize, usize)> = vec![(2, 1), (3, 1), (2, 3), (3, 3)];
+        assert_eq!(world.live_cells().collect::<Vec<_>>(), born);
+
+        world.simulate();
+        assert_eq!(world.cell(2, 2).state(), 0);
+        assert_eq!(world.cell(2, 1).state(), 2);
+    }
+
+    #[test]
+    fn test_wireworld() {
+        let mut world = World::new(5, 1);
+        world.set_boundary(Boundary::Dead);
+        world.set_rule("wireworld".parse().unwrap());
+        for x in 0..5 {
+            world.set_cell_state(x, 0, WIREWORLD_CONDUCTOR);
+        }
+        world.set_cell_state(0, 0, WIREWORLD_TAIL);
+        world.set_cell_state(1, 0, WIREWORLD_HEAD);
+
+        let states = |world: &World| -> Vec<u8> { world.row(0).iter().map(Cell::state).collect() };
+        assert_eq!(states(&world), vec![2, 1, 3, 3, 3]);
+
+        // The electron moves along the wire, all the way to the end.
+        world.simulate();
+        assert_eq!(states(&world), vec![3, 2, 1, 3, 3]);
+        world.simulate();
+        world.simulate();
+        assert_eq!(states(&world), vec![3, 3, 3, 2, 1]);
+        world.simulate();
+        assert_eq!(states(&world), vec![3, 3, 3, 3, 2]);
+    }
+
+    #[test]
+    fn test_get_set() {
+        let mut world = World::new(4, 3);
+        let mut cell = Cell::new();
+        cell.alive = true;
+        cell.team = 1;
+
+        world.set(3, 1, cell.clone());
+        assert_eq!(world.get(3, 1), Some(&cell));
+        assert_eq!(world.row(1)[3], cell);
+        assert_eq!(world.cell(2, 2).live_neighbours_count, 1);
+        assert_eq!(world.get(4, 1), None);
+        assert_eq!(world.get(0, 3), None);
+
+        world.set(3, 1, Cell::new());
+        assert!(!world.cell(3, 1).alive);
+        assert_eq!(world.cell(2, 2).live_neighbours_count, 0);
+    }
+
+    #[test]
+    fn test_set_alive() {
+        let mut world = World::new(4, 3);
+        world.set_alive(1, 1, true).unwrap();
+        world.set_alive(1, 1, true).unwrap();
+        assert_eq!(world.population(), 1);
+        assert_eq!(world.cell(2, 2).live_neighbours_count, 1);
+
+        world.set_alive(1, 1, false).unwrap();
+        assert_eq!(world.population(), 0);
+        assert_eq!(world.cell(2, 2).live_neighbours_count, 0);
+
+        let error = world.set_alive(4, 0, true).unwrap_err();
+        assert_eq!(
+            error,
+            OutOfBounds {
+                position: (4, 0),
+                size: (4, 3)
+            }
+        );
+        assert_eq!(error.to_string(), "4,0 is outside the 4x3 world");
+    }
+
+    #[test]
+    fn test_age() {
+        let mut world = World::new(6, 6);
+        world.set_boundary(Boundary::Dead);
+        world.seed_from_string(
+            "# # - - - -
+             # # - - - -
+             - - - - - -
+             - - - # # #"
+                .to_string(),
+            (0, 0),
+        );
+
+        for _ in 0..3 {
+            world.simulate();
+        }
+        // The block has survived every generation; the blinker's middle
+        // cell has too, while its ends keep dying and being born again.
+        assert_eq!(world.age(0, 0), Some(3));
+        assert_eq!(world.age(4, 3), Some(3));
+        assert_eq!(world.age(4, 2), Some(0));
+        assert_eq!(world.age(3, 3), Some(0));
+
+        world.set_generation(100);
+        assert_eq!(world.age(4, 3), Some(3));
+
+        world.toggle_cell(0, 0);
+        world.toggle_cell(0, 0);
+        assert_eq!(world.age(0, 0), Some(0));
+    }
+
+    #[test]
+    fn test_population() {
+        let mut world = World::new(8, 8);
+        world.seed_from_string(
+            "- # -
+             - - #
+             # # #"
+                .to_string(),
+            (0, 0),
+        );
+        assert_eq!(world.population(), 5);
+
+        for _ in 0..7 {
+            world.simulate();
+            assert_eq!(world.population(), world.live_cells().count());
+        }
+
+        world.invert();
+        assert_eq!(world.population(), 64 - 5);
+
+        world.set_rule("R2,C0,M0,S3..5,B3..4,NM".parse().unwrap());
+        world.simulate();
+        assert_eq!(world.population(), world.live_cells().count());
+    }
+
+    #[test]
+    fn test_empty_world() {
+        let mut world = World::new(4, 4);
+        world.resize(0, 0, Anchor::TopLeft);
+        world.set_boundary(Boundary::Mirror);
+        world.set_rule("R2,C0,M1,S2..3,B3..3,NM".parse().unwrap());
+        world.simulate();
+        assert_eq!(world.population(), 0);
+
+        let mut world = World::new(0, 5);
+        world.set_boundary(Boundary::Klein);
+        world.simulate();
+        assert_eq!(world.generation(), 1);
+    }
+
+    #[test]
+    fn test_stats() {
+        // Small worlds follow changes, wide ones run packed, and wide
+        // neighbourhoods update in bulk.
+        for (width, rule) in &[(16, "life"), (300, "life"), (16, "R2,C0,M0,S3..5,B3..4,NM")] {
+            let mut world = World::new(*width, 16);
+            world.set_rule(rule.parse().unwrap());
+            world.seed_random();
+            // Edits count until the next generation begins.
+            let changes = |stats: Stats| stats.births + stats.deaths;
+            let before = changes(world.stats());
+            world.toggle_cell(0, 0);
+            assert_eq!(changes(world.stats()), before + 1);
+
+            for _ in 0..5 {
+                let before = world.clone();
+                world.simulate();
+                let diff = before.diff(&world);
+                let births = diff
+                    .iter()
+                    .filter(|change| matches!(change, CellChange::Birth(..)))
+                    .count();
+                let stats = world.stats();
+                assert_eq!(stats.births, births, "{}", rule);
+                assert_eq!(stats.deaths, diff.len() - births, "{}", rule);
+                assert_eq!(stats.population, world.live_cells().count());
+                assert_eq!(stats.generation, world.generation());
+                assert_eq!(stats.state_hash, world.state_hash());
+            }
+        }
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        let mut world = World::new(10, 8);
+        assert_eq!(world.bounding_box(), None);
+
+        world.toggle_cell(4, 2);
+        assert_eq!(world.bounding_box(), Some(((4, 2), (1, 1))));
+
+        world.toggle_cell(1, 6);
+        world.toggle_cell(7, 3);
+        assert_eq!(world.bounding_box(), Some(((1, 2), (7, 5))));
+
+        world.toggle_cell(4, 2);
+        assert_eq!(world.bounding_box(), Some(((1, 3), (7, 4))));
+    }
+
+    #[test]
+    fn test_cells_with_coords() {
+        let mut world = World::new(3, 2);
+        world.set_cell_state(1, 1, 1);
+        world.set_cell_state(2, 0, 2);
+
+        let states: Vec<_> = world
+            .cells_with_coords()
+            .map(|(position, cell)| (position, cell.state()))
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                ((0, 0), 0),
+                ((1, 0), 0),
+                ((2, 0), 2),
+                ((0, 1), 0),
+                ((1, 1), 1),
+                ((2, 1), 0)
+            ]
+        );
+        assert_eq!(world.live_cells().collect::<Vec<_>>(), vec![(1, 1)]);
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut world = World::new(5, 5);
+        world.seed_from_string("- - -\n# # #".to_string(), (1, 1));
+        let before = world.clone();
+        world.simulate();
+
+        assert_eq!(
+            before.diff(&world),
+            vec![
+                CellChange::Birth(2, 1),
+                CellChange::Death(1, 2),
+                CellChange::Death(3, 2),
+                CellChange::Birth(2, 3),
+            ]
+        );
+        assert_eq!(world.diff(&world), vec![]);
+
+        let mut smaller = World::new(2, 2);
+        smaller.toggle_cell(1, 1);
+        assert_eq!(
+            smaller.diff(&World::new(3, 1)),
+            vec![CellChange::Death(1, 1)]
+        );
+    }
+
+    #[test]
+    fn test_changed_cells() {
+        let mut world = World::new(5, 5);
+        world.seed_from_string("- - -\n# # #".to_string(), (1, 1));
+        let before = world.clone();
+        world.simulate();
+        assert_eq!(
+            before.changed_cells(&world),
+            vec![(2, 1), (1, 2), (3, 2), (2, 3)]
+        );
+        assert_eq!(world.changed_cells(&world), vec![]);
+
+        // Decaying cells change state without coming back to life.
+        let mut world = World::new(3, 3);
+        world.set_rule("B3/S/C4".parse().unwrap());
+        world.toggle_cell(1, 1);
+        let before = world.clone();
+        world.simulate();
+        assert_eq!(world.cell(1, 1).state(), 2);
+        assert_eq!(before.changed_cells(&world), vec![(1, 1)]);
+        let before = world.clone();
+        world.simulate();
+        assert_eq!(world.cell(1, 1).state(), 3);
+        assert_eq!(before.changed_cells(&world), vec![(1, 1)]);
+    }
+
+    #[test]
+    fn test_state_hash() {
+        let mut world = World::new(6, 6);
+        assert_eq!(world.state_hash(), 0);
+        world.seed_from_string("# # #".to_string(), (1, 2));
+        let horizontal = world.state_hash();
+
+        world.simulate();
+        assert_ne!(world.state_hash(), horizontal);
+        world.simulate();
+        assert_eq!(world.state_hash(), horizontal);
+
+        // The same cells give the same hash however they got there.
+        let mut other = World::new(6, 6);
+        other.toggle_cell(3, 2);
+        other.seed_from_string("# #".to_string(), (1, 2));
+        assert_eq!(other.state_hash(), horizontal);
+        other.invert();
+        other.invert();
+        assert_eq!(other.state_hash(), horizontal);
+        other.toggle_cell(0, 0);
+        assert_ne!(other.state_hash(), horizontal);
+    }
+
+    #[test]
+    fn test_state_hash_states_and_teams() {
+        // The same live cells, but different dying ones.
+        let mut world = World::new(6, 6);
+        world.set_rule("briansbrain".parse().unwrap());
+        world.toggle_cell(1, 1);
+        let mut other = world.clone();
+        world.set_cell_state(3, 3, 2);
+        assert_ne!(world.state_hash(), other.state_hash());
+        other.set_cell_state(3, 3, 2);
+        assert_eq!(world.state_hash(), other.state_hash());
+
+        // Dying cells decaying in place, and dying and being born, across
+        // parallel bands in wide worlds.
+        for (width, rule) in &[(20, "briansbrain"), (300, "345/2/4"), (20, "wireworld")] {
+            let mut world = World::new(*width, 40);
+            world.set_rule(rule.parse().unwrap());
+            world.seed_random();
+            for _ in 0..6 {
+                world.simulate();
+                let mut recounted = world.clone();
+                recounted.recount_neighbours();
+                assert_eq!(world.state_hash(), recounted.state_hash(), "{}", rule);
+            }
+        }
+
+        // The same live cells in different teams.
+        let mut world = World::new(6, 6);
+        world.set_rule("immigration".parse().unwrap());
+        world.set_cell_team(2, 2, 0);
+        let mut other = world.clone();
+        other.set_cell_team(2, 2, 1);
+        assert_ne!(world.state_hash(), other.state_hash());
+        other.set_cell_team(2, 2, 0);
+        assert_eq!(world.state_hash(), other.state_hash());
+    }
+
+    #[test]
+    fn test_simulate_changed_cells() {
+        let mut world = World::new(60, 40);
+        world.seed_from_string(
+            "- # -
+             - - #
+             # # #"
+                .to_string(),
+            (5, 5),
+        );
+        // A block, which never changes.
+        world.seed_from_string("# #\n# #".to_string(), (40, 30));
+        let mut packed = crate::bitpacked::BitWorld::from_world(&world).unwrap();
+
+        for generation in 0..100 {
+            // Cells changed between generations are picked up too.
+            if generation == 50 {
+                for x in 20..23 {
+                    world.toggle_cell(x, 20);
+                    crate::grid::Grid::set_alive(&mut packed, x, 20, true);
+                }
+            }
+
+            world.simulate();
+            crate::grid::Grid::simulate(&mut packed);
+            let mut expected = crate::grid::Grid::live_cells(&packed);
+            expected.sort_by_key(|&(x, y)| (y, x));
+            assert_eq!(world.live_cells().collect::<Vec<_>>(), expected);
+        }
+        assert!(world.touched.is_some());
+        assert_eq!(world.age(40, 30), Some(100));
+    }
+
+    #[test]
+    fn test_simulate_large_world() {
+        // Tall enough to be split into several bands when simulating in
+        // parallel, with plenty going on across the bands' borders.
+        let mut world = World::new(70, 50);
+        for y in 0..50 {
+            for x in 0..70 {
+                if (x * 7 + y * 13) % 5 == 0 || (x + y) % 11 == 0 {
+                    world.toggle_cell(x, y);
+                }
+            }
+        }
+        let mut packed = crate::bitpacked::BitWorld::from_world(&world).unwrap();
+
+        for _ in 0..20 {
+            world.simulate();
+            crate::grid::Grid::simulate(&mut packed);
+        }
+        let mut expected = crate::grid::Grid::live_cells(&packed);
+        expected.sort_by_key(|&(x, y)| (y, x));
+        assert_eq!(world.live_cells().collect::<Vec<_>>(), expected);
+        assert_eq!(world.population(), expected.len());
+    }
+
+    #[test]
+    fn test_simulate_in_bits_keeps_counts() {
+        let boundaries = [
+            Boundary::Wrap,
+            Boundary::Dead,
+            Boundary::Mirror,
+            Boundary::Klein,
+            Boundary::CrossSurface,
+        ];
+        for boundary in &boundaries {
+            // Wide enough to be moved on in bits.
+            for (width, height) in &[(256, 5), (300, 3)] {
+                let mut world = World::new(*width, *height);
+                world.set_boundary(*boundary);
+                for i in (0..width * height).step_by(3) {
+                    world.toggle_cell(i % width, i / width);
+                }
+
+                for _ in 0..4 {
+                    world.simulate();
+                    let mut recounted = world.clone();
+                    recounted.recount_neighbours();
+                    assert_eq!(world, recounted, "{:?} {}x{}", boundary, width, height);
+                    assert_eq!(world.state_hash(), recounted.state_hash());
+                    assert_eq!(world.population(), recounted.population());
+                }
+            }
+        }
+
+        // A block's cells survive, getting older.
+        let mut world = World::new(256, 4);
+        world.seed_from_string("# #\n# #".to_string(), (100, 1));
+        world.simulate();
+        world.simulate();
+        assert_eq!(world.age(100, 1), Some(2));
+    }
+
+    #[test]
+    fn test_resize() {
+        let mut world = World::new(4, 3);
+        world.toggle_cell(0, 0);
+        world.toggle_cell(3, 2);
+
+        world.resize(6, 5, Anchor::TopLeft);
+        assert_eq!(world.live_cells().collect::<Vec<_>>(), vec![(0, 0), (3, 2)]);
+
+        world.resize(4, 3, Anchor::Centre);
+        assert_eq!(world.live_cells().collect::<Vec<_>>(), vec![(2, 1)]);
+
+        world.resize(6, 6, Anchor::BottomRight);
+        assert_eq!((world.width, world.height), (6, 6));
+        assert_eq!(world.live_cells().collect::<Vec<_>>(), vec![(4, 4)]);
+        assert_eq!(world.cell(3, 3).live_neighbours_count, 1);
+    }
+
+    #[test]
+    fn test_resize_moves_regions() {
+        let mut world = World::new(10, 10);
+        let region = |x, y| Region {
+            x,
+            y,
+            width: 2,
+            height: 2,
+            rule: "B36/S23".parse().unwrap(),
+        };
+        world.add_region(region(0, 0)).unwrap();
+        world.add_region(region(5, 5)).unwrap();
+
+        world.resize(6, 6, Anchor::BottomRight);
+        assert_eq!(world.regions(), &[region(1, 1)]);
+    }
+
+    #[test]
+    fn test_boundaries() {
+        // A blinker across the top left corner.
+        let blinker = |boundary| {
+            let mut world = World::new(5, 5);
+            world.set_boundary(boundary);
+            for x in 0..3 {
+                world.toggle_cell(x, 0);
+            }
+            world.simulate();
+            world.live_cells().collect::<Vec<_>>()
+        };
+
+        assert_eq!(blinker(Boundary::Wrap), vec![(1, 0), (1, 1), (1, 4)]);
+        assert_eq!(blinker(Boundary::Dead), vec![(1, 0), (1, 1)]);
+        assert_eq!(blinker(Boundary::Mirror), vec![(2, 0), (0, 1), (1, 1)]);
+        // Off the top, the blinker comes back at the bottom flipped over.
+        assert_eq!(blinker(Boundary::Klein), vec![(1, 0), (1, 1), (3, 4)]);
+    }
+
+    #[test]
+    fn test_recount_neighbours_at_boundaries() {
+        for boundary in &[
+            Boundary::Wrap,
+            Boundary::Dead,
+            Boundary::Mirror,
+            Boundary::Klein,
+            Boundary::CrossSurface,
+        ] {
+            let mut world = World::new(6, 5);
+            world.set_boundary(*boundary);
+            for (x, y) in &[(0, 0), (5, 0), (0, 4), (2, 2), (5, 3)] {
+                world.toggle_cell(*x, *y);
+            }
+            let counts = |world: &World| {
+                world
+                    .cells
+                    .iter()
+                    .map(|cell| cell.live_neighbours_count)
+                    .collect::<Vec<_>>()
+            };
+
+            let incremental = counts(&world);
+            world.recount_neighbours();
+            assert_eq!(counts(&world), incremental, "{:?}", boundary);
+        }
+    }
+
+    #[test]
+    fn test_counts_at_boundaries_in_every_neighbourhood() {
+        let mut rng = StdRng::seed_from_u64(552);
+        for boundary in &[
+            Boundary::Wrap,
+            Boundary::Dead,
+            Boundary::Mirror,
+            Boundary::Klein,
+            Boundary::CrossSurface,
+        ] {
+            for rule in &[
+                "B2/S34H",
+                "B2/S13L",
+                "B2/S23",
+                "B2/S23V",
+                "R2,C0,M0,S2..3,B3..3,NH",
+            ] {
+                for (width, height) in &[(5, 5), (6, 4), (7, 3), (2, 3), (1, 1)] {
+                    let mut world = World::new(*width, *height);
+                    world.set_boundary(*boundary);
+                    world.set_rule(rule.parse().unwrap());
+                    let rule = world.rule();
+                    let context = format!("{:?} {} {}x{}", boundary, rule, width, height);
+                    let counted = |world: &World| {
+                        world.cells_with_coords().all(|((x, y), cell)| {
+                            cell.live_neighbours_count == world.count_neighbours(&rule, x, y)
+                        })
+                    };
+
+                    for _ in 0..40 {
+                        world.toggle_cell(rng.gen_range(0, *width), rng.gen_range(0, *height));
+                        assert!(counted(&world), "{}", context);
+                    }
+                    for _ in 0..4 {
+                        world.simulate();
+                        assert!(counted(&world), "{}", context);
+                        let mut recounted = world.clone();
+                        recounted.recount_neighbours();
+                        assert_eq!(world, recounted, "{}", context);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_neighbours_inside_and_near_edges() {
+        let rule: Rule = "R2,C0,M0,S2..3,B3..3,NM".parse().unwrap();
+        let size = (7, 6);
+        for boundary in &[Boundary::Wrap, Boundary::Dead, Boundary::Klein] {
+            for y in 0..size.1 {
+                for x in 0..size.0 {
+                    // Every neighbour found the long way round.
+                    let expected: Vec<_> = (-2..=2)
+                        .flat_map(|dy| (-2..=2).map(move |dx| (dx, dy)))
+                        .filter(|offset| *offset != (0, 0))
+                        .filter_map(|(dx, dy)| {
+                            boundary.resolve((x as isize + dx, y as isize + dy), size)
+                        })
+                        .collect();
+                    let found: Vec<_> = neighbours(&rule, *boundary, size, x, y).collect();
+                    assert_eq!(found, expected, "{:?} ({}, {})", boundary, x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_count_neighbours() {
+        // Counts just the cells above and below.
+        struct Column;
+        impl NeighbourhoodShape for Column {
+            fn reach(&self) -> (isize, isize) {
+                (0, 1)
+            }
+
+            fn contains(&self, _: (usize, usize), _: isize, y_offset: isize) -> bool {
+                y_offset != 0
+            }
+        }
+
+        let mut world = World::new(3, 3);
+        for (x, y) in &[(1, 0), (0, 1), (1, 2)] {
+            world.toggle_cell(*x, *y);
+        }
+
+        assert_eq!(world.count_neighbours(&Column, 1, 1), 2);
+        assert_eq!(world.count_neighbours(&Rule::conway(), 1, 1), 3);
+        // Above the top row is the bottom row.
+        assert_eq!(world.count_neighbours(&Column, 1, 0), 1);
+    }
+
+    #[test]
+    fn test_regions() {
+        let mut world = World::new(10, 5);
+        world.add_region("5,0,5,5:seeds".parse().unwrap()).unwrap();
+        for x in &[1, 2, 3, 6, 7, 8] {
+            world.toggle_cell(*x, 2);
+        }
+
+        world.simulate();
+
+        // The blinker on the left turns. The line on the right, under Seeds,
+        // dies, with cells born above and below each of its ends.
+        assert_eq!(world.rule_at(2, 2), Rule::conway());
+        assert_eq!(
+            world.live_cells().collect::<Vec<_>>(),
+            vec![(2, 1), (6, 1), (8, 1), (2, 2), (2, 3), (6, 3), (8, 3)]
+        );
+
+        assert!(world
+            .add_region("0,0,1,1:wireworld".parse().unwrap())
+            .is_err());
+        assert!(world
+            .add_region("0,0,1,1:B2/S13V".parse().unwrap())
+            .is_err());
+        world.set_rule("B2/S13V".parse().unwrap());
+        assert!(world.regions().is_empty());
+    }
+
+    #[test]
+    fn test_cell_rule() {
+        // Cells with an odd number of neighbours are born, and nothing
+        // survives.
+        struct Odd;
+        impl CellRule for Odd {
+            fn next(&self, alive: bool, live_neighbours: u8) -> bool {
+                !alive && live_neighbours % 2 == 1
+            }
+        }
+
+        let mut world = World::new(7, 7);
+        world.toggle_cell(3, 3);
+        world.set_cell_rule(Odd);
+        world.simulate();
+        assert_eq!(world.live_cells().count(), 8);
+
+        world.set_rule(Rule::conway());
+        world.simulate();
+        assert_eq!(world.live_cells().count(), 8);
+    }
+
+    #[test]
+    fn test_chance() {
+        let blinker = |chance| {
+            let mut world = World::new(5, 5);
+            world.set_chance(chance);
+            for x in 1..=3 {
+                world.toggle_cell(x, 2);
+            }
+            world.simulate();
+            world.live_cells().collect::<Vec<_>>()
+        };
+
+        assert_eq!(blinker(Chance::new(0.0, 1.0, None)), vec![(2, 2)]);
+        assert_eq!(blinker(Chance::new(1.0, 0.0, None)), vec![(2, 1), (2, 3)]);
+    }
+
+    #[test]
+    fn test_chance_seed() {
+        let run = || {
+            let mut world = World::new(20, 20);
+            world.set_chance(Chance::new(0.9, 0.9, Some(42)));
+            for (x, y) in &[
+                (5, 5),
+                (6, 5),
+                (7, 5),
+                (7, 4),
+                (6, 3),
+                (10, 10),
+                (11, 10),
+                (12, 10),
+            ] {
+                world.toggle_cell(*x, *y);
+            }
+            world.run_with_callback(10, |_| {});
+            world
+        };
+
+        assert!(run() == run());
+    }
+
+    #[test]
+    fn test_noise() {
+        let mut world = World::new(5, 5);
+        world.set_rule("B/S012345678".parse().unwrap());
+        world.set_chance(Chance::new(1.0, 1.0, None).with_noise(1.0));
+
+        world.simulate();
+
+        assert_eq!(world.live_cells().count(), 25);
+        assert_eq!(world.cell(2, 2).live_neighbours_count, 8);
+    }
+
+    #[test]
+    fn test_immigration() {
+        let mut world = World::new(5, 5);
+        world.set_rule(Rule::immigration());
+        world.set_cell_team(1, 2, 1);
+        world.set_cell_team(2, 2, 0);
+        world.set_cell_team(3, 2, 1);
+
+        world.simulate();
+
+        // The blinker turns, with the newborn cells joining the majority team.
+        assert_eq!(
+            world.live_cells().collect::<Vec<_>>(),
+            vec![(2, 1), (2, 2), (2, 3)]
+        );
+        assert_eq!(world.cell(2, 1).team(), 1);
+        assert_eq!(world.cell(2, 2).team(), 0);
+        assert_eq!(world.cell(2, 3).team(), 1);
+    }
+
+    #[test]
+    fn test_quadlife() {
+        let mut world = World::new(5, 5);
+        world.set_rule(Rule::quadlife());
+        world.set_cell_team(1, 2, 0);
+        world.set_cell_team(2, 2, 1);
+        world.set_cell_team(3, 2, 3);
+
+        world.simulate();
+
+        // Each newborn cell's parents are all in different teams, so it
+        // joins the missing one.
+        assert_eq!(world.cell(2, 1).team(), 2);
+        assert_eq!(world.cell(2, 3).team(), 2);
+        assert_eq!(world.cell(2, 2).team(), 1);
+    }
+
+    #[test]
+    fn test_cyclic() {
+        let mut world = World::new(4, 3);
+        world.set_rule("cyclic/C3".parse().unwrap());
+        world.set_cell_state(1, 1, 1);
+        world.set_cell_state(2, 1, 2);
+        world.set_cell_state(0, 0, 2);
+
+        world.simulate();
+
+        // Zeros next to a one become ones, ones next to a two become twos,
+        // and twos next to a zero wrap around to zero.
+        let states: Vec<Vec<u8>> = (0..world.height)
+            .map(|y| world.row(y).iter().map(Cell::state).collect())
+            .collect();
+        assert_eq!(
+            states,
+            vec![vec![0, 1, 1, 0], vec![1, 2, 0, 0], vec![1, 1, 1, 0]]
+        );
+    }
+
+    #[test]
+    fn test_region_pattern() {
+        let mut world = World::new(4, 3);
+
+        world.seed_from_string(
+            "- # - -
+             - - # -
+             # # # -"
+                .to_string(),
+            (0, 0),
+        );
+
+        let pattern = world.region_pattern(1, 1, 2, 2);
+        assert_eq!((pattern.width, pattern.height), (2, 2));
+        assert_eq!(pattern.cells, vec![(1, 0), (0, 1), (1, 1)]);
+
+        let clipped = world.region_pattern(2, 2, 10, 10);
+        assert_eq!((clipped.width, clipped.height), (2, 1));
+        assert_eq!(clipped.cells, vec![(0, 0)]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let mut world = World::new(4, 3);
+        world.seed_from_string("# # #".to_string(), (0, 1));
+        world.simulate();
+
+        let json = serde_json::to_string(&world).unwrap();
+        assert!(!json.contains("live_neighbours_count"));
+
+        let mut restored: World = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, world);
+        assert_eq!(restored.generation(), 1);
+
+        restored.simulate();
+        world.simulate();
+        assert_eq!(restored, world);
+
+        assert!(serde_json::from_str::<World>(
+            r#"{"cells":[{"alive":true}],"width":2,"height":1,"generation":0}"#
+        )
+        .is_err());
     }
 }
@@ -0,0 +1,447 @@
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+fn write_temp_file(name: &str, contents: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("gol-{}-{}", std::process::id(), name));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+fn gol(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_gol"))
+        .args(args)
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_convert_native_to_rle() {
+    let input = write_temp_file("glider.txt", "- # -\n- - #\n# # #\n");
+
+    let output = gol(&["convert", "--to", "rle", input.to_str().unwrap()]);
+
+    assert_eq!(output, "x = 3, y = 3\nbo$2bo$3o!\n");
+}
+
+#[test]
+fn test_convert_rle_to_native() {
+    let input = write_temp_file("glider.rle", "#N Glider\nx = 3, y = 3\nbo$2bo$3o!\n");
+
+    let output = gol(&[
+        "convert",
+        "--from",
+        "rle",
+        "--to",
+        "native",
+        input.to_str().unwrap(),
+    ]);
+
+    assert_eq!(output, "- # -\n- - #\n# # #\n");
+}
+
+#[test]
+fn test_convert_to_output_file() {
+    let input = write_temp_file("block.txt", "# #\n# #\n");
+    let output = env::temp_dir().join(format!("gol-{}-block.rle", std::process::id()));
+
+    gol(&[
+        "convert",
+        "--to",
+        "rle",
+        input.to_str().unwrap(),
+        output.to_str().unwrap(),
+    ]);
+
+    assert_eq!(
+        fs::read_to_string(output).unwrap(),
+        "x = 2, y = 2\n2o$2o!\n"
+    );
+}
+
+#[test]
+fn test_convert_rle_to_plaintext() {
+    let input = write_temp_file("glider-plaintext.rle", "x = 3, y = 3\nbo$2bo$3o!\n");
+
+    let output = gol(&[
+        "convert",
+        "--from",
+        "rle",
+        "--to",
+        "plaintext",
+        input.to_str().unwrap(),
+    ]);
+
+    assert_eq!(output, ".O.\n..O\nOOO\n");
+}
+
+#[test]
+fn test_convert_native_to_life106_and_back() {
+    let input = write_temp_file("glider-life106.txt", "- # -\n- - #\n# # #\n");
+    let output = env::temp_dir().join(format!("gol-{}-glider.lif", std::process::id()));
+
+    gol(&[
+        "convert",
+        "--to",
+        "life106",
+        input.to_str().unwrap(),
+        output.to_str().unwrap(),
+    ]);
+    assert_eq!(
+        fs::read_to_string(&output).unwrap(),
+        "#Life 1.06\n1 0\n2 1\n0 2\n1 2\n2 2\n"
+    );
+
+    let native = gol(&[
+        "convert",
+        "--from",
+        "life106",
+        "--to",
+        "native",
+        output.to_str().unwrap(),
+    ]);
+    assert_eq!(native, "- # -\n- - #\n# # #\n");
+}
+
+#[test]
+fn test_convert_detects_formats() {
+    let input = write_temp_file("glider-detect.txt", "#Life 1.06\n1 0\n2 1\n0 2\n1 2\n2 2\n");
+    let output = env::temp_dir().join(format!("gol-{}-glider-detect.cells", std::process::id()));
+
+    gol(&["convert", input.to_str().unwrap(), output.to_str().unwrap()]);
+
+    assert_eq!(fs::read_to_string(output).unwrap(), ".O.\n..O\nOOO\n");
+}
+
+#[test]
+fn test_unrecognised_seed_fails() {
+    let input = write_temp_file("garbage.txt", "- # -\nhello\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_gol"))
+        .args(["headless", "--seed", input.to_str().unwrap()])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unexpected character"));
+}
+
+#[test]
+fn test_seed_from_stdin() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_gol"))
+        .args(["headless", "--seed", "-", "--generations", "0"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"x = 3, y = 3\nbo$2bo$3o!\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    let lines: Vec<String> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .take(3)
+        .map(|line| line[..5].to_string())
+        .collect();
+    assert_eq!(lines, vec!["- # -", "- - #", "# # #"]);
+}
+
+#[test]
+fn test_export_ascii_round_trips_as_a_seed() {
+    let input = write_temp_file("export-glider.rle", "x = 3, y = 3\nbo$2bo$3o!\n");
+    let exported = env::temp_dir().join(format!("gol-{}-export.txt", std::process::id()));
+
+    gol(&[
+        "export",
+        "--format",
+        "ascii",
+        "--generations",
+        "4",
+        "--seed",
+        input.to_str().unwrap(),
+        exported.to_str().unwrap(),
+    ]);
+    let output = gol(&[
+        "export",
+        "--format",
+        "rle",
+        "--seed",
+        exported.to_str().unwrap(),
+    ]);
+
+    assert!(output.starts_with("x = 400, y = 300\n$2bo$3bo$b3o!"));
+}
+
+#[test]
+fn test_seed_suggested_world_size() {
+    let input = write_temp_file(
+        "sized.txt",
+        "!Name: Block\n!Size: 4x3\n- - - -\n- # # -\n- # # -\n",
+    );
+
+    let output = gol(&[
+        "headless",
+        "--generations",
+        "1",
+        "--seed",
+        input.to_str().unwrap(),
+    ]);
+
+    assert_eq!(output, "- - - -\n- # # -\n- # # -\n");
+}
+
+#[test]
+fn test_world_grows_to_fit_seed() {
+    let input = write_temp_file("wide.rle", "x = 500, y = 1\n499bo!\n");
+
+    let output = gol(&[
+        "export",
+        "--format",
+        "rle",
+        "--padding",
+        "1",
+        "--seed",
+        input.to_str().unwrap(),
+    ]);
+
+    assert!(output.starts_with("x = 502, y = 300\n$500bo!"));
+}
+
+#[test]
+fn test_seed_offset_and_center() {
+    let input = write_temp_file("offset-block.txt", "!Size: 6x4\n# #\n# #\n");
+
+    let offset = gol(&[
+        "export",
+        "--seed-offset",
+        "3,1",
+        "--seed",
+        input.to_str().unwrap(),
+    ]);
+    assert_eq!(
+        offset,
+        "- - - - - -\n- - - # # -\n- - - # # -\n- - - - - -\n"
+    );
+
+    let centered = gol(&["export", "--center", "--seed", input.to_str().unwrap()]);
+    assert_eq!(
+        centered,
+        "- - - - - -\n- - # # - -\n- - # # - -\n- - - - - -\n"
+    );
+}
+
+#[test]
+fn test_composite_seeds() {
+    let block = write_temp_file("composite-block.txt", "# #\n# #\n");
+    let blinker = write_temp_file("composite-blinker.rle", "x = 3, y = 1\n3o!\n");
+
+    let output = gol(&[
+        "export",
+        "--format",
+        "life106",
+        "--seed",
+        block.to_str().unwrap(),
+        "--seed",
+        &format!("{}@4,2", blinker.to_str().unwrap()),
+    ]);
+
+    assert_eq!(output, "#Life 1.06\n0 0\n1 0\n0 1\n1 1\n4 2\n5 2\n6 2\n");
+}
+
+#[test]
+fn test_place_patterns() {
+    let output = gol(&[
+        "export",
+        "--format",
+        "life106",
+        "--place",
+        "blinker@1,0",
+        "--place",
+        "blinker@0,2,rot90",
+    ]);
+
+    assert_eq!(output, "#Life 1.06\n1 0\n2 0\n3 0\n0 2\n0 3\n0 4\n");
+}
+
+#[test]
+fn test_rule() {
+    let seed = write_temp_file("replicator.txt", "- # #\n# - #\n# # -\n");
+    let args = |rule| {
+        vec![
+            "export",
+            "--seed",
+            seed.to_str().unwrap(),
+            "--seed-offset",
+            "1,1",
+            "--format",
+            "life106",
+            "--generations",
+            "1",
+            "--rule",
+            rule,
+        ]
+    };
+
+    assert!(!gol(&args("B3/S23")).contains("\n2 2\n"));
+    assert!(gol(&args("B36/S23")).contains("\n2 2\n"));
+}
+
+#[test]
+fn test_rule_from_rle_header() {
+    let seed = write_temp_file(
+        "replicator.rle",
+        "x = 3, y = 3, rule = B36/S23\nb2o$obo$2o!\n",
+    );
+    let args = |rule: Option<&str>| {
+        let mut args = vec![
+            "export",
+            "--seed",
+            seed.to_str().unwrap(),
+            "--seed-offset",
+            "1,1",
+            "--format",
+            "life106",
+            "--generations",
+            "1",
+        ];
+        if let Some(rule) = rule {
+            args.extend(&["--rule", rule]);
+        }
+        gol(&args)
+    };
+
+    assert!(args(None).contains("\n2 2\n"));
+    assert!(!args(Some("B3/S23")).contains("\n2 2\n"));
+}
+
+#[test]
+fn test_neighbourhood() {
+    let seed = write_temp_file("dot.txt", "#\n");
+    let output = gol(&[
+        "export",
+        "--seed",
+        seed.to_str().unwrap(),
+        "--seed-offset",
+        "2,2",
+        "--format",
+        "life106",
+        "--generations",
+        "1",
+        "--rule",
+        "B1/S",
+        "--neighbourhood",
+        "vonneumann",
+    ]);
+
+    assert_eq!(output, "#Life 1.06\n2 1\n1 2\n3 2\n2 3\n");
+}
+
+#[test]
+fn test_birth_probability() {
+    let seed = write_temp_file("blinker.txt", "# # #\n");
+    let output = gol(&[
+        "export",
+        "--seed",
+        seed.to_str().unwrap(),
+        "--seed-offset",
+        "1,1",
+        "--format",
+        "life106",
+        "--generations",
+        "1",
+        "--birth-prob",
+        "0",
+    ]);
+
+    assert_eq!(output, "#Life 1.06\n2 1\n");
+}
+
+#[test]
+fn test_boundary() {
+    let seed = write_temp_file("edge_blinker.txt", "# # #\n");
+    let export = |boundary| {
+        gol(&[
+            "export",
+            "--seed",
+            seed.to_str().unwrap(),
+            "--seed-offset",
+            "0,0",
+            "--format",
+            "life106",
+            "--generations",
+            "1",
+            "--boundary",
+            boundary,
+        ])
+    };
+
+    assert_eq!(export("wrap"), "#Life 1.06\n1 0\n1 1\n1 299\n");
+    assert_eq!(export("dead"), "#Life 1.06\n1 0\n1 1\n");
+}
+
+#[test]
+fn test_bench() {
+    let output = gol(&["bench", "--sizes", "8,16", "--generations", "2"]);
+    let lines: Vec<&str> = output.lines().collect();
+
+    assert!(lines[1].starts_with("seed"));
+    assert!(lines[1].contains("bitpacked"));
+    // A row for each seed at each size, for times and then memory.
+    assert_eq!(lines.len(), 2 * (2 + 3 * 2) + 1);
+    assert!(lines[2].starts_with("soup"));
+    assert!(lines[7].starts_with("r-pentomino"));
+    assert!(lines[9].starts_with("Kilobytes"));
+    assert!(lines[11].starts_with("soup"));
+}
+
+#[test]
+fn test_bench_throughput() {
+    let output = gol(&[
+        "bench",
+        "--throughput",
+        "--sizes",
+        "8,16",
+        "--seconds",
+        "0.05",
+        "--rule",
+        "highlife",
+        "--boundary",
+        "dead",
+    ]);
+    let rows: Vec<Vec<&str>> = output
+        .lines()
+        .map(|line| line.split('\t').collect())
+        .collect();
+
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[0][0], "engine");
+    assert_eq!(rows[0].len(), 8);
+    for (row, size) in rows[1..].iter().zip(&["8", "16"]) {
+        assert_eq!(row.len(), 8);
+        assert_eq!(row[0], "dense");
+        assert_eq!(row[2], "dead");
+        assert_eq!(row[3], *size);
+        let generations: f64 = row[5].parse().unwrap();
+        assert!(generations > 0.0);
+        let per_second: f64 = row[6].parse().unwrap();
+        let updates: f64 = row[7].parse().unwrap();
+        let cells: f64 = size.parse::<f64>().unwrap().powi(2);
+        assert!((updates - per_second * cells).abs() <= per_second * cells / 1000.0 + 1.0);
+    }
+}